@@ -0,0 +1,216 @@
+//! Count how often each entry is looked up.
+//!
+//! When a vector is used as a cache of decoded objects, it helps to know
+//! which ones haven't been used in a while so they can be evicted. This is
+//! off by default; once `track_access()` is called, every lookup of a single
+//! entry (`get()`, `get_exact()`, and their `_mut` versions) counts as a hit.
+//! Iterating and range queries don't count, since they're usually not
+//! interested in any one entry.
+//!
+//! Lookups only need `&self`, so the statistics are kept behind a lock. It's
+//! held just long enough to bump a counter.
+//!
+//! The statistics aren't serialized.
+//!
+//! # Example
+//!
+//! ```
+//! use bumpy_vector::BumpyVector;
+//!
+//! let mut v: BumpyVector<&str> = BumpyVector::new(100);
+//! v.track_access();
+//!
+//! v.insert(("a", 0..10).into()).unwrap();
+//! v.insert(("b", 10..20).into()).unwrap();
+//! v.insert(("c", 20..30).into()).unwrap();
+//!
+//! v.get(5);
+//! v.get(25);
+//! v.get(6);
+//!
+//! // "b" was never used, then "c"
+//! let lru: Vec<&str> = v.least_recently_used(2).iter().map(|e| e.entry).collect();
+//! assert_eq!(vec!["b", "c"], lru);
+//!
+//! assert_eq!(2, v.access_stats(0).unwrap().hits);
+//! ```
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use crate::{BumpyEntry, BumpyVector};
+
+/// How an entry has been used.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct AccessStats {
+    /// How many times it's been looked up.
+    pub hits: u64,
+
+    /// When it was last looked up. This is a counter that goes up with every
+    /// lookup, so it's only meaningful compared to other entries.
+    pub last_access: u64,
+}
+
+#[derive(Debug, Default)]
+struct AccessState {
+    /// Goes up by one every lookup.
+    clock: u64,
+
+    /// Statistics for each entry that's been looked up, indexed by where
+    /// it starts.
+    entries: HashMap<usize, AccessStats>,
+}
+
+/// The per-vector state, once tracking is turned on.
+#[derive(Debug, Default)]
+pub(crate) struct AccessTracker {
+    state: Mutex<AccessState>,
+}
+
+impl AccessTracker {
+    fn with<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce(&mut AccessState) -> R
+    {
+        // A panic while bumping a counter can't leave anything inconsistent
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+
+        f(&mut state)
+    }
+
+    /// Called when the entry at `start` is looked up.
+    pub(crate) fn on_access(&self, start: usize) {
+        self.with(|state| {
+            state.clock += 1;
+
+            let clock = state.clock;
+            let stats = state.entries.entry(start).or_default();
+            stats.hits += 1;
+            stats.last_access = clock;
+        });
+    }
+
+    /// Called when the entry at `start` is removed.
+    pub(crate) fn on_remove(&self, start: usize) {
+        self.with(|state| state.entries.remove(&start));
+    }
+
+    /// Called when every entry is removed.
+    pub(crate) fn on_clear(&self) {
+        self.with(|state| state.entries.clear());
+    }
+}
+
+impl Clone for AccessTracker {
+    fn clone(&self) -> Self {
+        self.with(|state| AccessTracker {
+            state: Mutex::new(AccessState {
+                clock: state.clock,
+                entries: state.entries.clone(),
+            }),
+        })
+    }
+}
+
+impl<T> BumpyVector<T> {
+    /// Start counting lookups of each entry.
+    ///
+    /// Calling this when tracking is already on does nothing.
+    pub fn track_access(&mut self) {
+        if self.access.is_none() {
+            self.access = Some(AccessTracker::default());
+        }
+    }
+
+    /// Returns the access statistics for the entry at `index`, if there's an
+    /// entry and access is being tracked.
+    ///
+    /// Checking the statistics doesn't count as an access.
+    pub fn access_stats(&self, index: usize) -> Option<AccessStats> {
+        let start = self.get_entry_start(index)?;
+        let tracker = self.access.as_ref()?;
+
+        Some(tracker.with(|state| state.entries.get(&start).copied().unwrap_or_default()))
+    }
+
+    /// Returns up to `n` entries, starting with the one that was looked up
+    /// longest ago.
+    ///
+    /// Entries that have never been looked up come first, in order. If
+    /// access isn't being tracked, every entry counts as never looked up.
+    pub fn least_recently_used(&self, n: usize) -> Vec<&BumpyEntry<T>> {
+        let last_access: HashMap<usize, u64> = match &self.access {
+            Some(tracker) => tracker.with(|state| {
+                state.entries.iter().map(|(start, stats)| (*start, stats.last_access)).collect()
+            }),
+            None => HashMap::new(),
+        };
+
+        let mut result: Vec<&BumpyEntry<T>> = self.data.values().collect();
+        result.sort_by_key(|e| (last_access.get(&e.range.start).copied().unwrap_or(0), e.range.start));
+        result.truncate(n);
+
+        result
+    }
+
+    /// Count a lookup of the entry at `start`, if we're counting.
+    pub(crate) fn record_access(&self, start: usize) {
+        if let Some(tracker) = &self.access {
+            tracker.on_access(start);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_access() {
+        let mut h: BumpyVector<String> = BumpyVector::new(100);
+        h.insert((String::from("a"), 0..10).into()).unwrap();
+        h.insert((String::from("b"), 10..20).into()).unwrap();
+        h.insert((String::from("c"), 20..30).into()).unwrap();
+
+        // Nothing is counted until it's turned on
+        h.get(5);
+        assert_eq!(None, h.access_stats(5));
+
+        h.track_access();
+        assert_eq!(Some(AccessStats::default()), h.access_stats(5));
+        assert_eq!(None, h.access_stats(50));
+
+        h.get(25);
+        h.get_exact(0);
+        h.get_mut(15);
+        h.get_exact_mut(20);
+        h.get(5);
+
+        // Range queries don't count
+        h.get_range(0..100);
+        h.iter().count();
+
+        assert_eq!(2, h.access_stats(0).unwrap().hits);
+        assert_eq!(1, h.access_stats(10).unwrap().hits);
+        assert_eq!(2, h.access_stats(29).unwrap().hits);
+        assert!(h.access_stats(0).unwrap().last_access > h.access_stats(20).unwrap().last_access);
+
+        let lru: Vec<&str> = h.least_recently_used(10).iter().map(|e| &e.entry[..]).collect();
+        assert_eq!(vec!["b", "c", "a"], lru);
+
+        // Removing an entry forgets about it
+        h.remove(0);
+        h.insert((String::from("d"), 0..10).into()).unwrap();
+        assert_eq!(0, h.access_stats(0).unwrap().hits);
+
+        let lru: Vec<&str> = h.least_recently_used(2).iter().map(|e| &e.entry[..]).collect();
+        assert_eq!(vec!["d", "b"], lru);
+
+        // Clones get their own copy
+        let cloned = h.clone();
+        h.get(0);
+        assert_eq!(1, h.access_stats(0).unwrap().hits);
+        assert_eq!(0, cloned.access_stats(0).unwrap().hits);
+    }
+}
@@ -0,0 +1,124 @@
+//! A builder for `BumpyEntry`, that validates the entry as it's built.
+//!
+//! Constructing a `BumpyEntry` directly can create one that will never
+//! insert - an empty range, say, or one that overflows - and the mistake
+//! doesn't show up until much later. The builder checks as soon as `build()`
+//! is called.
+//!
+//! # Example
+//!
+//! ```
+//! use bumpy_vector::{BumpyEntry, BumpyVector};
+//!
+//! let entry = BumpyEntry::builder().value("hello").at(10).size(5).build().unwrap();
+//! assert_eq!(10..15, entry.range);
+//!
+//! let mut v: BumpyVector<&str> = BumpyVector::new(100);
+//! v.insert(entry).unwrap();
+//!
+//! // A zero-sized entry is caught right away
+//! assert!(BumpyEntry::builder().value("error").at(10).size(0).build().is_err());
+//! ```
+
+use crate::{BumpyEntry, BumpyError, BumpyResult};
+
+/// Builds a `BumpyEntry`; see `BumpyEntry::builder()`.
+#[derive(Debug, Clone)]
+pub struct BumpyEntryBuilder<T> {
+    value: Option<T>,
+    index: Option<usize>,
+    size: Option<usize>,
+}
+
+impl<T> Default for BumpyEntryBuilder<T> {
+    fn default() -> Self {
+        BumpyEntryBuilder {
+            value: None,
+            index: None,
+            size: None,
+        }
+    }
+}
+
+impl<T> BumpyEntryBuilder<T> {
+    /// Set the entry's value.
+    pub fn value(mut self, value: T) -> Self {
+        self.value = Some(value);
+        self
+    }
+
+    /// Set the index the entry starts at.
+    pub fn at(mut self, index: usize) -> Self {
+        self.index = Some(index);
+        self
+    }
+
+    /// Set the entry's size.
+    pub fn size(mut self, size: usize) -> Self {
+        self.size = Some(size);
+        self
+    }
+
+    /// Validate and create the entry.
+    ///
+    /// # Return
+    ///
+    /// Returns an error if the value, index, or size is missing, if the size
+    /// is 0, or if the entry would end past `usize::MAX`.
+    pub fn build(self) -> BumpyResult<BumpyEntry<T>> {
+        let value = match self.value {
+            Some(v) => v,
+            None => return Err(BumpyError::MissingField("value")),
+        };
+
+        let index = match self.index {
+            Some(i) => i,
+            None => return Err(BumpyError::MissingField("index")),
+        };
+
+        let size = match self.size {
+            Some(s) => s,
+            None => return Err(BumpyError::MissingField("size")),
+        };
+
+        if size == 0 {
+            return Err(BumpyError::ZeroSize);
+        }
+
+        BumpyEntry::try_entry(value, index, size)
+    }
+}
+
+impl<T> BumpyEntry<T> {
+    /// Start building an entry; see `BumpyEntryBuilder`.
+    pub fn builder() -> BumpyEntryBuilder<T> {
+        BumpyEntryBuilder::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_builder() {
+        let e = BumpyEntry::builder().value("a").at(0).size(1).build().unwrap();
+        assert_eq!("a", e.entry);
+        assert_eq!(0..1, e.range);
+
+        // Order doesn't matter, and the last one wins
+        let e = BumpyEntry::builder().size(1).at(5).value("a").size(3).build().unwrap();
+        assert_eq!(5..8, e.range);
+
+        // Everything is required
+        assert!(BumpyEntry::<&str>::builder().at(0).size(1).build().is_err());
+        assert!(BumpyEntry::builder().value("a").size(1).build().is_err());
+        assert!(BumpyEntry::builder().value("a").at(0).build().is_err());
+
+        // And has to make sense
+        assert!(BumpyEntry::builder().value("a").at(0).size(0).build().is_err());
+        assert!(BumpyEntry::builder().value("a").at(usize::MAX).size(1).build().is_err());
+        assert!(BumpyEntry::builder().value("a").at(usize::MAX - 1).size(1).build().is_ok());
+    }
+}
@@ -0,0 +1,705 @@
+//! Working with a lot of entries at once.
+//!
+//! Importing annotations from somewhere else usually means inserting
+//! hundreds or thousands of entries, some of which won't fit. Rather than
+//! finding out one error at a time, `plan_bulk_insert()` works out what would
+//! happen up front - without changing anything - so an import can be
+//! previewed, and the user can decide what to do about the conflicts.
+//! Once they're sorted out, `insert_many()` inserts the whole batch or, if
+//! anything still doesn't fit, none of it.
+//!
+//! A big scripted reorganization is harder: halfway through, entries can be
+//! on top of each other. `bulk_edit()` starts a batch of inserts, removes,
+//! and moves that aren't checked for overlaps until `finish()`, which either
+//! keeps all of them or puts everything back how it was.
+
+use std::collections::BTreeMap;
+use std::ops::Range;
+
+use crate::{ranges, BulkInsertError, BumpyEntry, BumpyError, BumpyResult, BumpyVector, InsertError};
+use crate::types::TypeTag;
+
+/// Why an entry in a bulk insert wouldn't fit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Conflict {
+    /// The vector is read-only, so nothing can be inserted.
+    ReadOnly,
+
+    /// The entry's range is empty.
+    Empty,
+
+    /// The entry goes past `max_size`.
+    OutOfBounds,
+
+    /// The entry touches a locked range (see `lock_range()`).
+    Locked,
+
+    /// The entry overlaps an entry that's already in the vector, with this
+    /// range.
+    Existing(Range<usize>),
+
+    /// The entry overlaps an earlier entry in the same batch, at this
+    /// position in the list.
+    Batch(usize),
+}
+
+/// What would happen if a list of entries were inserted in order.
+///
+/// See `BumpyVector::plan_bulk_insert()`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BulkPlan {
+    /// The positions (in the list) of the entries that would be inserted.
+    pub accepted: Vec<usize>,
+
+    /// The positions (in the list) of the entries that wouldn't, and why.
+    pub conflicts: Vec<(usize, Conflict)>,
+
+    /// How many indexes would be covered by entries afterwards, including
+    /// the ones that are already there.
+    pub coverage: usize,
+}
+
+impl<T> BumpyVector<T> {
+    /// Work out what would happen if `entries` were inserted one at a time,
+    /// in order, without actually changing anything.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bumpy_vector::{BumpyEntry, BumpyVector};
+    /// use bumpy_vector::bulk::Conflict;
+    ///
+    /// let mut v: BumpyVector<&str> = BumpyVector::new(100);
+    /// v.insert(("existing", 0..10).into()).unwrap();
+    ///
+    /// let entries: Vec<BumpyEntry<&str>> = vec![
+    ///     ("a", 10..20).into(),
+    ///     ("b", 5..15).into(),
+    ///     ("c", 15..25).into(),
+    ///     ("d", 90..110).into(),
+    /// ];
+    ///
+    /// let plan = v.plan_bulk_insert(&entries);
+    /// assert_eq!(vec![0], plan.accepted);
+    /// assert_eq!(vec![
+    ///     (1, Conflict::Existing(0..10)),
+    ///     (2, Conflict::Batch(0)),
+    ///     (3, Conflict::OutOfBounds),
+    /// ], plan.conflicts);
+    /// assert_eq!(20, plan.coverage);
+    ///
+    /// // Nothing has changed
+    /// assert_eq!(1, v.len());
+    /// ```
+    pub fn plan_bulk_insert(&self, entries: &[BumpyEntry<T>]) -> BulkPlan {
+        let mut accepted = Vec::new();
+        let mut conflicts = Vec::new();
+        let mut coverage = self.occupied_size();
+
+        // The entries we've accepted so far: start -> (end, position)
+        let mut planned: BTreeMap<usize, (usize, usize)> = BTreeMap::new();
+
+        for (position, e) in entries.iter().enumerate() {
+            if self.read_only {
+                conflicts.push((position, Conflict::ReadOnly));
+                continue;
+            }
+
+            if e.range.is_empty() {
+                conflicts.push((position, Conflict::Empty));
+                continue;
+            }
+
+            if e.range.end > self.max_size {
+                conflicts.push((position, Conflict::OutOfBounds));
+                continue;
+            }
+
+            if self.is_locked(&e.range) {
+                conflicts.push((position, Conflict::Locked));
+                continue;
+            }
+
+            if let Some(existing) = self.get_range(e.range.clone()).first() {
+                conflicts.push((position, Conflict::Existing(existing.range.clone())));
+                continue;
+            }
+
+            // The only planned entry that can overlap is the last one that
+            // starts before this one ends
+            if let Some((_, (end, other))) = planned.range(..e.range.end).next_back() {
+                if *end > e.range.start {
+                    conflicts.push((position, Conflict::Batch(*other)));
+                    continue;
+                }
+            }
+
+            planned.insert(e.range.start, (e.range.end, position));
+            accepted.push(position);
+            coverage += e.range.len();
+        }
+
+        BulkPlan {
+            accepted,
+            conflicts,
+            coverage,
+        }
+    }
+
+    /// Insert every entry in `entries`, or none of them.
+    ///
+    /// The entries are checked against the vector and against each other
+    /// before anything is inserted, so a failure can't leave the vector
+    /// half-populated.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bumpy_vector::{BumpyError, BumpyVector};
+    ///
+    /// let mut v: BumpyVector<&str> = BumpyVector::new(100);
+    ///
+    /// let e = v.insert_many(vec![
+    ///     ("a", 0..10).into(),
+    ///     ("b", 5..15).into(),
+    /// ]).unwrap_err();
+    /// assert!(matches!(e.rejected[0], (1, BumpyError::Overlap)));
+    /// assert_eq!(0, v.len());
+    ///
+    /// // Fix it up and try again
+    /// let mut entries = e.entries;
+    /// entries[1].range = 10..15;
+    /// v.insert_many(entries).unwrap();
+    /// assert_eq!(2, v.len());
+    /// ```
+    ///
+    /// # Return
+    ///
+    /// Returns a `BulkInsertError`, with every entry and the reason each
+    /// rejected one wouldn't fit, if any of them can't be inserted.
+    pub fn insert_many(&mut self, entries: Vec<BumpyEntry<T>>) -> Result<(), BulkInsertError<T>> {
+        let mut rejected = Vec::new();
+
+        // The entries we've accepted so far: start -> end
+        let mut planned: BTreeMap<usize, usize> = BTreeMap::new();
+
+        for (position, e) in entries.iter().enumerate() {
+            if let Err(reason) = self.check_insert(e) {
+                rejected.push((position, reason));
+                continue;
+            }
+
+            if let Some((_, end)) = planned.range(..e.range.end).next_back() {
+                if *end > e.range.start {
+                    rejected.push((position, BumpyError::Overlap));
+                    continue;
+                }
+            }
+
+            planned.insert(e.range.start, e.range.end);
+        }
+
+        if !rejected.is_empty() {
+            return Err(BulkInsertError { entries, rejected });
+        }
+
+        for e in entries {
+            self.insert_unchecked(e);
+        }
+
+        Ok(())
+    }
+
+    /// Insert every entry from an iterator that fits, and hand back the ones
+    /// that don't.
+    ///
+    /// This is `extend()`, except that it reports what it skipped. Unlike
+    /// `insert_many()`, every entry that fits is inserted, even if others
+    /// don't.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bumpy_vector::{BumpyError, BumpyVector};
+    ///
+    /// let mut v: BumpyVector<&str> = BumpyVector::new(10);
+    ///
+    /// let errors = v.try_extend(vec![("a", 0..4).into(), ("b", 2..6).into(), ("c", 6..10).into()]).unwrap_err();
+    /// assert_eq!(1, errors.len());
+    /// assert_eq!("b", errors[0].entry.entry);
+    /// assert!(matches!(errors[0].reason, BumpyError::Overlap));
+    ///
+    /// assert_eq!(2, v.len());
+    /// ```
+    ///
+    /// # Return
+    ///
+    /// Returns the entries that couldn't be inserted, in order, along with
+    /// why, if there were any.
+    pub fn try_extend<I>(&mut self, iter: I) -> Result<(), Vec<InsertError<T>>>
+    where
+        I: IntoIterator<Item=BumpyEntry<T>>
+    {
+        let errors: Vec<InsertError<T>> = iter.into_iter().filter_map(|entry| self.insert(entry).err()).collect();
+
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+
+        Ok(())
+    }
+}
+
+/// An entry that's been taken out of the vector during a `BulkEdit`, or is
+/// waiting to go in.
+#[derive(Debug)]
+struct Staged<T> {
+    entry: BumpyEntry<T>,
+
+    /// Where the entry was before the edit, if it was in the vector.
+    origin: Option<Range<usize>>,
+
+    tag: Option<TypeTag>,
+    pinned: bool,
+
+    /// When the entry expires, if it's temporary.
+    expires: Option<u64>,
+}
+
+/// A batch of changes that are only checked for overlaps at the end; see
+/// `BumpyVector::bulk_edit()`.
+///
+/// Removes and moves take entries out of the vector straight away, while
+/// inserts and the new homes of moved entries wait for `finish()`. Dropping
+/// the edit without finishing it puts everything back. Temporary entries
+/// stay temporary, and nothing expires until the edit is finished.
+#[derive(Debug)]
+pub struct BulkEdit<'a, T> {
+    vector: &'a mut BumpyVector<T>,
+
+    /// Entries that have been removed.
+    removed: Vec<Staged<T>>,
+
+    /// Entries that will be inserted, in order.
+    pending: Vec<Staged<T>>,
+}
+
+impl<'a, T> BulkEdit<'a, T> {
+    /// Insert an entry when the edit finishes.
+    ///
+    /// Nothing is checked until then.
+    pub fn insert(&mut self, entry: BumpyEntry<T>) {
+        self.pending.push(Staged { entry, origin: None, tag: None, pinned: false, expires: None });
+    }
+
+    /// Take an entry that's in the vector out of it, if the edit finishes.
+    ///
+    /// This only sees entries that are still in the vector, not ones waiting
+    /// to be inserted.
+    ///
+    /// # Return
+    ///
+    /// It's `NotFound` if there's no entry at `index`, `Locked` if it's
+    /// locked, and `ReadOnly` if the vector is read-only.
+    pub fn remove(&mut self, index: usize) -> BumpyResult<()> {
+        let staged = self.take(index)?;
+        self.removed.push(staged);
+
+        Ok(())
+    }
+
+    /// Move the entry at `index` so it starts at `to`.
+    ///
+    /// The entry leaves its old place straight away, so other entries can be
+    /// moved there; where it lands isn't checked until the edit finishes. It
+    /// keeps its type tag. Like `remove()`, this only sees entries that are
+    /// still in the vector, so each entry can only be moved once.
+    ///
+    /// # Return
+    ///
+    /// As well as the errors from `remove()`, it's `Pinned` if the entry is
+    /// pinned, and `Overflow` if it would end past `usize::MAX`.
+    pub fn relocate(&mut self, index: usize, to: usize) -> BumpyResult<()> {
+        let start = self.vector.get_entry_start(index).ok_or(BumpyError::NotFound)?;
+        if self.vector.pins.contains(&start) {
+            return Err(BumpyError::Pinned);
+        }
+
+        let size = self.vector.data[&start].range.len();
+        let end = to.checked_add(size).ok_or(BumpyError::Overflow)?;
+
+        let mut staged = self.take(index)?;
+        staged.entry.range = to..end;
+        self.pending.push(staged);
+
+        Ok(())
+    }
+
+    /// Check everything, and keep it all if it fits.
+    ///
+    /// # Return
+    ///
+    /// If any entry waiting to go in doesn't fit - it overlaps something,
+    /// including another waiting entry, or it's empty, out of bounds, or on a
+    /// locked range - the vector goes back to how it was before the edit and
+    /// the reason is returned. The entries passed to `insert()` are dropped.
+    pub fn finish(mut self) -> BumpyResult<()> {
+        let mut result = self.pending.iter().try_for_each(|s| self.vector.check_insert(&s.entry));
+
+        if result.is_ok() {
+            let mut destinations: Vec<&Range<usize>> = self.pending.iter().map(|s| &s.entry.range).collect();
+            destinations.sort_by_key(|r| r.start);
+
+            if destinations.windows(2).any(|pair| ranges::overlaps(pair[0], pair[1])) {
+                result = Err(BumpyError::Overlap);
+            }
+        }
+
+        if result.is_err() {
+            self.rollback();
+
+            return result;
+        }
+
+        // Every edit counts as a change for temporary entries, but only now
+        // that they're all kept
+        let mut changed: Vec<usize> = self.removed.drain(..).filter_map(|s| s.origin).map(|r| r.start).collect();
+        for s in std::mem::take(&mut self.pending) {
+            changed.push(s.entry.range.start);
+            self.vector.restore(s.entry, s.tag, s.pinned, s.expires);
+        }
+
+        for start in changed {
+            self.vector.expire_temporary(start);
+        }
+
+        Ok(())
+    }
+
+    /// Take the entry at `index` out of the vector.
+    fn take(&mut self, index: usize) -> BumpyResult<Staged<T>> {
+        if self.vector.read_only {
+            return Err(BumpyError::ReadOnly);
+        }
+
+        let start = self.vector.get_entry_start(index).ok_or(BumpyError::NotFound)?;
+        if self.vector.is_locked(&self.vector.data[&start].range) {
+            return Err(BumpyError::Locked);
+        }
+
+        let tag = self.vector.types.get(start);
+        let pinned = self.vector.pins.contains(&start);
+        let expires = self.vector.temporary.expiry(start);
+
+        // Nothing expires until the edit is finished
+        let entry = self.vector.unlink(start).ok_or(BumpyError::NotFound)?;
+        let origin = Some(entry.range.clone());
+
+        Ok(Staged { entry, origin, tag, pinned, expires })
+    }
+
+    /// Put back everything that was taken out, and forget everything else.
+    fn rollback(&mut self) {
+        let staged = self.removed.drain(..).chain(self.pending.drain(..));
+
+        // Nothing else has been inserted, so these all fit
+        for mut s in staged.collect::<Vec<_>>() {
+            if let Some(origin) = s.origin {
+                s.entry.range = origin;
+                self.vector.restore(s.entry, s.tag, s.pinned, s.expires);
+            }
+        }
+    }
+}
+
+impl<'a, T> Drop for BulkEdit<'a, T> {
+    fn drop(&mut self) {
+        self.rollback();
+    }
+}
+
+impl<T> BumpyVector<T> {
+    /// Start a batch of changes that aren't checked for overlaps until
+    /// they're finished.
+    ///
+    /// Checking every step of a big reorganization is slow, and often the
+    /// steps in between don't fit anyway - moving a run of entries along by
+    /// one lands each of them on its neighbour. Instead, the whole batch is
+    /// checked once by `BulkEdit::finish()`, which keeps all of it or none of
+    /// it. Entries that are taken out and put back lose their history, like
+    /// they would if they were removed and inserted again.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bumpy_vector::{BumpyError, BumpyVector};
+    ///
+    /// let mut v: BumpyVector<&str> = BumpyVector::new(100);
+    /// v.insert(("a", 0..10).into()).unwrap();
+    /// v.insert(("b", 10..20).into()).unwrap();
+    /// v.insert(("c", 30..40).into()).unwrap();
+    ///
+    /// // Swap "a" and "b", and replace "c"
+    /// let mut edit = v.bulk_edit();
+    /// edit.relocate(0, 10).unwrap();
+    /// edit.relocate(15, 0).unwrap();
+    /// edit.remove(30).unwrap();
+    /// edit.insert(("d", 20..40).into());
+    /// edit.finish().unwrap();
+    ///
+    /// assert_eq!("b", v.get(0).unwrap().entry);
+    /// assert_eq!("d", v.get(30).unwrap().entry);
+    ///
+    /// // If anything doesn't fit, nothing changes
+    /// let mut edit = v.bulk_edit();
+    /// edit.remove(0).unwrap();
+    /// edit.insert(("e", 15..25).into());
+    /// assert!(matches!(edit.finish(), Err(BumpyError::Overlap)));
+    ///
+    /// assert_eq!(3, v.len());
+    /// assert_eq!("b", v.get(0).unwrap().entry);
+    /// ```
+    pub fn bulk_edit(&mut self) -> BulkEdit<'_, T> {
+        BulkEdit {
+            vector: self,
+            removed: Vec::new(),
+            pending: Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_plan_bulk_insert() {
+        let mut h: BumpyVector<&str> = BumpyVector::new(10);
+        h.insert(("a", 1..3).into()).unwrap();
+
+        let entries: Vec<BumpyEntry<&str>> = vec![
+            ("b", 6..9).into(),
+            ("c", 3..4).into(),
+            ("d", 2..5).into(),
+            ("e", 8..10).into(),
+            ("f", 4..6).into(),
+            ("g", 5..5).into(),
+            ("h", 0..1).into(),
+            ("i", 9..11).into(),
+            ("j", 4..7).into(),
+        ];
+
+        let plan = h.plan_bulk_insert(&entries);
+        assert_eq!(vec![0, 1, 4, 6], plan.accepted);
+        assert_eq!(vec![
+            (2, Conflict::Existing(1..3)),
+            (3, Conflict::Batch(0)),
+            (5, Conflict::Empty),
+            (7, Conflict::OutOfBounds),
+            (8, Conflict::Batch(0)),
+        ], plan.conflicts);
+        assert_eq!(9, plan.coverage);
+
+        // Planning nothing is easy
+        let plan = h.plan_bulk_insert(&[]);
+        assert_eq!(0, plan.accepted.len());
+        assert_eq!(0, plan.conflicts.len());
+        assert_eq!(2, plan.coverage);
+    }
+
+    #[test]
+    fn test_plan_matches_insert_many() {
+        let mut h: BumpyVector<&str> = BumpyVector::new(10);
+        h.insert(("a", 1..3).into()).unwrap();
+        h.lock_range(6..7);
+
+        let entries = || -> Vec<BumpyEntry<&str>> {
+            vec![("b", 3..5).into(), ("c", 5..7).into(), ("d", 7..8).into(), ("e", 0..2).into()]
+        };
+
+        // The plan rejects the same entries, for the same reasons
+        let plan = h.plan_bulk_insert(&entries());
+        assert_eq!(vec![0, 2], plan.accepted);
+        assert_eq!(vec![(1, Conflict::Locked), (3, Conflict::Existing(1..3))], plan.conflicts);
+
+        let e = h.insert_many(entries()).unwrap_err();
+        assert_eq!(vec![1, 3], e.rejected.iter().map(|(position, _)| *position).collect::<Vec<_>>());
+        assert!(matches!(e.rejected[0].1, BumpyError::Locked));
+
+        // Nothing goes in when it's read-only
+        h.set_read_only(true);
+        let plan = h.plan_bulk_insert(&entries());
+        assert_eq!(0, plan.accepted.len());
+        assert!(plan.conflicts.iter().all(|(_, c)| *c == Conflict::ReadOnly));
+        assert_eq!(4, plan.conflicts.len());
+        assert_eq!(2, plan.coverage);
+    }
+
+    #[test]
+    fn test_insert_many() {
+        let mut h: BumpyVector<&str> = BumpyVector::new(10);
+        h.insert(("a", 1..3).into()).unwrap();
+        h.lock_range(9..10);
+
+        let e = h.insert_many(vec![
+            ("b", 6..9).into(),
+            ("c", 3..4).into(),
+            ("d", 2..5).into(),
+            ("e", 8..10).into(),
+            ("f", 4..4).into(),
+            ("g", 9..11).into(),
+        ]).unwrap_err();
+
+        assert_eq!(6, e.entries.len());
+        assert_eq!(vec![2, 3, 4, 5], e.rejected.iter().map(|(position, _)| *position).collect::<Vec<_>>());
+        assert!(matches!(e.rejected[0].1, BumpyError::Overlap));
+        assert!(matches!(e.rejected[1].1, BumpyError::Locked));
+        assert!(matches!(e.rejected[2].1, BumpyError::ZeroSize));
+        assert!(matches!(e.rejected[3].1, BumpyError::OutOfBounds));
+        assert_eq!("4 of 6 entries couldn't be inserted", e.to_string());
+
+        // Nothing was inserted
+        assert_eq!(1, h.len());
+
+        // Entries in the same batch can't overlap
+        let e = h.insert_many(vec![("b", 6..9).into(), ("c", 4..7).into()]).unwrap_err();
+        assert!(matches!(BumpyError::from(e), BumpyError::Overlap));
+        assert_eq!(1, h.len());
+
+        h.insert_many(vec![("b", 6..9).into(), ("c", 3..6).into()]).unwrap();
+        assert_eq!(vec!["a", "c", "b"], h.iter().map(|e| e.entry).collect::<Vec<_>>());
+
+        // Nothing is allowed when it's read-only
+        h.set_read_only(true);
+        let e = h.insert_many(vec![("h", 0..1).into()]).unwrap_err();
+        assert!(matches!(e.rejected[0], (0, BumpyError::ReadOnly)));
+        h.set_read_only(false);
+
+        h.insert_many(vec![]).unwrap();
+        assert_eq!(3, h.len());
+    }
+
+    #[test]
+    fn test_extend() {
+        let mut h: BumpyVector<&str> = BumpyVector::new(10);
+        h.insert(("a", 1..3).into()).unwrap();
+
+        // From a parser, say
+        let parsed = [("b", 3..4), ("c", 2..5), ("d", 8..11), ("e", 5..8)];
+        h.extend(parsed.iter().cloned().map(BumpyEntry::from));
+        assert_eq!(vec!["a", "b", "e"], h.iter().map(|e| e.entry).collect::<Vec<_>>());
+
+        let errors = h.try_extend(vec![("f", 0..1).into(), ("g", 0..1).into(), ("h", 9..10).into(), ("i", 9..12).into()]).unwrap_err();
+        assert_eq!(vec!["g", "i"], errors.iter().map(|e| e.entry.entry).collect::<Vec<_>>());
+        assert!(matches!(errors[0].reason, BumpyError::Overlap));
+        assert!(matches!(errors[1].reason, BumpyError::OutOfBounds));
+        assert_eq!(5, h.len());
+
+        assert!(h.try_extend(vec![("j", 8..9).into()]).is_ok());
+        assert!(h.try_extend(vec![]).is_ok());
+        assert_eq!(6, h.len());
+    }
+
+    #[test]
+    fn test_bulk_edit() {
+        let mut h: BumpyVector<&str> = BumpyVector::new(20);
+        h.insert(("a", 0..2).into()).unwrap();
+        h.insert(("b", 2..4).into()).unwrap();
+        h.insert_typed(("c", 4..6).into(), 7).unwrap();
+        h.insert(("d", 10..12).into()).unwrap();
+        h.insert(("e", 18..20).into()).unwrap();
+        h.pin(10);
+        h.lock_range(19..20);
+
+        let layout = |h: &BumpyVector<&'static str>| -> Vec<(&'static str, Range<usize>)> {
+            h.iter().map(|e| (e.entry, e.range.clone())).collect()
+        };
+
+        // Errors from each step come straight back
+        let mut edit = h.bulk_edit();
+        assert!(matches!(edit.remove(8), Err(BumpyError::NotFound)));
+        assert!(matches!(edit.remove(19), Err(BumpyError::Locked)));
+        assert!(matches!(edit.relocate(10, 14), Err(BumpyError::Pinned)));
+        assert!(matches!(edit.relocate(0, usize::MAX), Err(BumpyError::Overflow)));
+
+        // Shift a run along into itself, one step at a time
+        edit.relocate(0, 1).unwrap();
+        edit.relocate(2, 3).unwrap();
+        edit.relocate(4, 5).unwrap();
+        assert!(matches!(edit.relocate(1, 6), Err(BumpyError::NotFound)));
+        edit.remove(11).unwrap();
+        edit.insert(("f", 12..15).into());
+        edit.finish().unwrap();
+
+        assert_eq!(vec![("a", 1..3), ("b", 3..5), ("c", 5..7), ("f", 12..15), ("e", 18..20)], layout(&h));
+        assert_eq!(Some(7), h.type_of(5));
+
+        // Anything that doesn't fit puts it all back
+        let before = layout(&h);
+        for bad in [("g", 6..8), ("g", 11..12), ("g", 19..20), ("g", 20..21), ("g", 8..8)] {
+            let mut edit = h.bulk_edit();
+            edit.relocate(1, 10).unwrap();
+            edit.remove(13).unwrap();
+            edit.insert(bad.clone().into());
+            assert!(edit.finish().is_err(), "{:?}", bad);
+            assert_eq!(before, layout(&h));
+        }
+
+        // Including moved entries landing on each other
+        let mut edit = h.bulk_edit();
+        edit.relocate(1, 8).unwrap();
+        edit.relocate(3, 9).unwrap();
+        assert!(matches!(edit.finish(), Err(BumpyError::Overlap)));
+        assert_eq!(before, layout(&h));
+        assert_eq!(Some(7), h.type_of(5));
+
+        // Or dropping it without finishing
+        h.pin(12);
+        {
+            let mut edit = h.bulk_edit();
+            edit.remove(12).unwrap();
+            edit.remove(1).unwrap();
+        }
+        assert_eq!(before, layout(&h));
+        assert!(h.is_pinned(12));
+
+        h.set_read_only(true);
+        assert!(matches!(h.bulk_edit().remove(0), Err(BumpyError::ReadOnly)));
+        let mut edit = h.bulk_edit();
+        edit.insert(("g", 8..9).into());
+        assert!(matches!(edit.finish(), Err(BumpyError::ReadOnly)));
+    }
+
+    #[test]
+    fn test_bulk_edit_temporary() {
+        let mut h: BumpyVector<&str> = BumpyVector::new(20);
+        h.insert(("a", 0..2).into()).unwrap();
+        h.insert_temporary(("b", 4..6).into(), 2).unwrap();
+        h.insert_temporary(("c", 8..10).into(), 100).unwrap();
+
+        // A failed edit leaves temporary entries alone, including staged ones
+        let mut edit = h.bulk_edit();
+        edit.remove(0).unwrap();
+        edit.relocate(8, 12).unwrap();
+        edit.insert(("error", 12..13).into());
+        assert!(edit.finish().is_err());
+
+        assert_eq!(vec!["a", "b", "c"], h.iter().map(|e| e.entry).collect::<Vec<_>>());
+        assert!(h.is_temporary(4));
+        assert!(h.is_temporary(8));
+
+        // Dropping one does too
+        h.bulk_edit().remove(0).unwrap();
+        assert_eq!(3, h.len());
+        assert!(h.is_temporary(4));
+
+        // Once it's finished, moved entries are still temporary and the
+        // edits count as changes
+        let mut edit = h.bulk_edit();
+        edit.relocate(8, 12).unwrap();
+        edit.finish().unwrap();
+        assert!(h.get(4).is_none());
+        assert!(h.is_temporary(12));
+    }
+}
@@ -0,0 +1,171 @@
+//! Turning a `BumpyVector` back into bytes.
+//!
+//! When the entries represent the contents of a binary, it's handy to be
+//! able to write them back out again - for example, after editing a layout.
+
+use crate::{BumpyError, BumpyResult, BumpyVector};
+
+impl<T> BumpyVector<T> {
+    /// Render the whole vector as a `max_size`-byte buffer, using `f` to get
+    /// the bytes for each entry.
+    ///
+    /// Each entry's bytes are written at its offset, and any gaps between
+    /// entries are filled with `fill`. If `f` returns more bytes than an
+    /// entry covers, the extras are dropped; if it returns fewer, the rest of
+    /// the entry is filled with `fill`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bumpy_vector::BumpyVector;
+    ///
+    /// let mut v: BumpyVector<u16> = BumpyVector::new(6);
+    /// v.insert((0x4142, 0..2).into()).unwrap();
+    /// v.insert((0x4344, 4..6).into()).unwrap();
+    ///
+    /// let bytes = v.render_bytes_with(0, |value| value.to_be_bytes().to_vec());
+    /// assert_eq!(b"AB\x00\x00CD".to_vec(), bytes);
+    /// ```
+    pub fn render_bytes_with<F>(&self, fill: u8, mut f: F) -> Vec<u8>
+    where
+        F: FnMut(&T) -> Vec<u8>
+    {
+        let mut result = vec![fill; self.max_size];
+
+        for e in self.data.values() {
+            let bytes = f(&e.entry);
+            let length = std::cmp::min(bytes.len(), e.range.len());
+
+            result[e.range.start..(e.range.start + length)].copy_from_slice(&bytes[..length]);
+        }
+
+        result
+    }
+
+    /// Patch each entry's bytes into an existing buffer, leaving everything
+    /// else alone.
+    ///
+    /// This is meant for applying edits in place, such as to a memory-mapped
+    /// file. Everything is validated before anything is written, so on error
+    /// the buffer is untouched.
+    ///
+    /// # Return
+    ///
+    /// Returns `Ok(())` on success. Returns an error if an entry doesn't fit
+    /// in `buf`, or if `f` returns a different number of bytes than the entry
+    /// covers.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bumpy_vector::BumpyVector;
+    ///
+    /// let mut v: BumpyVector<&[u8]> = BumpyVector::new(8);
+    /// v.insert((&b"AB"[..], 2..4).into()).unwrap();
+    ///
+    /// let mut buffer = b"abcdefgh".to_vec();
+    /// v.write_into(&mut buffer, |e| e).unwrap();
+    /// assert_eq!(b"abABefgh".to_vec(), buffer);
+    ///
+    /// // The wrong size is an error
+    /// v.insert((&b"X"[..], 5..7).into()).unwrap();
+    /// assert!(v.write_into(&mut buffer, |e| e).is_err());
+    /// ```
+    pub fn write_into<F>(&self, buf: &mut [u8], f: F) -> BumpyResult<()>
+    where
+        F: Fn(&T) -> &[u8]
+    {
+        for e in self.data.values() {
+            if e.range.end > buf.len() {
+                return Err(BumpyError::OutOfBounds);
+            }
+
+            if f(&e.entry).len() != e.range.len() {
+                return Err(BumpyError::Other(format!("Entry {:?} has {} bytes", e.range, f(&e.entry).len())));
+            }
+        }
+
+        for e in self.data.values() {
+            buf[e.range.clone()].copy_from_slice(f(&e.entry));
+        }
+
+        Ok(())
+    }
+}
+
+impl<T> BumpyVector<T>
+where
+    T: AsRef<[u8]>
+{
+    /// Render the whole vector as a `max_size`-byte buffer.
+    ///
+    /// This works the same as `render_bytes_with()`, except that the entries
+    /// provide their own bytes.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bumpy_vector::BumpyVector;
+    ///
+    /// let mut v: BumpyVector<Vec<u8>> = BumpyVector::new(8);
+    /// v.insert((b"head".to_vec(), 0..4).into()).unwrap();
+    /// v.insert((b"t".to_vec(), 6..8).into()).unwrap();
+    ///
+    /// assert_eq!(b"head..t.".to_vec(), v.render_bytes(b'.'));
+    /// ```
+    pub fn render_bytes(&self, fill: u8) -> Vec<u8> {
+        self.render_bytes_with(fill, |e| e.as_ref().to_vec())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_render_bytes() {
+        let mut h: BumpyVector<&[u8]> = BumpyVector::new(10);
+        h.insert((&b"abc"[..], 1..4).into()).unwrap();
+        h.insert((&b"toolong"[..], 5..7).into()).unwrap();
+        h.insert((&b"x"[..], 7..9).into()).unwrap();
+
+        // Long entries are truncated, short ones are padded
+        assert_eq!(b"-abc-tox--".to_vec(), h.render_bytes(b'-'));
+
+        // An empty vector is all filler
+        let h: BumpyVector<&[u8]> = BumpyVector::new(3);
+        assert_eq!(vec![0xcc; 3], h.render_bytes(0xcc));
+    }
+
+    #[test]
+    fn test_render_bytes_with() {
+        let mut h: BumpyVector<u32> = BumpyVector::new(8);
+        h.insert((0x01020304, 0..4).into()).unwrap();
+        h.insert((0x05060708, 4..8).into()).unwrap();
+
+        assert_eq!(vec![4, 3, 2, 1, 8, 7, 6, 5], h.render_bytes_with(0, |v| v.to_le_bytes().to_vec()));
+    }
+
+    #[test]
+    fn test_write_into() {
+        let mut h: BumpyVector<Vec<u8>> = BumpyVector::new(10);
+        h.insert((b"AB".to_vec(), 0..2).into()).unwrap();
+        h.insert((b"CDE".to_vec(), 5..8).into()).unwrap();
+
+        let mut buffer = vec![b'.'; 10];
+        h.write_into(&mut buffer, |e| e).unwrap();
+        assert_eq!(b"AB...CDE..".to_vec(), buffer);
+
+        // A buffer that's too short is an error, and isn't touched
+        let mut buffer = vec![b'.'; 6];
+        assert!(h.write_into(&mut buffer, |e| e).is_err());
+        assert_eq!(vec![b'.'; 6], buffer);
+
+        // So is a size mismatch
+        h.insert((b"toolong".to_vec(), 8..10).into()).unwrap();
+        let mut buffer = vec![b'.'; 10];
+        assert!(h.write_into(&mut buffer, |e| e).is_err());
+        assert_eq!(vec![b'.'; 10], buffer);
+    }
+}
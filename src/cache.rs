@@ -0,0 +1,163 @@
+//! Remember the last entry that was looked up.
+//!
+//! Rendering a hex view calls `get()` for one address after another, and
+//! most of those land in the same entry as the last one. Remembering the
+//! range of the last entry found means those lookups don't touch the map at
+//! all.
+//!
+//! Lookups only need `&self`, and a vector can be shared between threads, so
+//! the range is kept in atomics rather than behind a lock. They're guarded
+//! by a sequence number (a seqlock): it's odd while the range is being
+//! written, and changes every time it's written, so a lookup that sees it
+//! change, or sees it odd, just skips the cache. Nothing ever waits.
+
+use std::ops::Range;
+use std::sync::atomic::{fence, AtomicUsize, Ordering};
+
+#[derive(Debug, Default)]
+pub(crate) struct LookupCache {
+    /// Even when `start` and `end` can be read, odd while they're being
+    /// written.
+    sequence: AtomicUsize,
+
+    /// The range of the entry that was last looked up, if it's still there
+    /// (an empty range if it isn't).
+    start: AtomicUsize,
+    end: AtomicUsize,
+}
+
+impl LookupCache {
+    /// Returns the remembered range, unless it's being written.
+    fn load(&self) -> Option<Range<usize>> {
+        let before = self.sequence.load(Ordering::Acquire);
+        if before % 2 == 1 {
+            return None;
+        }
+
+        let range = self.start.load(Ordering::Relaxed)..self.end.load(Ordering::Relaxed);
+
+        // Make sure the range was read before checking it didn't change
+        fence(Ordering::Acquire);
+        if self.sequence.load(Ordering::Relaxed) != before {
+            return None;
+        }
+
+        Some(range)
+    }
+
+    /// Returns where the remembered entry starts, if it covers `index`.
+    pub(crate) fn get(&self, index: usize) -> Option<usize> {
+        self.load().filter(|r| r.contains(&index)).map(|r| r.start)
+    }
+
+    /// Remember the entry covering `range`.
+    ///
+    /// If another thread is writing at the same time, this one gives up.
+    pub(crate) fn set(&self, range: Range<usize>) {
+        let current = self.sequence.load(Ordering::Relaxed);
+        if current % 2 == 1 || self.sequence.compare_exchange(current, current.wrapping_add(1), Ordering::Acquire, Ordering::Relaxed).is_err() {
+            return;
+        }
+
+        // Make sure readers see the sequence change before the range does
+        fence(Ordering::Release);
+        self.start.store(range.start, Ordering::Relaxed);
+        self.end.store(range.end, Ordering::Relaxed);
+
+        self.sequence.store(current.wrapping_add(2), Ordering::Release);
+    }
+
+    /// Called when the entry at `start` is removed.
+    pub(crate) fn on_remove(&mut self, start: usize) {
+        if *self.start.get_mut() == start {
+            *self.end.get_mut() = start;
+        }
+    }
+
+    /// Called when every entry is removed.
+    pub(crate) fn on_clear(&mut self) {
+        *self.end.get_mut() = *self.start.get_mut();
+    }
+}
+
+impl Clone for LookupCache {
+    fn clone(&self) -> Self {
+        // If it's being written right now, the clone starts out empty
+        let range = self.load().unwrap_or(0..0);
+
+        LookupCache {
+            sequence: AtomicUsize::new(0),
+            start: AtomicUsize::new(range.start),
+            end: AtomicUsize::new(range.end),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::BumpyVector;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_lookup_cache() {
+        let mut h: BumpyVector<&str> = BumpyVector::new(100);
+        h.insert(("a", 10..20).into()).unwrap();
+        h.insert(("b", 20..30).into()).unwrap();
+
+        // Walking through an entry, and into the next one
+        for i in 10..30 {
+            let expected = if i < 20 { "a" } else { "b" };
+            assert_eq!(expected, h.get(i).unwrap().entry);
+        }
+        assert!(h.get(30).is_none());
+        assert!(h.get(9).is_none());
+
+        // A removed entry isn't remembered
+        h.get(15);
+        h.remove(10);
+        assert!(h.get(15).is_none());
+        assert!(h.get(10).is_none());
+
+        // Nor is one that's been replaced by a smaller one
+        h.get(25);
+        h.remove(20);
+        h.insert(("c", 20..22).into()).unwrap();
+        assert_eq!("c", h.get(21).unwrap().entry);
+        assert!(h.get(25).is_none());
+
+        // Clones start out remembering the same thing, but go their own way
+        h.get(21);
+        let mut cloned = h.clone();
+        cloned.remove(20);
+        assert!(cloned.get(21).is_none());
+        assert_eq!("c", h.get(21).unwrap().entry);
+    }
+
+    #[test]
+    fn test_lookup_cache_threads() {
+        let mut h: BumpyVector<usize> = BumpyVector::new(1000);
+        for i in 0..100 {
+            h.insert((i, (i * 10)..(i * 10 + 5)).into()).unwrap();
+        }
+
+        // Readers sharing a vector keep replacing each other's cached range,
+        // but never see the wrong entry
+        std::thread::scope(|scope| {
+            for t in 0..4 {
+                let h = &h;
+                scope.spawn(move || {
+                    for round in 0..50 {
+                        for i in 0..1000 {
+                            let i = (i * (t + 1) + round) % 1000;
+
+                            match h.get(i) {
+                                Some(e) => assert_eq!(i / 10, e.entry),
+                                None    => assert!(i % 10 >= 5),
+                            }
+                        }
+                    }
+                });
+            }
+        });
+    }
+}
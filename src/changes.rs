@@ -0,0 +1,191 @@
+//! Find out what's changed since a given generation.
+//!
+//! `generation()` says *whether* a vector has changed; this says *what*
+//! changed. It's off by default - once `track_changes()` is called, each
+//! entry remembers the generation it was last inserted or modified at, and
+//! removals are logged along with the range they freed up.
+//!
+//! `changes_since()` then returns everything that's different since a
+//! generation the caller saw earlier, which is all an incremental renderer
+//! or indexer needs to catch up.
+//!
+//! Like the generation itself, none of this is serialized.
+//!
+//! # Example
+//!
+//! ```
+//! use bumpy_vector::BumpyVector;
+//!
+//! let mut v: BumpyVector<&str> = BumpyVector::new(100);
+//! v.track_changes();
+//!
+//! v.insert(("a", 0..10).into()).unwrap();
+//! v.insert(("b", 10..20).into()).unwrap();
+//!
+//! // Draw everything, then remember where we were
+//! let seen = v.generation();
+//!
+//! v.insert(("c", 20..30).into()).unwrap();
+//! v.remove(5).unwrap();
+//!
+//! let changes = v.changes_since(seen);
+//! assert_eq!(1, changes.modified.len());
+//! assert_eq!("c", changes.modified[0].entry);
+//! assert_eq!(vec![0..10], changes.removed);
+//! ```
+
+use std::collections::HashMap;
+use std::ops::Range;
+
+use crate::{BumpyEntry, BumpyVector};
+
+/// What's changed in a vector since a particular generation.
+///
+/// See `BumpyVector::changes_since()`.
+#[derive(Debug, Clone)]
+pub struct Changes<'a, T> {
+    /// Entries that were inserted or modified, in order.
+    pub modified: Vec<&'a BumpyEntry<T>>,
+
+    /// Ranges where entries were removed, in order.
+    ///
+    /// An entry that was removed and then replaced shows up in both lists.
+    pub removed: Vec<Range<usize>>,
+}
+
+/// The per-vector state, once tracking is turned on.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ChangeTracker {
+    /// The generation each entry last changed at, indexed by where it starts.
+    entries: HashMap<usize, u64>,
+
+    /// Every removal, and the generation it happened at, oldest first.
+    removed: Vec<(u64, Range<usize>)>,
+}
+
+impl ChangeTracker {
+    /// Called when the entry at `start` is added or (maybe) changed.
+    pub(crate) fn on_change(&mut self, start: usize, generation: u64) {
+        self.entries.insert(start, generation);
+    }
+
+    /// Called when the entry covering `range` is removed.
+    pub(crate) fn on_remove(&mut self, range: Range<usize>, generation: u64) {
+        self.entries.remove(&range.start);
+        self.removed.push((generation, range));
+    }
+
+    /// Called when the entries covering `ranges` are all removed at once.
+    pub(crate) fn on_clear<I>(&mut self, ranges: I, generation: u64)
+    where
+        I: Iterator<Item = Range<usize>>
+    {
+        self.entries.clear();
+        self.removed.extend(ranges.map(|range| (generation, range)));
+    }
+}
+
+impl<T> BumpyVector<T> {
+    /// Start keeping track of changes, for `changes_since()`.
+    ///
+    /// Entries that are already in the vector count as changed at the current
+    /// generation. Calling this when tracking is already on does nothing.
+    pub fn track_changes(&mut self) {
+        if self.changes.is_some() {
+            return;
+        }
+
+        let mut tracker = ChangeTracker::default();
+        for start in self.data.keys() {
+            tracker.on_change(*start, self.generation);
+        }
+
+        self.changes = Some(tracker);
+    }
+
+    /// Returns what's changed after `generation` (see `generation()`).
+    ///
+    /// If changes aren't being tracked, nothing has changed as far as this is
+    /// concerned.
+    pub fn changes_since(&self, generation: u64) -> Changes<'_, T> {
+        let tracker = match &self.changes {
+            Some(t) => t,
+            None    => return Changes { modified: vec![], removed: vec![] },
+        };
+
+        let mut modified: Vec<&BumpyEntry<T>> = tracker.entries.iter()
+            .filter(|(_, g)| **g > generation)
+            .filter_map(|(start, _)| self.data.get(start))
+            .collect();
+        modified.sort_by_key(|e| e.range.start);
+
+        // The log is in order, so everything we want is at the end
+        let first = tracker.removed.partition_point(|(g, _)| *g <= generation);
+        let mut removed: Vec<Range<usize>> = tracker.removed[first..].iter().map(|(_, r)| r.clone()).collect();
+        removed.sort_by_key(|r| (r.start, r.end));
+        removed.dedup();
+
+        Changes { modified, removed }
+    }
+
+    /// Discard the record of removals at or before `generation`.
+    ///
+    /// The removal log grows for as long as changes are tracked, so once
+    /// every consumer has caught up to a generation, the older entries can be
+    /// dropped. `changes_since()` for an older generation will be missing
+    /// those removals afterwards.
+    pub fn forget_changes(&mut self, generation: u64) {
+        if let Some(tracker) = &mut self.changes {
+            tracker.removed.retain(|(g, _)| *g > generation);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_changes_since() {
+        let mut h: BumpyVector<String> = BumpyVector::new(100);
+
+        // Nothing is tracked until it's turned on
+        h.insert((String::from("a"), 0..10).into()).unwrap();
+        assert_eq!(0, h.changes_since(0).modified.len());
+
+        h.track_changes();
+        let start = h.generation();
+        assert_eq!(1, h.changes_since(start - 1).modified.len());
+        assert_eq!(0, h.changes_since(start).modified.len());
+
+        h.insert((String::from("b"), 10..20).into()).unwrap();
+        h.insert((String::from("c"), 20..30).into()).unwrap();
+        let middle = h.generation();
+
+        h.get_mut(5).unwrap().entry.push('!');
+        h.remove(15).unwrap();
+        h.insert((String::from("d"), 10..12).into()).unwrap();
+
+        let changes = h.changes_since(start);
+        let modified: Vec<&str> = changes.modified.iter().map(|e| &e.entry[..]).collect();
+        assert_eq!(vec!["a!", "d", "c"], modified);
+        assert_eq!(vec![10..20], changes.removed);
+
+        let changes = h.changes_since(middle);
+        let modified: Vec<&str> = changes.modified.iter().map(|e| &e.entry[..]).collect();
+        assert_eq!(vec!["a!", "d"], modified);
+        assert_eq!(vec![10..20], changes.removed);
+
+        // Nothing has changed since now
+        let changes = h.changes_since(h.generation());
+        assert_eq!(0, changes.modified.len());
+        assert_eq!(0, changes.removed.len());
+
+        // Forgetting drops old removals, but not modifications
+        h.forget_changes(h.generation());
+        let changes = h.changes_since(start);
+        assert_eq!(3, changes.modified.len());
+        assert_eq!(0, changes.removed.len());
+    }
+}
@@ -0,0 +1,389 @@
+//! A more compact `BumpyVector` that stores indexes as `u32`.
+//!
+//! A `BumpyVector` stores the start and end of every entry as a `usize`,
+//! plus the start again as the key. With tens of millions of entries that
+//! adds up, so `CompactBumpyVector` only stores the start once (as the key)
+//! and the end, both as `u32` - 8 bytes of bookkeeping per entry rather
+//! than 24, on 64-bit platforms.
+//!
+//! The catch is that the address space is limited to 4GB; creating a
+//! `CompactBumpyVector` with a larger `max_size`, or converting a larger
+//! `BumpyVector`, is an error.
+//!
+//! The API mirrors `BumpyVector`, except that entries are handed out as
+//! `BumpyEntry<&T>` (built on the fly) rather than `&BumpyEntry<T>`.
+
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::ops::Range;
+
+#[cfg(feature = "serialize")]
+use serde::{Serialize, Deserialize};
+
+use crate::{BumpyEntry, BumpyError, BumpyResult, BumpyVector, InsertError};
+
+/// An entry, minus its start (which is the key it's stored under).
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+struct CompactEntry<T> {
+    entry: T,
+    end: u32,
+}
+
+/// Represents an instance of a compact Bumpy Vector.
+///
+/// # Example
+///
+/// ```
+/// use bumpy_vector::compact::CompactBumpyVector;
+///
+/// // Anything up to 4GB is fine
+/// let mut v: CompactBumpyVector<&str> = CompactBumpyVector::new(100).unwrap();
+///
+/// // Use it like a normal BumpyVector
+/// assert!(v.insert(("hello", 0..10).into()).is_ok());
+/// assert!(v.insert(("error", 5..15).into()).is_err());
+/// assert_eq!("hello", *v.get(5).unwrap().entry);
+/// assert_eq!(0..10, v.get(5).unwrap().range);
+/// ```
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serialize", serde(try_from = "SerializedCompactBumpyVector<T>"))]
+pub struct CompactBumpyVector<T> {
+    /// The data, indexed by where each entry starts.
+    data: BTreeMap<u32, CompactEntry<T>>,
+
+    /// The maximum size.
+    max_size: u32,
+}
+
+/// The fields of a `CompactBumpyVector`, before they've been checked.
+///
+/// Like a `BumpyVector`, a deserialized compact vector is built by inserting
+/// its entries one at a time, so a bad file can't break the rules.
+#[cfg(feature = "serialize")]
+#[derive(Deserialize)]
+struct SerializedCompactBumpyVector<T> {
+    data: BTreeMap<u32, CompactEntry<T>>,
+    max_size: u32,
+}
+
+#[cfg(feature = "serialize")]
+impl<T> TryFrom<SerializedCompactBumpyVector<T>> for CompactBumpyVector<T> {
+    type Error = BumpyError;
+
+    fn try_from(o: SerializedCompactBumpyVector<T>) -> BumpyResult<Self> {
+        let mut result = CompactBumpyVector::new(o.max_size as usize)?;
+
+        for (start, e) in o.data.into_iter() {
+            let (start, end) = result.check_insert(&((start as usize)..(e.end as usize)))?;
+            result.data.insert(start, CompactEntry { entry: e.entry, end });
+        }
+
+        Ok(result)
+    }
+}
+
+/// Convert a `usize` index into a `u32`, or fail if it won't fit.
+fn to_u32(index: usize) -> BumpyResult<u32> {
+    match u32::try_from(index) {
+        Ok(i) => Ok(i),
+        Err(_) => Err(BumpyError::Overflow),
+    }
+}
+
+impl<T> CompactBumpyVector<T> {
+    /// Create a new instance of `CompactBumpyVector`.
+    ///
+    /// # Return
+    ///
+    /// Returns an error if `max_size` doesn't fit in a `u32`.
+    pub fn new(max_size: usize) -> BumpyResult<Self> {
+        Ok(CompactBumpyVector {
+            data: BTreeMap::new(),
+            max_size: to_u32(max_size)?,
+        })
+    }
+
+    /// Get the start of the entry that overlaps `starting_index`.
+    ///
+    /// Works the same way as `BumpyVector`'s lookup.
+    fn get_entry_start(&self, starting_index: usize) -> Option<u32> {
+        if starting_index >= self.max_size as usize {
+            return None;
+        }
+
+        // This can't truncate, since it's below max_size
+        let starting_index = starting_index as u32;
+
+        match self.data.range(..=starting_index).next_back() {
+            Some((index, d)) if starting_index < d.end => Some(*index),
+            _ => None,
+        }
+    }
+
+    /// Build the range for the entry starting at `start`.
+    fn range(start: u32, e: &CompactEntry<T>) -> Range<usize> {
+        (start as usize)..(e.end as usize)
+    }
+
+    /// Insert a new entry.
+    ///
+    /// See `BumpyVector::insert()`; like that, the entry is handed back in
+    /// the error if it doesn't fit.
+    pub fn insert(&mut self, entry: BumpyEntry<T>) -> Result<(), InsertError<T>> {
+        let (start, end) = match self.check_insert(&entry.range) {
+            Ok(r) => r,
+            Err(reason) => return Err(InsertError { entry, reason }),
+        };
+
+        self.data.insert(start, CompactEntry { entry: entry.entry, end });
+
+        Ok(())
+    }
+
+    /// Check whether an entry covering `range` would fit, and return where
+    /// it starts and ends if it would.
+    fn check_insert(&self, range: &Range<usize>) -> BumpyResult<(u32, u32)> {
+        if range.is_empty() {
+            return Err(BumpyError::ZeroSize);
+        }
+
+        if range.end > self.max_size as usize {
+            return Err(BumpyError::OutOfBounds);
+        }
+
+        if self.get_entry_start(range.start).is_some() {
+            return Err(BumpyError::Overlap);
+        }
+
+        let start = to_u32(range.start)?;
+        let end = to_u32(range.end)?;
+
+        if self.data.range(start..end).next().is_some() {
+            return Err(BumpyError::Overlap);
+        }
+
+        Ok((start, end))
+    }
+
+    /// Remove and return the entry at `index`.
+    ///
+    /// See `BumpyVector::remove()`.
+    pub fn remove(&mut self, index: usize) -> Option<BumpyEntry<T>> {
+        let start = self.get_entry_start(index)?;
+        let e = self.data.remove(&start)?;
+
+        Some(BumpyEntry {
+            range: Self::range(start, &e),
+            entry: e.entry,
+        })
+    }
+
+    /// Return the entry at the given index.
+    ///
+    /// See `BumpyVector::get()`.
+    pub fn get(&self, index: usize) -> Option<BumpyEntry<&T>> {
+        let start = self.get_entry_start(index)?;
+        let e = self.data.get(&start)?;
+
+        Some(BumpyEntry {
+            range: Self::range(start, e),
+            entry: &e.entry,
+        })
+    }
+
+    /// Return the entry at the given index, with a mutable reference to its
+    /// value.
+    ///
+    /// See `BumpyVector::get_mut()`.
+    pub fn get_mut(&mut self, index: usize) -> Option<BumpyEntry<&mut T>> {
+        let start = self.get_entry_start(index)?;
+        let e = self.data.get_mut(&start)?;
+
+        Some(BumpyEntry {
+            range: (start as usize)..(e.end as usize),
+            entry: &mut e.entry,
+        })
+    }
+
+    /// Return the entries within the given range, in order.
+    ///
+    /// See `BumpyVector::get_range()`.
+    pub fn get_range(&self, range: Range<usize>) -> Vec<BumpyEntry<&T>> {
+        if range.is_empty() {
+            return Vec::new();
+        }
+
+        let start = match self.get_entry_start(range.start) {
+            Some(e) => e as usize,
+            None    => range.start,
+        };
+        let end = std::cmp::min(range.end, self.max_size as usize);
+
+        if start >= end {
+            return Vec::new();
+        }
+
+        // These can't truncate, since they're no bigger than max_size
+        self.data.range((start as u32)..(end as u32)).map(|(i, e)| BumpyEntry {
+            range: Self::range(*i, e),
+            entry: &e.entry,
+        }).collect()
+    }
+
+    /// Returns the number of entries.
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Returns `true` if there are no entries.
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// Returns the maximum size.
+    pub fn max_size(&self) -> usize {
+        self.max_size as usize
+    }
+}
+
+/// Expand a compact vector back into a normal `BumpyVector`.
+impl<T> From<CompactBumpyVector<T>> for BumpyVector<T> {
+    fn from(o: CompactBumpyVector<T>) -> Self {
+        let mut result = BumpyVector::new(o.max_size as usize);
+
+        for (start, e) in o.data.into_iter() {
+            // A compact vector follows the same rules, so these always fit
+            let inserted = result.insert(BumpyEntry {
+                range: (start as usize)..(e.end as usize),
+                entry: e.entry,
+            });
+            debug_assert!(inserted.is_ok());
+        }
+
+        result
+    }
+}
+
+/// Shrink a `BumpyVector` into a compact one.
+///
+/// Fails if its `max_size` doesn't fit into a `u32`.
+impl<T> TryFrom<BumpyVector<T>> for CompactBumpyVector<T> {
+    type Error = BumpyError;
+
+    fn try_from(o: BumpyVector<T>) -> BumpyResult<Self> {
+        let mut result = CompactBumpyVector::new(o.max_size)?;
+
+        for (start, e) in o.data.into_iter() {
+            // Every entry is within max_size, so these can't fail
+            result.data.insert(to_u32(start)?, CompactEntry {
+                end: to_u32(e.range.end)?,
+                entry: e.entry,
+            });
+        }
+
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_compact() {
+        let mut h: CompactBumpyVector<&str> = CompactBumpyVector::new(100).unwrap();
+
+        h.insert(("hello", 10..15).into()).unwrap();
+        h.insert(("ok", 15..16).into()).unwrap();
+        assert!(h.insert(("error", 5..11).into()).is_err());
+        assert!(h.insert(("error", 14..20).into()).is_err());
+        assert!(h.insert(("error", 99..101).into()).is_err());
+        assert!(h.insert(("error", 20..20).into()).is_err());
+        assert_eq!(2, h.len());
+
+        // The entry comes back when it doesn't fit
+        let e = h.insert(("retry", 14..20).into()).unwrap_err();
+        assert_eq!("retry", e.entry.entry);
+        assert!(matches!(e.reason, BumpyError::Overlap));
+
+        assert!(h.get(9).is_none());
+        assert_eq!(10..15, h.get(14).unwrap().range);
+        assert_eq!("ok", *h.get(15).unwrap().entry);
+        assert!(h.get(16).is_none());
+        assert!(h.get(1000).is_none());
+
+        *h.get_mut(12).unwrap().entry = "HELLO";
+        assert_eq!("HELLO", *h.get(10).unwrap().entry);
+
+        assert_eq!(2, h.get_range(0..100).len());
+        assert_eq!(1, h.get_range(14..15).len());
+        assert_eq!(0, h.get_range(16..usize::MAX).len());
+
+        let e = h.remove(11).unwrap();
+        assert_eq!(10..15, e.range);
+        assert!(h.remove(11).is_none());
+        assert_eq!(1, h.len());
+    }
+
+    #[test]
+    fn test_compact_large_entries() {
+        let mut h: CompactBumpyVector<&str> = CompactBumpyVector::new(u32::MAX as usize).unwrap();
+
+        // Neither of these should have to look at every index
+        h.insert(("big", 0..(u32::MAX as usize - 10)).into()).unwrap();
+        assert!(h.insert(("error", 100..200).into()).is_err());
+        h.insert(("small", (u32::MAX as usize - 10)..(u32::MAX as usize)).into()).unwrap();
+
+        assert_eq!("big", *h.get(u32::MAX as usize - 11).unwrap().entry);
+        assert_eq!("small", *h.get(u32::MAX as usize - 1).unwrap().entry);
+        assert_eq!(2, h.get_range(1..u32::MAX as usize).len());
+    }
+
+    #[test]
+    fn test_compact_too_large() {
+        assert!(CompactBumpyVector::<u8>::new(u32::MAX as usize).is_ok());
+
+        // Only matters on platforms where usize is bigger than u32
+        if std::mem::size_of::<usize>() > 4 {
+            assert!(CompactBumpyVector::<u8>::new(u32::MAX as usize + 1).is_err());
+        }
+    }
+
+    #[test]
+    fn test_compact_conversion() {
+        let mut v: BumpyVector<&str> = BumpyVector::new(10);
+        v.insert(("a", 1..3).into()).unwrap();
+        v.insert(("b", 3..4).into()).unwrap();
+
+        let c = CompactBumpyVector::try_from(v).unwrap();
+        assert_eq!(2, c.len());
+        assert_eq!(1..3, c.get(2).unwrap().range);
+
+        let v: BumpyVector<&str> = c.into();
+        assert_eq!(2, v.len());
+        assert_eq!("b", v.get(3).unwrap().entry);
+        assert_eq!(10, v.max_size());
+    }
+
+    #[test]
+    #[cfg(feature = "serialize")]
+    fn test_compact_deserialize() {
+        let mut h: CompactBumpyVector<String> = CompactBumpyVector::new(10).unwrap();
+        h.insert((String::from("a"), 1..3).into()).unwrap();
+        h.insert((String::from("b"), 3..4).into()).unwrap();
+
+        let h: CompactBumpyVector<String> = ron::de::from_str(&ron::ser::to_string(&h).unwrap()).unwrap();
+        assert_eq!(2, h.len());
+        assert_eq!(1..3, h.get(2).unwrap().range);
+
+        // Overlapping, out of bounds, and empty or backwards entries are all
+        // rejected
+        assert!(ron::de::from_str::<CompactBumpyVector<String>>(r#"(data: { 1: (entry: "a", end: 3), 2: (entry: "b", end: 4) }, max_size: 10)"#).is_err());
+        assert!(ron::de::from_str::<CompactBumpyVector<String>>(r#"(data: { 8: (entry: "a", end: 12) }, max_size: 10)"#).is_err());
+        assert!(ron::de::from_str::<CompactBumpyVector<String>>(r#"(data: { 5: (entry: "a", end: 5) }, max_size: 10)"#).is_err());
+        assert!(ron::de::from_str::<CompactBumpyVector<String>>(r#"(data: { 5: (entry: "a", end: 2) }, max_size: 10)"#).is_err());
+    }
+}
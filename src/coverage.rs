@@ -0,0 +1,224 @@
+//! How much of a vector is covered, and converting it to and from a
+//! coverage bitmap.
+//!
+//! The number of covered indexes is kept up to date as entries are inserted
+//! and removed, so `occupied_size()`, `free_size()`, and `coverage_ratio()`
+//! don't have to look at any entries.
+//!
+//! Fuzzers and tracers tend to exchange coverage as a plain bitmap - one bit
+//! per offset, set if it was hit - with no idea of what's there. These
+//! convert between that and a `BumpyVector`, where each run of set bits is
+//! an entry.
+//!
+//! Bit `i` is bit `i % 64` (counting from the least significant) of word
+//! `i / 64`. A bitmap takes `max_size / 8` bytes, so this is only practical
+//! for vectors the size of a real file.
+//!
+//! # Example
+//!
+//! ```
+//! use bumpy_vector::BumpyVector;
+//!
+//! let mut v: BumpyVector<&str> = BumpyVector::new(100);
+//! v.insert(("a", 0..4).into()).unwrap();
+//! v.insert(("b", 4..6).into()).unwrap();
+//! v.insert(("c", 70..72).into()).unwrap();
+//!
+//! let bitmap = v.coverage_bitmap();
+//! assert_eq!(vec![0b111111, 0b11 << 6], bitmap);
+//!
+//! // Adjacent entries come back as one
+//! let covered = BumpyVector::from_coverage_bitmap(&bitmap, 100);
+//! assert_eq!(2, covered.len());
+//! assert_eq!(0..6, covered.get(3).unwrap().range);
+//! ```
+
+use crate::{BumpyEntry, BumpyVector};
+
+/// How many offsets fit in one word of a bitmap.
+const BITS: usize = 64;
+
+impl<T> BumpyVector<T> {
+    /// Returns how many indexes are covered by entries.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bumpy_vector::BumpyVector;
+    ///
+    /// let mut v: BumpyVector<&str> = BumpyVector::new(100);
+    /// v.insert(("header", 0..16).into()).unwrap();
+    /// v.insert(("code", 32..80).into()).unwrap();
+    ///
+    /// assert_eq!(64, v.occupied_size());
+    /// assert_eq!(36, v.free_size());
+    /// assert_eq!(0.64, v.coverage_ratio());
+    /// ```
+    pub fn occupied_size(&self) -> usize {
+        self.occupied
+    }
+
+    /// Returns how many indexes aren't covered by any entry.
+    pub fn free_size(&self) -> usize {
+        self.max_size - self.occupied
+    }
+
+    /// Returns how much of the vector is covered by entries, from `0.0` to
+    /// `1.0`.
+    ///
+    /// A vector with a `max_size` of `0` has nothing to cover, so it's
+    /// `0.0`.
+    pub fn coverage_ratio(&self) -> f64 {
+        if self.max_size == 0 {
+            return 0.0;
+        }
+
+        self.occupied as f64 / self.max_size as f64
+    }
+
+    /// Build a bitmap with a bit set for every offset that's covered by an
+    /// entry.
+    ///
+    /// The bitmap has enough words for `max_size` bits; any extra bits in
+    /// the last word are clear.
+    pub fn coverage_bitmap(&self) -> Vec<u64> {
+        let mut result = vec![0u64; self.max_size.div_ceil(BITS)];
+
+        for e in self.data.values() {
+            let mut i = e.range.start;
+
+            while i < e.range.end {
+                let word = i / BITS;
+                let bit = i % BITS;
+
+                // Set as many bits as we can in this word at once
+                let count = std::cmp::min(BITS - bit, e.range.end - i);
+                let mask = if count == BITS { !0 } else { ((1u64 << count) - 1) << bit };
+                result[word] |= mask;
+
+                i += count;
+            }
+        }
+
+        result
+    }
+}
+
+impl BumpyVector<()> {
+    /// Build a vector from a bitmap, with an entry for each run of set bits.
+    ///
+    /// Bits past `max_size`, or past the end of the bitmap, are treated as
+    /// clear.
+    pub fn from_coverage_bitmap(bitmap: &[u64], max_size: usize) -> Self {
+        let mut result = BumpyVector::new(max_size);
+        let end = std::cmp::min(max_size, bitmap.len().saturating_mul(BITS));
+
+        let is_set = |i: usize| bitmap[i / BITS] & (1 << (i % BITS)) != 0;
+
+        let mut i = 0;
+        while i < end {
+            // Skip empty words without looking at each bit
+            if i % BITS == 0 && bitmap[i / BITS] == 0 {
+                i += BITS;
+                continue;
+            }
+
+            if !is_set(i) {
+                i += 1;
+                continue;
+            }
+
+            let start = i;
+            while i < end && is_set(i) {
+                i += 1;
+            }
+
+            // Runs can't overlap, and they're within max_size
+            result.insert_unchecked(BumpyEntry {
+                entry: (),
+                range: start..i,
+            });
+        }
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_coverage_bitmap() {
+        let mut h: BumpyVector<&str> = BumpyVector::new(200);
+        h.insert(("a", 1..3).into()).unwrap();
+        h.insert(("b", 60..130).into()).unwrap();
+        h.insert(("c", 199..200).into()).unwrap();
+
+        let bitmap = h.coverage_bitmap();
+        assert_eq!(4, bitmap.len());
+        assert_eq!(0b110 | (0b1111 << 60), bitmap[0]);
+        assert_eq!(!0, bitmap[1]);
+        assert_eq!(0b11, bitmap[2]);
+        assert_eq!(1 << 7, bitmap[3]);
+
+        // And back again
+        let covered = BumpyVector::from_coverage_bitmap(&bitmap, 200);
+        let ranges: Vec<_> = covered.iter().map(|e| e.range.clone()).collect();
+        assert_eq!(vec![1..3, 60..130, 199..200], ranges);
+
+        // Cutting it short
+        let covered = BumpyVector::from_coverage_bitmap(&bitmap, 100);
+        let ranges: Vec<_> = covered.iter().map(|e| e.range.clone()).collect();
+        assert_eq!(vec![1..3, 60..100], ranges);
+
+        // Or with a short bitmap
+        let covered = BumpyVector::from_coverage_bitmap(&bitmap[..1], 1000);
+        let ranges: Vec<_> = covered.iter().map(|e| e.range.clone()).collect();
+        assert_eq!(vec![1..3, 60..64], ranges);
+
+        // Nothing at all
+        let h: BumpyVector<&str> = BumpyVector::new(0);
+        assert_eq!(0, h.coverage_bitmap().len());
+        assert_eq!(0, BumpyVector::from_coverage_bitmap(&[0, 0], 128).len());
+    }
+
+    #[test]
+    fn test_coverage_stats() {
+        let mut h: BumpyVector<&str> = BumpyVector::new(100);
+        assert_eq!(0, h.occupied_size());
+        assert_eq!(100, h.free_size());
+        assert_eq!(0.0, h.coverage_ratio());
+
+        h.insert(("a", 0..10).into()).unwrap();
+        h.insert(("b", 10..30).into()).unwrap();
+        h.insert(("c", 50..75).into()).unwrap();
+        assert!(h.insert(("error", 5..15).into()).is_err());
+        assert_eq!(55, h.occupied_size());
+        assert_eq!(45, h.free_size());
+        assert_eq!(0.55, h.coverage_ratio());
+
+        // Everything that removes or replaces entries keeps it up to date
+        h.remove(15);
+        assert_eq!(35, h.occupied_size());
+        h.insert_or_replace(("d", 70..80).into()).unwrap();
+        assert_eq!(20, h.occupied_size());
+        h.convert_units(1, 10).unwrap();
+        assert_eq!(2, h.occupied_size());
+        assert_eq!(8, h.free_size());
+        h.shrink_to(5);
+        assert_eq!(1, h.occupied_size());
+        h.remove_range(0..5);
+        assert_eq!(0, h.occupied_size());
+
+        h.insert(("e", 0..5).into()).unwrap();
+        assert_eq!(1.0, h.coverage_ratio());
+        h.clear();
+        assert_eq!(0, h.occupied_size());
+        assert_eq!(5, h.free_size());
+
+        let h: BumpyVector<&str> = BumpyVector::new(0);
+        assert_eq!(0.0, h.coverage_ratio());
+    }
+}
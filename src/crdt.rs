@@ -0,0 +1,486 @@
+//! A conflict-free replicated layer on top of `BumpyVector`.
+//!
+//! Several clients can edit the same annotation space independently, then
+//! swap operations with each other in any order. As long as every replica
+//! eventually sees the same set of operations, they all converge on the same
+//! layout - no central lock required.
+//!
+//! Every operation is tagged with a `Stamp`, which is a Lamport timestamp
+//! plus the ID of the site that created it. Stamps are totally ordered, and
+//! the state of a replica is always the result of applying its operations in
+//! stamp order. When an incoming insert overlaps entries that are already
+//! there, the `ConflictPolicy` decides which side wins.
+
+use std::collections::{BTreeMap, HashMap};
+
+#[cfg(feature = "serialize")]
+use serde::{Serialize, Deserialize};
+
+use crate::{BumpyEntry, BumpyError, BumpyResult, BumpyVector};
+
+/// Identifies a single replica.
+///
+/// Every replica editing the same data must have a unique `SiteId`.
+pub type SiteId = u64;
+
+/// A unique, totally ordered tag for a single operation.
+///
+/// Stamps compare by `counter` first, then by `site`, so concurrent
+/// operations from different sites still have a well-defined order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct Stamp {
+    pub counter: u64,
+    pub site: SiteId,
+}
+
+/// A value, along with the stamp of the operation that inserted it.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct Stamped<T> {
+    pub stamp: Stamp,
+    pub value: T,
+}
+
+/// A single replicated operation.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub enum Operation<T> {
+    /// Insert an entry.
+    Insert(BumpyEntry<T>),
+
+    /// Remove the entry that was inserted by the operation with the given
+    /// stamp. If that entry has already been replaced, this does nothing.
+    Remove(Stamp),
+}
+
+/// How to resolve an incoming insert that overlaps an existing entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Resolution {
+    /// Drop the incoming entry.
+    KeepExisting,
+
+    /// Remove the existing entry and use the incoming one.
+    TakeIncoming,
+}
+
+/// A function that resolves a conflict between `(existing, incoming)` entries.
+pub type Resolver<T> = fn(&BumpyEntry<Stamped<T>>, &BumpyEntry<Stamped<T>>) -> Resolution;
+
+/// Decides what happens when replicated inserts overlap.
+#[derive(Debug, Clone, Copy)]
+pub enum ConflictPolicy<T> {
+    /// The entry with the highest stamp wins.
+    LastWriterWins,
+
+    /// Call a function with the `(existing, incoming)` entries.
+    ///
+    /// The incoming entry is only inserted if every overlapping entry resolves
+    /// to `TakeIncoming`. The function must be deterministic and identical on
+    /// every replica, otherwise the replicas won't converge.
+    Custom(Resolver<T>),
+}
+
+/// A `BumpyVector` that can be edited concurrently by multiple sites.
+///
+/// # Example
+///
+/// ```
+/// use bumpy_vector::crdt::CrdtBumpyVector;
+///
+/// // Two replicas of the same 100-byte space
+/// let mut a: CrdtBumpyVector<&str> = CrdtBumpyVector::new(1, 100);
+/// let mut b: CrdtBumpyVector<&str> = CrdtBumpyVector::new(2, 100);
+///
+/// // Each of them annotates the same bytes without talking to the other
+/// a.insert(("from a", 0..10).into()).unwrap();
+/// b.insert(("from b", 5..15).into()).unwrap();
+///
+/// // Swap operations, in either order
+/// a.merge(&b);
+/// b.merge(&a);
+///
+/// // They agree on the result
+/// assert_eq!(1, a.len());
+/// assert_eq!(1, b.len());
+/// assert_eq!(a.get(5).unwrap().entry.value, b.get(5).unwrap().entry.value);
+/// ```
+#[derive(Debug, Clone)]
+pub struct CrdtBumpyVector<T> {
+    /// The ID of this replica.
+    site: SiteId,
+
+    /// The Lamport clock - the highest counter we've seen so far.
+    clock: u64,
+
+    /// How overlapping inserts are resolved.
+    policy: ConflictPolicy<T>,
+
+    /// Every operation we know about, ordered by stamp.
+    log: BTreeMap<Stamp, Operation<T>>,
+
+    /// Where each live entry starts, indexed by the stamp that inserted it.
+    positions: HashMap<Stamp, usize>,
+
+    /// The result of applying `log` in order.
+    state: BumpyVector<Stamped<T>>,
+}
+
+impl<T> CrdtBumpyVector<T>
+where
+    T: Clone
+{
+    /// Create a new, empty replica using `ConflictPolicy::LastWriterWins`.
+    pub fn new(site: SiteId, max_size: usize) -> Self {
+        Self::with_policy(site, max_size, ConflictPolicy::LastWriterWins)
+    }
+
+    /// Create a new, empty replica with a custom `ConflictPolicy`.
+    pub fn with_policy(site: SiteId, max_size: usize, policy: ConflictPolicy<T>) -> Self {
+        CrdtBumpyVector {
+            site,
+            clock: 0,
+            policy,
+            log: BTreeMap::new(),
+            positions: HashMap::new(),
+            state: BumpyVector::new(max_size),
+        }
+    }
+
+    /// The ID of this replica.
+    pub fn site(&self) -> SiteId {
+        self.site
+    }
+
+    /// Get the next stamp for a local operation.
+    fn next_stamp(&mut self) -> Stamp {
+        self.clock += 1;
+
+        Stamp {
+            counter: self.clock,
+            site: self.site,
+        }
+    }
+
+    /// Insert a new entry locally.
+    ///
+    /// Local edits follow the same rules as `BumpyVector::insert()`: the entry
+    /// must fit, and must not overlap anything. Only replicated operations
+    /// go through the `ConflictPolicy`.
+    ///
+    /// # Return
+    ///
+    /// Returns the stamp of the new operation, which can be shipped to other
+    /// replicas along with the operation (see `operations()`).
+    pub fn insert(&mut self, entry: BumpyEntry<T>) -> BumpyResult<Stamp> {
+        if entry.range.is_empty() {
+            return Err(BumpyError::ZeroSize);
+        }
+
+        if entry.range.end > self.state.max_size() {
+            return Err(BumpyError::OutOfBounds);
+        }
+
+        if !self.state.get_range(entry.range.clone()).is_empty() {
+            return Err(BumpyError::Overlap);
+        }
+
+        let stamp = self.next_stamp();
+        self.apply(stamp, Operation::Insert(entry));
+
+        Ok(stamp)
+    }
+
+    /// Remove the entry at `index` locally.
+    ///
+    /// Like `BumpyVector::remove()`, the entry only needs to overlap `index`.
+    /// Returns the stamp of the new operation, or `None` if there was nothing
+    /// to remove.
+    pub fn remove(&mut self, index: usize) -> Option<Stamp> {
+        let target = self.state.get(index)?.entry.stamp;
+
+        let stamp = self.next_stamp();
+        self.apply(stamp, Operation::Remove(target));
+
+        Some(stamp)
+    }
+
+    /// Integrate a single operation, local or remote.
+    ///
+    /// Applying an operation that's already known does nothing, so it's safe
+    /// to receive the same operations more than once.
+    pub fn apply(&mut self, stamp: Stamp, operation: Operation<T>) {
+        if self.log.contains_key(&stamp) {
+            return;
+        }
+
+        // Keep the Lamport clock ahead of anything we've seen
+        if stamp.counter > self.clock {
+            self.clock = stamp.counter;
+        }
+
+        // If this is the newest operation, we can just apply it on top;
+        // otherwise, history changed and we need to replay everything
+        let newest = match self.log.keys().next_back() {
+            Some(last) => stamp > *last,
+            None       => true,
+        };
+
+        self.log.insert(stamp, operation.clone());
+
+        if newest {
+            self.replay_one(stamp, operation);
+        } else {
+            self.rebuild();
+        }
+    }
+
+    /// Apply every operation from `other` that we haven't seen yet.
+    ///
+    /// If they're all newer than everything we have, they're applied on top
+    /// one at a time; otherwise history changed, and the state is rebuilt
+    /// once at the end rather than once per operation.
+    pub fn merge(&mut self, other: &Self) {
+        let newest = self.log.keys().next_back().copied();
+
+        // The log is in stamp order, so these are too
+        let incoming: Vec<(Stamp, &Operation<T>)> = other.log.iter()
+            .filter(|(stamp, _)| !self.log.contains_key(stamp))
+            .map(|(stamp, operation)| (*stamp, operation))
+            .collect();
+
+        let in_order = match (newest, incoming.first()) {
+            (_, None)                        => return,
+            (None, _)                        => true,
+            (Some(newest), Some((first, _))) => *first > newest,
+        };
+
+        for (stamp, operation) in incoming {
+            if stamp.counter > self.clock {
+                self.clock = stamp.counter;
+            }
+
+            self.log.insert(stamp, operation.clone());
+
+            if in_order {
+                self.replay_one(stamp, operation.clone());
+            }
+        }
+
+        if !in_order {
+            self.rebuild();
+        }
+    }
+
+    /// Iterate over every known operation, in stamp order.
+    pub fn operations(&self) -> impl Iterator<Item=(&Stamp, &Operation<T>)> {
+        self.log.iter()
+    }
+
+    /// Rebuild the state from scratch by replaying the whole log.
+    fn rebuild(&mut self) {
+        self.state = BumpyVector::new(self.state.max_size());
+        self.positions.clear();
+
+        let log: Vec<(Stamp, Operation<T>)> = self.log.iter().map(|(s, o)| (*s, o.clone())).collect();
+        for (stamp, operation) in log {
+            self.replay_one(stamp, operation);
+        }
+    }
+
+    /// Apply one operation to the current state.
+    fn replay_one(&mut self, stamp: Stamp, operation: Operation<T>) {
+        match operation {
+            Operation::Insert(entry) => {
+                let incoming = BumpyEntry {
+                    entry: Stamped { stamp, value: entry.entry },
+                    range: entry.range,
+                };
+
+                if incoming.range.is_empty() || incoming.range.end > self.state.max_size() {
+                    return;
+                }
+
+                // Find everything in the way, and make sure the policy lets
+                // us replace all of it
+                let overlapping: Vec<(usize, Stamp)> = self.state.get_range(incoming.range.clone())
+                    .into_iter()
+                    .filter(|existing| {
+                        match self.policy {
+                            ConflictPolicy::LastWriterWins => false,
+                            ConflictPolicy::Custom(f) => f(existing, &incoming) == Resolution::KeepExisting,
+                        }
+                    })
+                    .map(|existing| (existing.range.start, existing.entry.stamp))
+                    .collect();
+
+                if !overlapping.is_empty() {
+                    return;
+                }
+
+                for existing in self.state.remove_range(incoming.range.clone()) {
+                    self.positions.remove(&existing.entry.stamp);
+                }
+
+                self.positions.insert(stamp, incoming.range.start);
+
+                // This can't fail - we verified the range and cleared the way
+                if self.state.insert(incoming).is_err() {
+                    unreachable!("Verified entry failed to insert");
+                }
+            },
+
+            Operation::Remove(target) => {
+                if let Some(start) = self.positions.remove(&target) {
+                    self.state.remove(start);
+                }
+            },
+        }
+    }
+
+    /// Return a reference to the entry at the given index.
+    ///
+    /// See `BumpyVector::get()`.
+    pub fn get(&self, index: usize) -> Option<&BumpyEntry<Stamped<T>>> {
+        self.state.get(index)
+    }
+
+    /// Return the number of live entries.
+    pub fn len(&self) -> usize {
+        self.state.len()
+    }
+
+    /// Return `true` if there are no live entries.
+    pub fn is_empty(&self) -> bool {
+        self.state.len() == 0
+    }
+
+    /// Return the converged state as a read-only `BumpyVector`.
+    pub fn vector(&self) -> &BumpyVector<Stamped<T>> {
+        &self.state
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn layout(v: &CrdtBumpyVector<&'static str>) -> Vec<(&'static str, std::ops::Range<usize>)> {
+        v.vector().into_iter().map(|e| (e.entry.value, e.range.clone())).collect()
+    }
+
+    #[test]
+    fn test_local_edits() {
+        let mut a: CrdtBumpyVector<&str> = CrdtBumpyVector::new(1, 100);
+
+        assert!(a.insert(("hello", 0..10).into()).is_ok());
+        assert!(a.insert(("error", 5..15).into()).is_err());
+        assert!(a.insert(("error", 95..105).into()).is_err());
+        assert!(a.insert(("error", 10..10).into()).is_err());
+        assert_eq!(1, a.len());
+
+        assert!(a.remove(5).is_some());
+        assert!(a.remove(5).is_none());
+        assert!(a.is_empty());
+    }
+
+    #[test]
+    fn test_convergence() {
+        let mut a: CrdtBumpyVector<&str> = CrdtBumpyVector::new(1, 100);
+        let mut b: CrdtBumpyVector<&str> = CrdtBumpyVector::new(2, 100);
+        let mut c: CrdtBumpyVector<&str> = CrdtBumpyVector::new(3, 100);
+
+        a.insert(("a1", 0..10).into()).unwrap();
+        a.insert(("a2", 20..30).into()).unwrap();
+        b.insert(("b1", 5..25).into()).unwrap();
+        c.insert(("c1", 50..60).into()).unwrap();
+        c.remove(50).unwrap();
+        c.insert(("c2", 55..65).into()).unwrap();
+
+        // Merge in different orders
+        let mut x = a.clone();
+        x.merge(&b);
+        x.merge(&c);
+
+        let mut y = c.clone();
+        y.merge(&b);
+        y.merge(&a);
+
+        let mut z = b.clone();
+        z.merge(&c);
+        z.merge(&a);
+        z.merge(&a);
+
+        assert_eq!(layout(&x), layout(&y));
+        assert_eq!(layout(&x), layout(&z));
+
+        // "b1" evicted "a1", but "a2" was written later and evicted "b1"
+        assert_eq!(vec![("a2", 20..30), ("c2", 55..65)], layout(&x));
+
+        // Merging keeps the clock ahead of everything it's seen
+        let newest = *x.operations().map(|(stamp, _)| stamp).max().unwrap();
+        assert!(y.insert(("y1", 90..95).into()).unwrap() > newest);
+    }
+
+    #[test]
+    fn test_remove_only_targets_original_entry() {
+        let mut a: CrdtBumpyVector<&str> = CrdtBumpyVector::new(1, 100);
+        a.insert(("old", 0..10).into()).unwrap();
+
+        // A second client that starts from a's state
+        let mut b: CrdtBumpyVector<&str> = CrdtBumpyVector::new(2, 100);
+        b.merge(&a);
+        assert_eq!(layout(&a), layout(&b));
+
+        // a removes the entry while b, concurrently, replaces it
+        let removed_a = a.remove(0).unwrap();
+        let removed_b = b.remove(0).unwrap();
+        let replaced = b.insert(("new", 0..10).into()).unwrap();
+        assert_eq!(removed_a.counter, removed_b.counter);
+
+        // At the same time as b's insert, a inserts something overlapping it.
+        // The counters tie, so the higher site wins
+        let overlapping = a.insert(("overlapping", 5..15).into()).unwrap();
+        assert_eq!(replaced.counter, overlapping.counter);
+        assert!(replaced > overlapping);
+
+        // Merging either way round gives the same answer
+        let mut x = a.clone();
+        x.merge(&b);
+        let mut y = b.clone();
+        y.merge(&a);
+
+        assert_eq!(vec![("new", 0..10)], layout(&x));
+        assert_eq!(layout(&x), layout(&y));
+
+        a.merge(&b);
+        b.merge(&a);
+        assert_eq!(layout(&x), layout(&a));
+        assert_eq!(layout(&x), layout(&b));
+    }
+
+    #[test]
+    fn test_custom_policy() {
+        // Keep whichever entry is longer
+        fn longest(existing: &BumpyEntry<Stamped<&str>>, incoming: &BumpyEntry<Stamped<&str>>) -> Resolution {
+            if incoming.range.len() > existing.range.len() {
+                Resolution::TakeIncoming
+            } else {
+                Resolution::KeepExisting
+            }
+        }
+
+        let mut a: CrdtBumpyVector<&str> = CrdtBumpyVector::with_policy(1, 100, ConflictPolicy::Custom(longest));
+        let mut b: CrdtBumpyVector<&str> = CrdtBumpyVector::with_policy(2, 100, ConflictPolicy::Custom(longest));
+
+        a.insert(("long", 0..20).into()).unwrap();
+        b.insert(("short", 5..10).into()).unwrap();
+
+        a.merge(&b);
+        b.merge(&a);
+
+        assert_eq!(vec![("long", 0..20)], layout(&a));
+        assert_eq!(layout(&a), layout(&b));
+    }
+}
@@ -0,0 +1,309 @@
+//! The errors that `BumpyVector` and friends can return.
+//!
+//! Every fallible function returns a `BumpyError`, so callers can tell what
+//! went wrong by matching on it instead of on the message. Inserts wrap it
+//! in an `InsertError`, which also hands back the entry.
+//!
+//! # Example
+//!
+//! ```
+//! use bumpy_vector::{BumpyError, BumpyVector};
+//!
+//! let mut v: BumpyVector<&str> = BumpyVector::new(10);
+//! v.insert(("hello", 0..5).into()).unwrap();
+//!
+//! match v.get_range_strict(5..11) {
+//!     Err(BumpyError::OutOfBounds) => (),
+//!     _ => panic!("That should have been out of bounds"),
+//! }
+//!
+//! assert_eq!("Invalid entry: entry exceeds max size", v.insert(("!", 9..11).into()).unwrap_err().to_string());
+//! ```
+
+use std::fmt;
+
+use crate::BumpyEntry;
+
+/// Everything that can go wrong.
+#[derive(Debug)]
+pub enum BumpyError {
+    /// An entry has an empty range.
+    ZeroSize,
+
+    /// An entry or range goes past `max_size`.
+    OutOfBounds,
+
+    /// An entry overlaps another one.
+    Overlap,
+
+    /// An entry overlaps a locked range (see `BumpyVector::lock_range()`).
+    Locked,
+
+    /// The vector is read-only (see `BumpyVector::set_read_only()`).
+    ReadOnly,
+
+    /// A range ends before it starts.
+    InvalidRange,
+
+    /// There's no empty space big enough for an entry.
+    NoRoom,
+
+    /// There's no entry at an index.
+    NotFound,
+
+    /// An entry is pinned, so it can't move (see `BumpyVector::pin()`).
+    Pinned,
+
+    /// An offset isn't a whole number of units (see
+    /// `BumpyVector::convert_units()`).
+    Misaligned,
+
+    /// A value isn't what it was expected to be (see
+    /// `BumpyVector::compare_and_replace()`).
+    Mismatch,
+
+    /// A number doesn't fit in the type it needs to be stored as.
+    Overflow,
+
+    /// A builder wasn't given something it needs; this names it.
+    MissingField(&'static str),
+
+    /// Reading or writing failed.
+    Io(std::io::Error),
+
+    /// Something went wrong on a line of input, numbered from 1.
+    Line(usize, Box<BumpyError>),
+
+    /// Something went wrong in a record of binary input, numbered from 0.
+    Record(usize, Box<BumpyError>),
+
+    /// Anything else, described in words.
+    Other(String),
+}
+
+/// A `Result` with a `BumpyError`.
+pub type BumpyResult<T> = Result<T, BumpyError>;
+
+impl fmt::Display for BumpyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BumpyError::ZeroSize          => write!(f, "An entry can't have an empty range"),
+            BumpyError::OutOfBounds       => write!(f, "Invalid entry: entry exceeds max size"),
+            BumpyError::Overlap           => write!(f, "Invalid entry: overlaps another object"),
+            BumpyError::Locked            => write!(f, "Invalid entry: overlaps a locked range"),
+            BumpyError::ReadOnly          => write!(f, "Vector is read-only"),
+            BumpyError::InvalidRange      => write!(f, "Invalid range: ends before it starts"),
+            BumpyError::NoRoom            => write!(f, "No room for an entry that size"),
+            BumpyError::NotFound          => write!(f, "No entry at that index"),
+            BumpyError::Pinned            => write!(f, "Entry is pinned"),
+            BumpyError::Misaligned        => write!(f, "Offset isn't a whole number of units"),
+            BumpyError::Mismatch          => write!(f, "Value has changed"),
+            BumpyError::Overflow          => write!(f, "Value is too large"),
+            BumpyError::MissingField(s)   => write!(f, "Invalid entry: no {}", s),
+            BumpyError::Io(e)             => write!(f, "I/O error: {}", e),
+            BumpyError::Line(number, e)   => write!(f, "Line {}: {}", number, e),
+            BumpyError::Record(number, e) => write!(f, "Record {}: {}", number, e),
+            BumpyError::Other(s)          => write!(f, "{}", s),
+        }
+    }
+}
+
+impl std::error::Error for BumpyError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            BumpyError::Io(e)        => Some(e),
+            BumpyError::Line(_, e)   => Some(e.as_ref()),
+            BumpyError::Record(_, e) => Some(e.as_ref()),
+            _                        => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for BumpyError {
+    fn from(e: std::io::Error) -> Self {
+        BumpyError::Io(e)
+    }
+}
+
+impl From<String> for BumpyError {
+    fn from(s: String) -> Self {
+        BumpyError::Other(s)
+    }
+}
+
+impl From<&str> for BumpyError {
+    fn from(s: &str) -> Self {
+        BumpyError::Other(s.to_string())
+    }
+}
+
+/// An entry that couldn't be inserted, handed back so it isn't lost.
+///
+/// Converts into the `BumpyError` it wraps, so `?` works in functions that
+/// return a `BumpyResult`.
+///
+/// # Example
+///
+/// ```
+/// use bumpy_vector::{BumpyError, BumpyVector};
+///
+/// let mut v: BumpyVector<String> = BumpyVector::new(10);
+/// v.insert((String::from("first"), 0..5).into()).unwrap();
+///
+/// let e = v.insert((String::from("second"), 4..8).into()).unwrap_err();
+/// assert!(matches!(e.reason, BumpyError::Overlap));
+///
+/// // Try again somewhere else
+/// v.insert((e.entry.entry, 5..9).into()).unwrap();
+/// ```
+#[derive(Debug)]
+pub struct InsertError<T> {
+    /// The entry that wasn't inserted.
+    pub entry: BumpyEntry<T>,
+
+    /// Why not.
+    pub reason: BumpyError,
+}
+
+impl<T> fmt::Display for InsertError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.reason.fmt(f)
+    }
+}
+
+impl<T> std::error::Error for InsertError<T>
+where
+    T: fmt::Debug
+{
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.reason.source()
+    }
+}
+
+impl<T> From<InsertError<T>> for BumpyError {
+    fn from(e: InsertError<T>) -> Self {
+        e.reason
+    }
+}
+
+/// A value that couldn't be swapped in by
+/// `BumpyVector::compare_and_replace()`, handed back so it isn't lost.
+///
+/// Converts into the `BumpyError` it wraps.
+#[derive(Debug)]
+pub struct CasError<T> {
+    /// The new value, which wasn't stored.
+    pub value: T,
+
+    /// Why not.
+    pub reason: BumpyError,
+}
+
+impl<T> fmt::Display for CasError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.reason.fmt(f)
+    }
+}
+
+impl<T> std::error::Error for CasError<T>
+where
+    T: fmt::Debug
+{
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.reason.source()
+    }
+}
+
+impl<T> From<CasError<T>> for BumpyError {
+    fn from(e: CasError<T>) -> Self {
+        e.reason
+    }
+}
+
+/// A batch of entries that couldn't be inserted, handed back so they aren't
+/// lost; see `BumpyVector::insert_many()`.
+///
+/// Converts into the first rejected entry's `BumpyError`.
+#[derive(Debug)]
+pub struct BulkInsertError<T> {
+    /// Every entry in the batch, in the order they were given. None of them
+    /// were inserted.
+    pub entries: Vec<BumpyEntry<T>>,
+
+    /// The position (in `entries`) of each entry that wouldn't fit, and why.
+    /// This is never empty.
+    pub rejected: Vec<(usize, BumpyError)>,
+}
+
+impl<T> fmt::Display for BulkInsertError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} of {} entries couldn't be inserted", self.rejected.len(), self.entries.len())
+    }
+}
+
+impl<T> std::error::Error for BulkInsertError<T>
+where
+    T: fmt::Debug
+{
+}
+
+impl<T> From<BulkInsertError<T>> for BumpyError {
+    fn from(e: BulkInsertError<T>) -> Self {
+        match e.rejected.into_iter().next() {
+            Some((_, reason)) => reason,
+            None => BumpyError::Other(String::from("Bulk insert failed")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::error::Error;
+    use crate::BumpyVector;
+    use std::ops::Range;
+
+    #[test]
+    fn test_errors() {
+        assert_eq!("Invalid entry: no size", BumpyError::MissingField("size").to_string());
+        assert_eq!("hi", BumpyError::from("hi").to_string());
+
+        let e = BumpyError::Line(3, Box::new(BumpyError::Overlap));
+        assert_eq!("Line 3: Invalid entry: overlaps another object", e.to_string());
+        assert!(matches!(e.source().unwrap().downcast_ref::<BumpyError>(), Some(BumpyError::Overlap)));
+
+        let e = BumpyError::Record(0, Box::new(BumpyError::OutOfBounds));
+        assert_eq!("Record 0: Invalid entry: entry exceeds max size", e.to_string());
+        assert!(e.source().is_some());
+
+        let e: BumpyError = std::io::Error::other("disk on fire").into();
+        assert!(matches!(e, BumpyError::Io(_)));
+        assert!(e.source().is_some());
+        assert!(BumpyError::ZeroSize.source().is_none());
+    }
+
+    #[test]
+    fn test_insert_errors() {
+        let mut h: BumpyVector<&str> = BumpyVector::new(100);
+        h.insert(("a", 10..20).into()).unwrap();
+        h.lock_range(50..60);
+
+        assert!(matches!(h.insert(("b", 15..25).into()), Err(InsertError { reason: BumpyError::Overlap, .. })));
+        assert!(matches!(h.insert(("b", 5..11).into()), Err(InsertError { reason: BumpyError::Overlap, .. })));
+        assert!(matches!(h.insert(("b", 95..101).into()), Err(InsertError { reason: BumpyError::OutOfBounds, .. })));
+        assert!(matches!(h.insert(("b", 30..30).into()), Err(InsertError { reason: BumpyError::ZeroSize, .. })));
+        assert!(matches!(h.insert(("b", 55..56).into()), Err(InsertError { reason: BumpyError::Locked, .. })));
+        assert!(matches!(h.get_range_strict(Range { start: 5, end: 1 }), Err(BumpyError::InvalidRange)));
+
+        h.set_read_only(true);
+        assert!(matches!(h.insert(("b", 30..40).into()), Err(InsertError { reason: BumpyError::ReadOnly, .. })));
+
+        // The entry comes back, and the error can become a BumpyError
+        let e = h.insert(("b", 30..40).into()).unwrap_err();
+        assert_eq!("b", e.entry.entry);
+        assert_eq!(30..40, e.entry.range);
+        assert_eq!("Vector is read-only", e.to_string());
+        assert!(matches!(BumpyError::from(e), BumpyError::ReadOnly));
+    }
+}
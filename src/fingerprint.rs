@@ -0,0 +1,172 @@
+//! Fingerprint part of a vector.
+//!
+//! A UI that caches whatever it rendered for a viewport needs to know when
+//! that part of the vector has changed. Comparing every entry is about as
+//! slow as rendering it again; comparing a fingerprint is cheap.
+//!
+//! The same goes for a whole vector: `content_hash()` is a cheap key for
+//! caching results, or noticing two analysis runs came up with the same
+//! layout.
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::ops::Range;
+
+use crate::BumpyVector;
+
+impl<T> BumpyVector<T>
+where
+    T: Hash
+{
+    /// Feed the layout and values of every entry within `range` into
+    /// `hasher`, and return the result.
+    ///
+    /// The entries are the same ones `get_range()` would return, so an entry
+    /// that's only partly inside `range` counts - including the part that's
+    /// outside. Nothing else about the vector affects the fingerprint.
+    ///
+    /// The fingerprint is only as stable as `hasher`: use one with fixed
+    /// keys, like `DefaultHasher::new()`, rather than a randomly-seeded one.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::collections::hash_map::DefaultHasher;
+    /// use bumpy_vector::BumpyVector;
+    ///
+    /// let mut v: BumpyVector<&str> = BumpyVector::new(100);
+    /// v.insert(("a", 0..10).into()).unwrap();
+    /// v.insert(("b", 50..60).into()).unwrap();
+    ///
+    /// let before = v.fingerprint_range(0..20, DefaultHasher::new());
+    ///
+    /// // Changing something somewhere else doesn't matter
+    /// v.remove(50);
+    /// assert_eq!(before, v.fingerprint_range(0..20, DefaultHasher::new()));
+    ///
+    /// // But changing something inside it does
+    /// v.insert(("c", 15..20).into()).unwrap();
+    /// assert_ne!(before, v.fingerprint_range(0..20, DefaultHasher::new()));
+    /// ```
+    pub fn fingerprint_range<H>(&self, range: Range<usize>, mut hasher: H) -> u64
+    where
+        H: Hasher
+    {
+        let mut count: usize = 0;
+
+        for e in self.iter_range(range) {
+            e.range.start.hash(&mut hasher);
+            e.range.end.hash(&mut hasher);
+            e.entry.hash(&mut hasher);
+
+            count += 1;
+        }
+
+        // So that no list of entries is a prefix of another
+        count.hash(&mut hasher);
+
+        hasher.finish()
+    }
+
+    /// Hash `max_size` and every entry.
+    ///
+    /// Two vectors with the same hash almost certainly have equal layouts and
+    /// values (see `PartialEq`), no matter what order the entries were
+    /// inserted in. Nothing else, like locks or history, affects it.
+    ///
+    /// This uses `DefaultHasher::new()`, which has fixed keys, so the hash is
+    /// the same every run; but it's only guaranteed to stay the same for a
+    /// given version of Rust, so don't save it anywhere permanent.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bumpy_vector::BumpyVector;
+    ///
+    /// let mut a: BumpyVector<&str> = BumpyVector::new(100);
+    /// a.insert(("a", 0..10).into()).unwrap();
+    /// a.insert(("b", 50..60).into()).unwrap();
+    ///
+    /// let mut b: BumpyVector<&str> = BumpyVector::new(100);
+    /// b.insert(("b", 50..60).into()).unwrap();
+    /// b.insert(("a", 0..10).into()).unwrap();
+    ///
+    /// assert_eq!(a.content_hash(), b.content_hash());
+    /// ```
+    pub fn content_hash(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.hash(&mut hasher);
+
+        hasher.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use crate::BumpyEntry;
+
+    fn fingerprint(h: &BumpyVector<String>, range: Range<usize>) -> u64 {
+        h.fingerprint_range(range, DefaultHasher::new())
+    }
+
+    #[test]
+    fn test_fingerprint_range() {
+        let mut h: BumpyVector<String> = BumpyVector::new(100);
+        h.insert((String::from("a"), 10..20).into()).unwrap();
+        h.insert((String::from("b"), 20..30).into()).unwrap();
+
+        let before = fingerprint(&h, 15..25);
+
+        // The same layout and values always fingerprint the same way
+        let mut other: BumpyVector<String> = BumpyVector::new(1000);
+        other.insert((String::from("b"), 20..30).into()).unwrap();
+        other.insert((String::from("a"), 10..20).into()).unwrap();
+        other.insert((String::from("c"), 500..510).into()).unwrap();
+        assert_eq!(before, fingerprint(&other, 15..25));
+        assert_eq!(before, fingerprint(&h, 10..30));
+
+        // Values matter
+        *h.get_mut(10).unwrap().entry = String::from("A");
+        assert_ne!(before, fingerprint(&h, 15..25));
+        *h.get_mut(10).unwrap().entry = String::from("a");
+        assert_eq!(before, fingerprint(&h, 15..25));
+
+        // So does the layout
+        let b = h.remove(20).unwrap();
+        h.insert((b.entry, 21..30).into()).unwrap();
+        assert_ne!(before, fingerprint(&h, 15..25));
+
+        // Empty ranges are all alike
+        assert_eq!(fingerprint(&h, 0..10), fingerprint(&h, 15..15));
+        assert_ne!(fingerprint(&h, 0..10), fingerprint(&h, 10..11));
+    }
+
+    #[test]
+    fn test_content_hash() {
+        let mut h: BumpyVector<String> = BumpyVector::new(100);
+        h.insert((String::from("a"), 10..20).into()).unwrap();
+        h.insert((String::from("b"), 20..30).into()).unwrap();
+        let before = h.content_hash();
+
+        // Only the layout and values matter
+        let mut other = h.clone();
+        other.lock_range(0..100);
+        other.pin(10);
+        assert_eq!(before, other.content_hash());
+
+        *h.get_mut(10).unwrap().entry = String::from("A");
+        assert_ne!(before, h.content_hash());
+        *h.get_mut(10).unwrap().entry = String::from("a");
+        assert_eq!(before, h.content_hash());
+
+        let mut bigger: BumpyVector<String> = BumpyVector::new(101);
+        bigger.extend(h.iter().cloned());
+        assert_ne!(before, bigger.content_hash());
+
+        // Entries can go in a set, too
+        let entries: std::collections::HashSet<BumpyEntry<String>> = h.into_iter().collect();
+        assert!(entries.contains(&(String::from("b"), 20..30).into()));
+    }
+}
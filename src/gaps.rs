@@ -0,0 +1,170 @@
+//! Find the gaps between entries.
+//!
+//! A gap is a run of indexes with no entry, as long as it can be: it ends
+//! where the next entry starts (or the end of the range being searched).
+//! Gaps are found as the iterator goes, so asking for the first big enough
+//! gap in a huge, mostly-full vector doesn't look at everything.
+//!
+//! Locks don't matter here - a locked gap is still a gap. To find somewhere
+//! an entry can actually be inserted, use `find_room()` instead.
+//!
+//! # Example
+//!
+//! ```
+//! use bumpy_vector::BumpyVector;
+//!
+//! // A .text section from 0x1000 to 0x2000 that's mostly full
+//! let mut v: BumpyVector<&str> = BumpyVector::new(0x3000);
+//! v.insert(("main", 0x1000..0x1400).into()).unwrap();
+//! v.insert(("padding?", 0x1410..0x1800).into()).unwrap();
+//! v.insert(("helper", 0x1900..0x2000).into()).unwrap();
+//!
+//! // Where would a 0x80-byte payload fit?
+//! let room: Vec<_> = v.gaps_in(0x1000..0x2000, 0x80).collect();
+//! assert_eq!(vec![0x1800..0x1900], room);
+//! ```
+
+use std::cmp::min;
+use std::ops::Range;
+
+use crate::BumpyVector;
+
+/// A lazy iterator over the gaps in a range; see `BumpyVector::gaps_in()`.
+#[derive(Debug, Clone)]
+pub struct Gaps<'a, T> {
+    vector: &'a BumpyVector<T>,
+
+    /// Where the next gap could start.
+    position: usize,
+
+    /// Where the search stops.
+    end: usize,
+
+    /// How long a gap has to be to be returned.
+    min_size: usize,
+}
+
+impl<'a, T> Iterator for Gaps<'a, T> {
+    type Item = Range<usize>;
+
+    fn next(&mut self) -> Option<Range<usize>> {
+        while self.position < self.end {
+            let gap = match self.vector.data.range(self.position..self.end).next() {
+                Some((_, e)) => {
+                    let gap = self.position..e.range.start;
+                    self.position = e.range.end;
+
+                    gap
+                },
+                None => {
+                    let gap = self.position..self.end;
+                    self.position = self.end;
+
+                    gap
+                },
+            };
+
+            if !gap.is_empty() && gap.len() >= self.min_size {
+                return Some(gap);
+            }
+        }
+
+        None
+    }
+}
+
+impl<T> BumpyVector<T> {
+    /// Iterate over every gap, in order.
+    ///
+    /// This is `gaps_in()` over the whole vector, with no minimum size. Each
+    /// gap is a range, so its length is just `len()`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bumpy_vector::BumpyVector;
+    ///
+    /// let mut v: BumpyVector<&str> = BumpyVector::new(10);
+    /// v.insert(("a", 2..4).into()).unwrap();
+    /// v.insert(("b", 4..6).into()).unwrap();
+    ///
+    /// let gaps: Vec<(usize, usize)> = v.gaps().map(|r| (r.start, r.len())).collect();
+    /// assert_eq!(vec![(0, 2), (6, 4)], gaps);
+    /// ```
+    pub fn gaps(&self) -> Gaps<'_, T> {
+        self.gaps_in(0..self.max_size, 0)
+    }
+
+    /// Iterate over the gaps within `range` that are at least `min_size`
+    /// long, in order.
+    ///
+    /// Gaps are cut off at the edges of `range` (and at `max_size`), and
+    /// they're measured after they're cut, so a big gap that's mostly
+    /// outside of `range` might not be returned. A `min_size` of `0` returns
+    /// every gap.
+    pub fn gaps_in(&self, range: Range<usize>, min_size: usize) -> Gaps<'_, T> {
+        let end = min(range.end, self.max_size);
+
+        // If the range starts inside an entry, the first gap is after it
+        let position = match self.get_entry_start(range.start).and_then(|start| self.data.get(&start)) {
+            Some(e) => e.range.end,
+            None    => range.start,
+        };
+
+        Gaps {
+            vector: self,
+            position,
+            end,
+            min_size,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_gaps_in() {
+        // [--0-- --1-- --2-- --3-- --4-- --5-- --6-- --7-- --8-- --9--]
+        //        +-----------------            +----------------+
+        //        |   "a" (2)| "b" |            |      "c"       |
+        //        +----------+------            +----------------+
+        let mut h: BumpyVector<&str> = BumpyVector::new(10);
+        h.insert(("a", 1..3).into()).unwrap();
+        h.insert(("b", 3..4).into()).unwrap();
+        h.insert(("c", 6..9).into()).unwrap();
+
+        assert_eq!(vec![0..1, 4..6, 9..10], h.gaps_in(0..10, 0).collect::<Vec<_>>());
+        assert_eq!(vec![4..6], h.gaps_in(0..10, 2).collect::<Vec<_>>());
+        assert_eq!(0, h.gaps_in(0..10, 3).count());
+
+        // Gaps are cut to fit the range
+        assert_eq!(vec![4..5], h.gaps_in(2..5, 1).collect::<Vec<_>>());
+        assert_eq!(0, h.gaps_in(2..5, 2).count());
+        assert_eq!(vec![5..6, 9..10], h.gaps_in(5..100, 0).collect::<Vec<_>>());
+
+        // Starting and ending inside entries
+        assert_eq!(vec![4..6], h.gaps_in(2..7, 0).collect::<Vec<_>>());
+
+        // Nothing in an empty range, or past the end
+        assert_eq!(0, h.gaps_in(5..5, 0).count());
+        assert_eq!(0, h.gaps_in(10..20, 0).count());
+        assert_eq!(0, h.gaps_in(Range { start: 7, end: 2 }, 0).count());
+
+        // Locks don't count
+        h.lock_range(4..6);
+        assert_eq!(vec![4..6], h.gaps_in(0..10, 2).collect::<Vec<_>>());
+
+        assert_eq!(h.gaps_in(0..10, 0).collect::<Vec<_>>(), h.gaps().collect::<Vec<_>>());
+
+        // An empty vector is one big gap
+        let h: BumpyVector<&str> = BumpyVector::new(1_000_000);
+        assert_eq!(Some(0..1_000_000), h.gaps_in(0..1_000_000, 1).next());
+        assert_eq!(vec![0..1_000_000], h.gaps().collect::<Vec<_>>());
+
+        let h: BumpyVector<&str> = BumpyVector::new(0);
+        assert_eq!(0, h.gaps().count());
+    }
+}
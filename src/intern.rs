@@ -0,0 +1,273 @@
+//! Share repeated values between entries.
+//!
+//! Real binaries are full of repetition - the same "padding byte" or
+//! "undefined" annotation might show up millions of times. Storing each one
+//! separately wastes a lot of memory, so an `Interner` keeps a single copy
+//! of each distinct value and hands out cheap `Interned<T>` handles to it.
+//!
+//! # Example
+//!
+//! ```
+//! use bumpy_vector::BumpyVector;
+//! use bumpy_vector::intern::{Interned, Interner};
+//!
+//! let mut interner: Interner<String> = Interner::new();
+//! let mut v: BumpyVector<Interned<String>> = BumpyVector::new(100);
+//!
+//! // Insert the same value a bunch of times
+//! for i in 0..10 {
+//!     v.insert_interned(&mut interner, String::from("padding"), (i * 10)..(i * 10 + 10)).unwrap();
+//! }
+//!
+//! // There are ten entries, but only one copy of the string
+//! assert_eq!(10, v.len());
+//! assert_eq!(1, interner.len());
+//! assert_eq!("padding", v.get(55).unwrap().entry.as_str());
+//! ```
+
+use std::collections::HashSet;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::ops::{Deref, Range};
+use std::sync::Arc;
+
+#[cfg(feature = "serialize")]
+use serde::{Serialize, Serializer, Deserialize, Deserializer};
+
+use crate::{BumpyEntry, BumpyVector, InsertError};
+
+/// A shared handle to an interned value.
+///
+/// Dereferences to the value itself. Cloning an `Interned<T>` is cheap, and
+/// comparing two handles to the same interned value doesn't need to look at
+/// the value at all.
+pub struct Interned<T>(Arc<T>);
+
+impl<T> Interned<T> {
+    /// Returns `true` if both handles point at the same stored value.
+    pub fn ptr_eq(a: &Self, b: &Self) -> bool {
+        Arc::ptr_eq(&a.0, &b.0)
+    }
+}
+
+impl<T> Clone for Interned<T> {
+    fn clone(&self) -> Self {
+        Interned(Arc::clone(&self.0))
+    }
+}
+
+impl<T> Deref for Interned<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> AsRef<T> for Interned<T> {
+    fn as_ref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T: PartialEq> PartialEq for Interned<T> {
+    fn eq(&self, other: &Self) -> bool {
+        Interned::ptr_eq(self, other) || *self.0 == *other.0
+    }
+}
+
+impl<T: Eq> Eq for Interned<T> {}
+
+impl<T: Hash> Hash for Interned<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.hash(state)
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Interned<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for Interned<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+/// Interned values are serialized as the value itself.
+///
+/// Deserializing creates a separate copy for every entry; use
+/// `Interner::intern_vector()` afterwards to share them again.
+#[cfg(feature = "serialize")]
+impl<T: Serialize> Serialize for Interned<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.0.serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serialize")]
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for Interned<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(Interned(Arc::new(T::deserialize(deserializer)?)))
+    }
+}
+
+/// Stores a single copy of each distinct value.
+#[derive(Debug, Clone)]
+pub struct Interner<T> {
+    values: HashSet<Arc<T>>,
+}
+
+impl<T> Default for Interner<T>
+where
+    T: Eq + Hash
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Interner<T>
+where
+    T: Eq + Hash
+{
+    /// Create an empty interner.
+    pub fn new() -> Self {
+        Interner {
+            values: HashSet::new(),
+        }
+    }
+
+    /// Get a handle to `value`, storing it if it hasn't been seen before.
+    pub fn intern(&mut self, value: T) -> Interned<T> {
+        if let Some(existing) = self.values.get(&value) {
+            return Interned(Arc::clone(existing));
+        }
+
+        let value = Arc::new(value);
+        self.values.insert(Arc::clone(&value));
+
+        Interned(value)
+    }
+
+    /// Make every entry in `vector` share this interner's copies.
+    ///
+    /// Useful after deserializing, or when merging vectors that were built
+    /// with different interners. Every value is swapped for one that's equal
+    /// to it, so this doesn't count as a change (see `generation()`). A
+    /// read-only vector is left alone.
+    pub fn intern_vector(&mut self, vector: &mut BumpyVector<Interned<T>>) {
+        if vector.is_read_only() {
+            return;
+        }
+
+        for e in vector.data.values_mut() {
+            if let Some(existing) = self.values.get(&e.entry.0) {
+                e.entry = Interned(Arc::clone(existing));
+            } else {
+                self.values.insert(Arc::clone(&e.entry.0));
+            }
+        }
+    }
+
+    /// Drop stored values that nothing refers to anymore.
+    pub fn purge(&mut self) {
+        self.values.retain(|v| Arc::strong_count(v) > 1);
+    }
+
+    /// The number of distinct values stored.
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Returns `true` if nothing has been interned.
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+}
+
+impl<T> BumpyVector<Interned<T>>
+where
+    T: Eq + Hash
+{
+    /// Intern `value`, then insert it as a new entry covering `range`.
+    ///
+    /// See `insert()` for return and errors; if the insert fails, the
+    /// interned value is handed back in the error. The value stays interned
+    /// until that's dropped too, then `Interner::purge()` will clean it up.
+    pub fn insert_interned(&mut self, interner: &mut Interner<T>, value: T, range: Range<usize>) -> Result<(), InsertError<Interned<T>>> {
+        self.insert(BumpyEntry {
+            entry: interner.intern(value),
+            range,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_interning() {
+        let mut interner: Interner<String> = Interner::new();
+
+        let a = interner.intern(String::from("a"));
+        let a2 = interner.intern(String::from("a"));
+        let b = interner.intern(String::from("b"));
+
+        assert!(Interned::ptr_eq(&a, &a2));
+        assert!(!Interned::ptr_eq(&a, &b));
+        assert_eq!(a, a2);
+        assert_ne!(a, b);
+        assert_eq!("a", a.as_str());
+        assert_eq!(2, interner.len());
+
+        // Dropping every handle lets purge() clean up
+        drop(b);
+        interner.purge();
+        assert_eq!(1, interner.len());
+    }
+
+    #[test]
+    fn test_insert_interned() {
+        let mut interner: Interner<&str> = Interner::new();
+        let mut v: BumpyVector<Interned<&str>> = BumpyVector::new(10);
+
+        v.insert_interned(&mut interner, "x", 0..2).unwrap();
+        v.insert_interned(&mut interner, "x", 2..4).unwrap();
+        // The error hands the value back, so it can go somewhere else
+        let e = v.insert_interned(&mut interner, "y", 3..5).unwrap_err();
+        assert_eq!("y", *e.entry.entry);
+        v.insert(BumpyEntry { entry: e.entry.entry, range: 4..6 }).unwrap();
+
+        assert!(Interned::ptr_eq(&v.get(0).unwrap().entry, &v.get(3).unwrap().entry));
+        assert_eq!(3, v.len());
+    }
+
+    #[test]
+    fn test_intern_vector() {
+        let mut v: BumpyVector<Interned<&str>> = BumpyVector::new(10);
+
+        // Build the entries with throwaway interners, so nothing is shared
+        v.insert_interned(&mut Interner::new(), "x", 0..2).unwrap();
+        v.insert_interned(&mut Interner::new(), "x", 2..4).unwrap();
+        assert!(!Interned::ptr_eq(&v.get(0).unwrap().entry, &v.get(2).unwrap().entry));
+
+        let mut interner: Interner<&str> = Interner::new();
+        interner.intern_vector(&mut v);
+
+        assert!(Interned::ptr_eq(&v.get(0).unwrap().entry, &v.get(2).unwrap().entry));
+        assert_eq!(1, interner.len());
+
+        // Read-only vectors aren't touched
+        v.insert_interned(&mut Interner::new(), "x", 4..6).unwrap();
+        let generation = v.generation();
+        v.set_read_only(true);
+        interner.intern_vector(&mut v);
+        assert!(!Interned::ptr_eq(&v.get(0).unwrap().entry, &v.get(4).unwrap().entry));
+        assert_eq!(generation, v.generation());
+    }
+}
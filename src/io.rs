@@ -0,0 +1,519 @@
+//! Import and export in formats other tools understand.
+//!
+//! Only available with the 'io' feature:
+//!
+//! ```toml
+//! bumpy_vector = { version = "~0.0.0", features = ["io"] }
+//! ```
+//!
+//! # Range lists
+//!
+//! Many reverse engineering tools can import and export annotations as a
+//! flat list of ranges, one per line:
+//!
+//! ```text
+//! start,end,label
+//! ```
+//!
+//! Where `start` and `end` are a half-open range (just like `Range<usize>`),
+//! and `label` is everything after the second delimiter. The delimiter is
+//! either a comma or a tab (see `RangeFormat`). When importing, numbers can
+//! be decimal or `0x`-prefixed hex, and blank lines and lines starting with
+//! `#` are ignored.
+//!
+//! # Coverage
+//!
+//! Overlaying execution coverage on a file's layout shows what's actually
+//! been run. Coverage from DynamoRIO's drcov (binary or text block tables),
+//! or from a plain list of `address size` lines, can be imported as a
+//! `BumpyVector<CoverageInfo>`. Blocks that overlap are merged, and their
+//! hits are added together.
+
+use std::io::{BufRead, Write};
+use std::ops::Range;
+
+use crate::{BumpyEntry, BumpyError, BumpyResult, BumpyVector};
+
+/// Which delimiter a range list uses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RangeFormat {
+    /// Comma-separated.
+    Csv,
+
+    /// Tab-separated.
+    Tsv,
+}
+
+impl RangeFormat {
+    fn delimiter(self) -> char {
+        match self {
+            RangeFormat::Csv => ',',
+            RangeFormat::Tsv => '\t',
+        }
+    }
+}
+
+/// Parse a decimal or `0x`-prefixed hex number.
+fn parse_number(s: &str) -> Option<usize> {
+    let s = s.trim();
+
+    match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        Some(hex) => usize::from_str_radix(hex, 16).ok(),
+        None => s.parse().ok(),
+    }
+}
+
+impl<T> BumpyVector<T> {
+    /// Write every entry as a `start,end,label` line, in order.
+    ///
+    /// `f` converts each value to its label. Labels can contain the
+    /// delimiter, but not newlines.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bumpy_vector::BumpyVector;
+    /// use bumpy_vector::io::RangeFormat;
+    ///
+    /// let mut v: BumpyVector<&str> = BumpyVector::new(100);
+    /// v.insert(("header", 0..16).into()).unwrap();
+    /// v.insert(("code, probably", 16..64).into()).unwrap();
+    ///
+    /// let mut out: Vec<u8> = Vec::new();
+    /// v.export_ranges(&mut out, RangeFormat::Csv, |e| e.to_string()).unwrap();
+    ///
+    /// assert_eq!("0,16,header\n16,64,code, probably\n", String::from_utf8(out).unwrap());
+    /// ```
+    pub fn export_ranges<W, F>(&self, writer: &mut W, format: RangeFormat, mut f: F) -> BumpyResult<()>
+    where
+        W: Write,
+        F: FnMut(&T) -> String,
+    {
+        let delimiter = format.delimiter();
+
+        for e in self {
+            let label = f(&e.entry);
+
+            if label.contains('\n') || label.contains('\r') {
+                return Err(BumpyError::Other(format!("Label for {:?} contains a newline", e.range)));
+            }
+
+            writeln!(writer, "{}{}{}{}{}", e.range.start, delimiter, e.range.end, delimiter, label)?;
+        }
+
+        Ok(())
+    }
+
+    /// Read a list of `start,end,label` lines into a new vector.
+    ///
+    /// `parser` converts each label into a value.
+    ///
+    /// # Return
+    ///
+    /// Returns the new vector, or an error naming the first line that
+    /// couldn't be read, parsed, or inserted.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bumpy_vector::BumpyVector;
+    /// use bumpy_vector::io::RangeFormat;
+    ///
+    /// let input = "# exported from somewhere\n0x0\t0x10\theader\n16\t64\tcode\n";
+    ///
+    /// let v: BumpyVector<String> = BumpyVector::import_ranges(input.as_bytes(), 100, RangeFormat::Tsv, |label| {
+    ///     Ok(label.to_string())
+    /// }).unwrap();
+    ///
+    /// assert_eq!(2, v.len());
+    /// assert_eq!("header", v.get(10).unwrap().entry);
+    /// ```
+    pub fn import_ranges<R, F>(reader: R, max_size: usize, format: RangeFormat, mut parser: F) -> BumpyResult<Self>
+    where
+        R: BufRead,
+        F: FnMut(&str) -> BumpyResult<T>,
+    {
+        let mut result = Self::new(max_size);
+
+        for (number, line) in reader.lines().enumerate() {
+            let line = line?;
+            let number = number + 1;
+
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let mut fields = line.splitn(3, format.delimiter());
+            let start = fields.next().and_then(parse_number);
+            let end = fields.next().and_then(parse_number);
+            let label = fields.next().unwrap_or("");
+
+            let range = match (start, end) {
+                (Some(start), Some(end)) => start..end,
+                _ => return Err(BumpyError::Line(number, Box::new(BumpyError::Other(String::from("couldn't parse range"))))),
+            };
+
+            let entry = parser(label).map_err(|e| BumpyError::Line(number, Box::new(e)))?;
+
+            result.insert(BumpyEntry { entry, range }).map_err(|e| BumpyError::Line(number, Box::new(e.reason)))?;
+        }
+
+        Ok(result)
+    }
+}
+
+/// How often a block of code was executed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct CoverageInfo {
+    /// How many times the block showed up in the coverage data.
+    pub hits: u64,
+}
+
+/// Read one line, which might not be valid UTF-8, without the line ending.
+fn read_line<R: BufRead>(reader: &mut R) -> BumpyResult<Option<String>> {
+    let mut line = Vec::new();
+
+    if reader.read_until(b'\n', &mut line)? == 0 {
+        return Ok(None);
+    }
+
+    Ok(Some(String::from_utf8_lossy(&line).trim_end().to_string()))
+}
+
+/// Parse a text-mode drcov block, `module[  2]: 0x1000, 16`, into its
+/// module, offset, and size.
+fn parse_drcov_text(line: &str) -> Option<(usize, usize, usize)> {
+    let rest = line.trim().strip_prefix("module[")?;
+    let (module, rest) = rest.split_once(']')?;
+    let (offset, size) = rest.strip_prefix(':')?.split_once(',')?;
+
+    Some((module.trim().parse().ok()?, parse_number(offset)?, parse_number(size)?))
+}
+
+impl BumpyVector<CoverageInfo> {
+    /// Record one hit on `range`.
+    ///
+    /// If `range` overlaps any existing blocks, they're all merged into one
+    /// that covers the lot, with their hits added together.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bumpy_vector::BumpyVector;
+    /// use bumpy_vector::io::CoverageInfo;
+    ///
+    /// let mut v: BumpyVector<CoverageInfo> = BumpyVector::new(100);
+    /// v.record_coverage(0..10).unwrap();
+    /// v.record_coverage(0..10).unwrap();
+    /// v.record_coverage(8..12).unwrap();
+    ///
+    /// assert_eq!(1, v.len());
+    /// assert_eq!(0..12, v.get(0).unwrap().range);
+    /// assert_eq!(3, v.get(0).unwrap().entry.hits);
+    /// ```
+    pub fn record_coverage(&mut self, range: Range<usize>) -> BumpyResult<()> {
+        let mut merged = range.clone();
+        let mut hits = 1;
+
+        for e in self.get_range(range) {
+            merged.start = std::cmp::min(merged.start, e.range.start);
+            merged.end = std::cmp::max(merged.end, e.range.end);
+            hits += e.entry.hits;
+        }
+
+        // Make sure the merged block will fit before taking anything out
+        if self.is_read_only() {
+            return Err(BumpyError::ReadOnly);
+        }
+
+        if merged.is_empty() {
+            return Err(BumpyError::ZeroSize);
+        }
+
+        if merged.end > self.max_size() {
+            return Err(BumpyError::OutOfBounds);
+        }
+
+        if self.is_locked(&merged) {
+            return Err(BumpyError::Locked);
+        }
+
+        self.remove_range(merged.clone());
+        self.insert(BumpyEntry { entry: CoverageInfo { hits }, range: merged })?;
+
+        Ok(())
+    }
+
+    /// Read the blocks belonging to one module out of a drcov file.
+    ///
+    /// drcov records every block relative to the start of the module it's
+    /// in, so the vector covers a single module, numbered as in the file's
+    /// module table. Both the binary and the text (`-dump_text`) block
+    /// tables can be read.
+    ///
+    /// # Return
+    ///
+    /// Returns the new vector, or an error if the file can't be parsed or a
+    /// block doesn't fit in `max_size`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bumpy_vector::BumpyVector;
+    /// use bumpy_vector::io::CoverageInfo;
+    ///
+    /// let input = "DRCOV VERSION: 2\n\
+    ///              DRCOV FLAVOR: drcov\n\
+    ///              Module Table: version 2, count 2\n\
+    ///              Columns: id, base, end, entry, checksum, timestamp, path\n  \
+    ///                0, 0x400000, 0x401000, 0x0, 0x0, 0x0, /bin/true\n  \
+    ///                1, 0x7f0000, 0x7f8000, 0x0, 0x0, 0x0, /lib/libc.so\n\
+    ///              BB Table: 3 bbs\n\
+    ///              module[  0]: 0x100, 8\n\
+    ///              module[  1]: 0x100, 4\n\
+    ///              module[  0]: 0x104, 8\n";
+    ///
+    /// let v = BumpyVector::import_drcov(input.as_bytes(), 0x1000, 0).unwrap();
+    /// assert_eq!(1, v.len());
+    /// assert_eq!(0x100..0x10c, v.get(0x100).unwrap().range);
+    /// assert_eq!(2, v.get(0x100).unwrap().entry.hits);
+    /// ```
+    pub fn import_drcov<R>(mut reader: R, max_size: usize, module: usize) -> BumpyResult<Self>
+    where
+        R: BufRead
+    {
+        let mut result = Self::new(max_size);
+        let mut number = 0;
+
+        // Skip the header and module table, up to the block table
+        let count = loop {
+            number += 1;
+
+            let line = match read_line(&mut reader)? {
+                Some(line) => line,
+                None => return Err(BumpyError::Other(String::from("Missing drcov block table"))),
+            };
+
+            if number == 1 && !line.starts_with("DRCOV VERSION:") {
+                return Err(BumpyError::Line(number, Box::new(BumpyError::Other(String::from("not a drcov file")))));
+            }
+
+            if let Some(rest) = line.strip_prefix("BB Table:") {
+                match rest.split_whitespace().next().and_then(|n| n.parse::<usize>().ok()) {
+                    Some(count) => break count,
+                    None => return Err(BumpyError::Line(number, Box::new(BumpyError::Other(String::from("couldn't parse block count"))))),
+                }
+            }
+        };
+
+        if reader.fill_buf()?.starts_with(b"module[") {
+            for _ in 0..count {
+                number += 1;
+
+                let line = match read_line(&mut reader)? {
+                    Some(line) => line,
+                    None => return Err(BumpyError::Other(String::from("drcov block table is too short"))),
+                };
+
+                let (m, offset, size) = match parse_drcov_text(&line) {
+                    Some(block) => block,
+                    None => return Err(BumpyError::Line(number, Box::new(BumpyError::Other(String::from("couldn't parse block"))))),
+                };
+
+                if m == module && size > 0 {
+                    let range = result.range_from(offset, size).map_err(|e| BumpyError::Line(number, Box::new(e)))?;
+                    result.record_coverage(range).map_err(|e| BumpyError::Line(number, Box::new(e)))?;
+                }
+            }
+        } else {
+            let mut block = [0u8; 8];
+
+            for i in 0..count {
+                reader.read_exact(&mut block)?;
+
+                // struct { u32 start; u16 size; u16 module; }, little endian
+                let offset = u32::from_le_bytes([block[0], block[1], block[2], block[3]]) as usize;
+                let size = u16::from_le_bytes([block[4], block[5]]) as usize;
+                let m = u16::from_le_bytes([block[6], block[7]]) as usize;
+
+                if m == module && size > 0 {
+                    let range = result.range_from(offset, size).map_err(|e| BumpyError::Record(i, Box::new(e)))?;
+                    result.record_coverage(range).map_err(|e| BumpyError::Record(i, Box::new(e)))?;
+                }
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// Read a list of `address size` lines, separated by whitespace or a
+    /// comma, as coverage.
+    ///
+    /// Each block's offset is its address minus `base`. As with range
+    /// lists, numbers can be decimal or `0x`-prefixed hex, and blank lines
+    /// and lines starting with `#` are ignored.
+    ///
+    /// # Return
+    ///
+    /// Returns the new vector, or an error naming the first line that
+    /// couldn't be read, parsed, or recorded.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bumpy_vector::BumpyVector;
+    /// use bumpy_vector::io::CoverageInfo;
+    ///
+    /// let input = "# address size\n0x401000 16\n0x40100c, 8\n0x402000 8\n";
+    ///
+    /// let v = BumpyVector::import_coverage_blocks(input.as_bytes(), 0x4000, 0x400000).unwrap();
+    /// assert_eq!(2, v.len());
+    /// assert_eq!(0x1000..0x1014, v.get(0x1000).unwrap().range);
+    /// ```
+    pub fn import_coverage_blocks<R>(reader: R, max_size: usize, base: usize) -> BumpyResult<Self>
+    where
+        R: BufRead
+    {
+        let mut result = Self::new(max_size);
+
+        for (number, line) in reader.lines().enumerate() {
+            let line = line?;
+            let number = number + 1;
+
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let mut fields = line.split(|c: char| c == ',' || c.is_whitespace()).filter(|f| !f.is_empty());
+            let address = fields.next().and_then(parse_number);
+            let size = fields.next().and_then(parse_number);
+
+            let (offset, size) = match (address, size) {
+                (Some(address), Some(size)) if address >= base => (address - base, size),
+                _ => return Err(BumpyError::Line(number, Box::new(BumpyError::Other(String::from("couldn't parse block"))))),
+            };
+
+            let range = result.range_from(offset, size).map_err(|e| BumpyError::Line(number, Box::new(e)))?;
+            result.record_coverage(range).map_err(|e| BumpyError::Line(number, Box::new(e)))?;
+        }
+
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_round_trip() {
+        let mut h: BumpyVector<String> = BumpyVector::new(100);
+        h.insert((String::from("a"), 1..3).into()).unwrap();
+        h.insert((String::from("b,with,commas"), 3..4).into()).unwrap();
+        h.insert((String::from(""), 60..90).into()).unwrap();
+
+        for format in [RangeFormat::Csv, RangeFormat::Tsv] {
+            let mut out: Vec<u8> = Vec::new();
+            h.export_ranges(&mut out, format, |e| e.clone()).unwrap();
+
+            let h2: BumpyVector<String> = BumpyVector::import_ranges(&out[..], 100, format, |l| Ok(l.to_string())).unwrap();
+
+            let before: Vec<_> = h.iter().map(|e| (e.entry.clone(), e.range.clone())).collect();
+            let after: Vec<_> = h2.iter().map(|e| (e.entry.clone(), e.range.clone())).collect();
+            assert_eq!(before, after);
+        }
+    }
+
+    #[test]
+    fn test_export_newline() {
+        let mut h: BumpyVector<&str> = BumpyVector::new(100);
+        h.insert(("two\nlines", 1..3).into()).unwrap();
+
+        let mut out: Vec<u8> = Vec::new();
+        assert!(h.export_ranges(&mut out, RangeFormat::Csv, |e| e.to_string()).is_err());
+    }
+
+    #[test]
+    fn test_import_errors() {
+        let parse = |l: &str| -> BumpyResult<String> { Ok(l.to_string()) };
+
+        // Bad numbers
+        assert!(BumpyVector::import_ranges("1,x,a\n".as_bytes(), 100, RangeFormat::Csv, parse).is_err());
+        assert!(BumpyVector::import_ranges("1\n".as_bytes(), 100, RangeFormat::Csv, parse).is_err());
+
+        // Wrong delimiter
+        assert!(BumpyVector::import_ranges("1,2,a\n".as_bytes(), 100, RangeFormat::Tsv, parse).is_err());
+
+        // Overlapping, or out of bounds
+        assert!(BumpyVector::import_ranges("1,5,a\n2,3,b\n".as_bytes(), 100, RangeFormat::Csv, parse).is_err());
+        assert!(BumpyVector::import_ranges("1,500,a\n".as_bytes(), 100, RangeFormat::Csv, parse).is_err());
+
+        // The parser can fail too
+        assert!(BumpyVector::<String>::import_ranges("1,2,a\n".as_bytes(), 100, RangeFormat::Csv, |_| Err("no".into())).is_err());
+
+        // But a missing label is fine
+        let h = BumpyVector::import_ranges("1,2\n".as_bytes(), 100, RangeFormat::Csv, parse).unwrap();
+        assert_eq!("", h.get(1).unwrap().entry);
+    }
+
+    #[test]
+    fn test_record_coverage() {
+        let mut h: BumpyVector<CoverageInfo> = BumpyVector::new(100);
+        h.record_coverage(10..20).unwrap();
+        h.record_coverage(30..40).unwrap();
+        h.record_coverage(50..60).unwrap();
+
+        // Bridging two blocks merges all three
+        h.record_coverage(15..35).unwrap();
+        let blocks: Vec<_> = h.iter().map(|e| (e.range.clone(), e.entry.hits)).collect();
+        assert_eq!(vec![(10..40, 3), (50..60, 1)], blocks);
+
+        // Nothing is lost if the merged block won't fit
+        h.lock_range(59..60);
+        assert!(h.record_coverage(45..55).is_err());
+        assert!(h.record_coverage(95..105).is_err());
+        assert!(h.record_coverage(5..5).is_err());
+        assert_eq!(2, h.len());
+    }
+
+    #[test]
+    fn test_import_drcov_binary() {
+        let mut input: Vec<u8> = b"DRCOV VERSION: 2\nDRCOV FLAVOR: drcov\nModule Table: version 2, count 2\nColumns: id, base, end, entry, checksum, timestamp, path\n  0, 0x1000, 0x2000, 0x0, 0x0, 0x0, a\n  1, 0x3000, 0x4000, 0x0, 0x0, 0x0, b\nBB Table: 4 bbs\n".to_vec();
+        for (start, size, module) in [(0x10u32, 4u16, 0u16), (0x10, 4, 1), (0x12, 4, 0), (0x80, 2, 0)] {
+            input.extend_from_slice(&start.to_le_bytes());
+            input.extend_from_slice(&size.to_le_bytes());
+            input.extend_from_slice(&module.to_le_bytes());
+        }
+
+        let h = BumpyVector::import_drcov(&input[..], 0x1000, 0).unwrap();
+        let blocks: Vec<_> = h.iter().map(|e| (e.range.clone(), e.entry.hits)).collect();
+        assert_eq!(vec![(0x10..0x16, 2), (0x80..0x82, 1)], blocks);
+
+        let h = BumpyVector::import_drcov(&input[..], 0x1000, 1).unwrap();
+        assert_eq!(1, h.len());
+
+        // Too small for the blocks; the last one is the problem
+        let e = BumpyVector::import_drcov(&input[..], 0x20, 0).unwrap_err();
+        assert!(matches!(e, BumpyError::Record(3, _)));
+
+        // Cut off partway through the table
+        assert!(BumpyVector::import_drcov(&input[..input.len() - 1], 0x1000, 0).is_err());
+
+        // Not drcov at all
+        assert!(BumpyVector::import_drcov("hello\n".as_bytes(), 0x1000, 0).is_err());
+        assert!(BumpyVector::import_drcov("DRCOV VERSION: 2\n".as_bytes(), 0x1000, 0).is_err());
+    }
+
+    #[test]
+    fn test_import_coverage_blocks() {
+        let h = BumpyVector::import_coverage_blocks("16 4\n0x10,4\n\n# comment\n  32\t2  \n".as_bytes(), 100, 0).unwrap();
+        let blocks: Vec<_> = h.iter().map(|e| (e.range.clone(), e.entry.hits)).collect();
+        assert_eq!(vec![(16..20, 2), (32..34, 1)], blocks);
+
+        assert!(BumpyVector::import_coverage_blocks("16\n".as_bytes(), 100, 0).is_err());
+        assert!(BumpyVector::import_coverage_blocks("16 x\n".as_bytes(), 100, 0).is_err());
+        assert!(BumpyVector::import_coverage_blocks("99 2\n".as_bytes(), 100, 0).is_err());
+        assert!(BumpyVector::import_coverage_blocks("0x100 2\n".as_bytes(), 100, 0x200).is_err());
+    }
+}
@@ -0,0 +1,281 @@
+//! Iterators over the entries in a `BumpyVector`.
+//!
+//! Both iterators find entries as they go, rather than collecting them up
+//! front, and both can go backwards. Iterating over everything knows exactly
+//! how many entries are left, so it's an `ExactSizeIterator` as well.
+
+use std::ops::Range;
+
+use crate::{BumpyEntry, BumpyVector};
+
+/// A lazy iterator over the entries in a range; see
+/// `BumpyVector::iter_range()`.
+#[derive(Debug, Clone)]
+pub struct RangeIter<'a, T> {
+    vector: &'a BumpyVector<T>,
+
+    /// The next index to look at.
+    front: usize,
+
+    /// One past the last index to look at.
+    back: usize,
+}
+
+impl<'a, T> Iterator for RangeIter<'a, T> {
+    type Item = &'a BumpyEntry<T>;
+
+    fn next(&mut self) -> Option<&'a BumpyEntry<T>> {
+        if self.front >= self.back {
+            return None;
+        }
+
+        match self.vector.data.range(self.front..self.back).next() {
+            Some((_, e)) => {
+                // Jump over it
+                self.front = e.range.end;
+
+                Some(e)
+            },
+            None => {
+                self.front = self.back;
+
+                None
+            },
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        // Every entry is at least one index long
+        let most = std::cmp::min(self.vector.len(), self.back.saturating_sub(self.front));
+
+        (0, Some(most))
+    }
+}
+
+impl<'a, T> DoubleEndedIterator for RangeIter<'a, T> {
+    fn next_back(&mut self) -> Option<&'a BumpyEntry<T>> {
+        // Going backwards, the first start we find belongs to the last entry,
+        // whether or not it overlaps `back`
+        if self.front >= self.back {
+            return None;
+        }
+
+        match self.vector.data.range(self.front..self.back).next_back() {
+            Some((_, e)) => {
+                self.back = e.range.start;
+
+                Some(e)
+            },
+            None => {
+                self.back = self.front;
+
+                None
+            },
+        }
+    }
+}
+
+/// An iterator over every entry; see `BumpyVector::iter()`.
+#[derive(Debug, Clone)]
+pub struct Iter<'a, T> {
+    inner: RangeIter<'a, T>,
+
+    /// How many entries haven't been returned yet.
+    remaining: usize,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a BumpyEntry<T>;
+
+    fn next(&mut self) -> Option<&'a BumpyEntry<T>> {
+        let e = self.inner.next()?;
+        self.remaining -= 1;
+
+        Some(e)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'a, T> DoubleEndedIterator for Iter<'a, T> {
+    fn next_back(&mut self) -> Option<&'a BumpyEntry<T>> {
+        let e = self.inner.next_back()?;
+        self.remaining -= 1;
+
+        Some(e)
+    }
+}
+
+impl<'a, T> ExactSizeIterator for Iter<'a, T> {}
+
+impl<T> BumpyVector<T> {
+    /// Iterate over every entry, in order.
+    ///
+    /// This is the same as iterating over `&vector`. Since the number of
+    /// entries is known, `len()` and `size_hint()` are exact.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bumpy_vector::BumpyVector;
+    ///
+    /// let mut v: BumpyVector<&str> = BumpyVector::new(10);
+    /// v.insert(("b", 5..8).into()).unwrap();
+    /// v.insert(("a", 0..2).into()).unwrap();
+    ///
+    /// let mut iter = v.iter();
+    /// assert_eq!(2, iter.len());
+    /// assert_eq!("a", iter.next().unwrap().entry);
+    /// assert_eq!(1, iter.len());
+    /// ```
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            inner: self.iter_range(0..self.max_size),
+            remaining: self.len(),
+        }
+    }
+
+    /// Iterate over the entries within the given range, in order.
+    ///
+    /// This returns the same entries as `get_range()`, but finds them as it
+    /// goes instead of collecting them all up front, so taking the first few
+    /// entries of a huge range is cheap. It can go backwards, too.
+    ///
+    /// Like `get_range()`, any part of the range past `max_size` is ignored.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bumpy_vector::BumpyVector;
+    ///
+    /// let mut v: BumpyVector<&str> = BumpyVector::new(1_000_000);
+    /// v.insert(("a", 1..3).into()).unwrap();
+    /// v.insert(("b", 3..4).into()).unwrap();
+    /// v.insert(("c", 999_990..999_999).into()).unwrap();
+    ///
+    /// // This stops looking as soon as it has two entries
+    /// let first: Vec<&str> = v.iter_range(2..1_000_000).take(2).map(|e| e.entry).collect();
+    /// assert_eq!(vec!["a", "b"], first);
+    ///
+    /// // Or start from the end
+    /// let last: Vec<&str> = v.iter_range(0..1_000_000).rev().take(2).map(|e| e.entry).collect();
+    /// assert_eq!(vec!["c", "b"], last);
+    /// ```
+    pub fn iter_range(&self, range: Range<usize>) -> RangeIter<'_, T> {
+        let back = std::cmp::min(range.end, self.max_size);
+
+        // An empty range can't contain anything (even if it's inside an entry)
+        let front = if range.is_empty() {
+            back
+        } else {
+            self.get_entry_start(range.start).unwrap_or(range.start)
+        };
+
+        RangeIter {
+            vector: self,
+            front,
+            back,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_iter_range() {
+        // [--0-- --1-- --2-- --3-- --4-- --5-- --6-- --7-- --8-- --9--]
+        //        +-----------------            +----------------+
+        //        |   "a" (2)| "b" |            |      "c"       |
+        //        +----------+------            +----------------+
+        let mut h: BumpyVector<&str> = BumpyVector::new(10);
+        h.insert(("a", 1..3).into()).unwrap();
+        h.insert(("b", 3..4).into()).unwrap();
+        h.insert(("c", 6..9).into()).unwrap();
+
+        // It should always match get_range()
+        for start in 0..12 {
+            for end in 0..12 {
+                let expected: Vec<&str> = h.get_range(start..end).iter().map(|e| e.entry).collect();
+                let actual: Vec<&str> = h.iter_range(start..end).map(|e| e.entry).collect();
+
+                assert_eq!(expected, actual, "range {}..{}", start, end);
+            }
+        }
+
+        let mut iter = h.iter_range(2..7);
+        assert_eq!("a", iter.next().unwrap().entry);
+        assert_eq!("b", iter.next().unwrap().entry);
+        assert_eq!("c", iter.next().unwrap().entry);
+        assert!(iter.next().is_none());
+        assert!(iter.next().is_none());
+    }
+
+    #[test]
+    fn test_iter_range_backwards() {
+        // Same layout as above
+        let mut h: BumpyVector<&str> = BumpyVector::new(10);
+        h.insert(("a", 1..3).into()).unwrap();
+        h.insert(("b", 3..4).into()).unwrap();
+        h.insert(("c", 6..9).into()).unwrap();
+
+        // Backwards should always be forwards, reversed
+        for start in 0..12 {
+            for end in 0..12 {
+                let mut expected: Vec<&str> = h.get_range(start..end).iter().map(|e| e.entry).collect();
+                expected.reverse();
+                let actual: Vec<&str> = h.iter_range(start..end).rev().map(|e| e.entry).collect();
+
+                assert_eq!(expected, actual, "range {}..{}", start, end);
+            }
+        }
+
+        // Both ends meet in the middle without repeating anything
+        let mut iter = h.iter_range(2..7);
+        assert_eq!("c", iter.next_back().unwrap().entry);
+        assert_eq!("a", iter.next().unwrap().entry);
+        assert_eq!("b", iter.next_back().unwrap().entry);
+        assert!(iter.next().is_none());
+        assert!(iter.next_back().is_none());
+
+        let mut iter = h.iter_range(0..10);
+        assert_eq!("a", iter.next().unwrap().entry);
+        assert_eq!("b", iter.next().unwrap().entry);
+        assert_eq!("c", iter.next_back().unwrap().entry);
+        assert!(iter.next_back().is_none());
+        assert!(iter.next().is_none());
+    }
+
+    #[test]
+    fn test_iter_exact_size() {
+        // Same layout as above
+        let mut h: BumpyVector<&str> = BumpyVector::new(10);
+        h.insert(("a", 1..3).into()).unwrap();
+        h.insert(("b", 3..4).into()).unwrap();
+        h.insert(("c", 6..9).into()).unwrap();
+
+        let mut iter = h.iter();
+        assert_eq!((3, Some(3)), iter.size_hint());
+        assert_eq!(3, iter.len());
+
+        iter.next();
+        assert_eq!(2, iter.len());
+        iter.next_back();
+        assert_eq!(1, iter.len());
+        assert_eq!("b", iter.next().unwrap().entry);
+        assert_eq!(0, iter.len());
+        assert!(iter.next().is_none());
+        assert_eq!(0, iter.len());
+
+        // The range iterator can only guess
+        let iter = h.iter_range(0..2);
+        assert_eq!((0, Some(2)), iter.size_hint());
+
+        let h: BumpyVector<&str> = BumpyVector::new(10);
+        assert_eq!(0, h.iter().len());
+    }
+}
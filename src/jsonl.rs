@@ -0,0 +1,199 @@
+//! Import and export entries as JSON Lines.
+//!
+//! Only available with the 'jsonl' feature:
+//!
+//! ```toml
+//! bumpy_vector = { version = "~0.0.0", features = ["jsonl"] }
+//! ```
+//!
+//! Each entry is one line, in order:
+//!
+//! ```text
+//! {"index":0,"size":16,"value":"header"}
+//! ```
+//!
+//! Unlike serializing the whole vector, nothing needs to be held in memory
+//! at once on either end, and the output works with line-oriented tools like
+//! `grep`, `jq`, and `split`. Only the entries are written, not `max_size`,
+//! locks, or anything else about the vector. As with serializing, temporary
+//! entries (see `insert_temporary()`) are left out.
+//!
+//! # Example
+//!
+//! ```
+//! use bumpy_vector::BumpyVector;
+//!
+//! let mut v: BumpyVector<String> = BumpyVector::new(100);
+//! v.insert((String::from("header"), 0..16).into()).unwrap();
+//! v.insert((String::from("code"), 16..64).into()).unwrap();
+//!
+//! let mut out: Vec<u8> = Vec::new();
+//! v.export_jsonl(&mut out).unwrap();
+//!
+//! let out = String::from_utf8(out).unwrap();
+//! assert_eq!(r#"{"index":0,"size":16,"value":"header"}"#, out.lines().next().unwrap());
+//!
+//! let v: BumpyVector<String> = BumpyVector::import_jsonl(out.as_bytes(), 100).unwrap();
+//! assert_eq!(16..64, v.get(20).unwrap().range);
+//! ```
+
+use std::io::{BufRead, Write};
+
+use serde::{Serialize, Serializer};
+use serde::de::DeserializeOwned;
+use serde::ser::SerializeStruct;
+use serde_json::Value;
+
+use crate::{BumpyEntry, BumpyError, BumpyResult, BumpyVector};
+
+/// Parse one line into an entry.
+fn parse_line<T>(line: &str) -> BumpyResult<BumpyEntry<T>>
+where
+    T: DeserializeOwned
+{
+    let mut line: Value = serde_json::from_str(line).map_err(|e| BumpyError::Other(e.to_string()))?;
+
+    let index = line.get("index").and_then(Value::as_u64).ok_or(BumpyError::MissingField("index"))?;
+    let size = line.get("size").and_then(Value::as_u64).ok_or(BumpyError::MissingField("size"))?;
+    let value = line.get_mut("value").map(Value::take).ok_or(BumpyError::MissingField("value"))?;
+
+    BumpyEntry::try_entry(
+        serde_json::from_value(value).map_err(|e| BumpyError::Other(e.to_string()))?,
+        index,
+        size,
+    )
+}
+
+/// The line for one entry (without the newline).
+pub(crate) struct Line<'a, T>(pub(crate) &'a BumpyEntry<T>);
+
+impl<'a, T> Serialize for Line<'a, T>
+where
+    T: Serialize
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("Line", 3)?;
+        state.serialize_field("index", &self.0.range.start)?;
+        state.serialize_field("size", &self.0.range.len())?;
+        state.serialize_field("value", &self.0.entry)?;
+
+        state.end()
+    }
+}
+
+/// Turn an error from `serde_json` into a `BumpyError`, keeping I/O errors
+/// as they are.
+pub(crate) fn json_error(e: serde_json::Error) -> BumpyError {
+    match e.is_io() {
+        true  => BumpyError::Io(e.into()),
+        false => BumpyError::Other(e.to_string()),
+    }
+}
+
+impl<T> BumpyVector<T> {
+    /// Write every entry as a line of JSON, in order.
+    ///
+    /// # Return
+    ///
+    /// Returns an error if writing fails, or if a value can't be turned into
+    /// JSON (like a map whose keys aren't strings). Any lines before it have
+    /// already been written.
+    pub fn export_jsonl<W>(&self, writer: &mut W) -> BumpyResult<()>
+    where
+        W: Write,
+        T: Serialize,
+    {
+        for e in self.iter().filter(|e| !self.temporary.contains(e.range.start)) {
+            serde_json::to_writer(&mut *writer, &Line(e)).map_err(json_error)?;
+            writeln!(writer)?;
+        }
+
+        Ok(())
+    }
+
+    /// Read lines of JSON into a new vector, one at a time.
+    ///
+    /// Blank lines are ignored.
+    ///
+    /// # Return
+    ///
+    /// Returns the new vector, or an error naming the first line that
+    /// couldn't be read, parsed, or inserted.
+    pub fn import_jsonl<R>(reader: R, max_size: usize) -> BumpyResult<Self>
+    where
+        R: BufRead,
+        T: DeserializeOwned,
+    {
+        let mut result = Self::new(max_size);
+
+        for (number, line) in reader.lines().enumerate() {
+            let line = line?;
+            let number = number + 1;
+
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let entry = parse_line(&line).map_err(|e| BumpyError::Line(number, Box::new(e)))?;
+
+            result.insert(entry).map_err(|e| BumpyError::Line(number, Box::new(e.reason)))?;
+        }
+
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_jsonl() {
+        let mut h: BumpyVector<(u32, String)> = BumpyVector::new(100);
+        h.insert(((1, String::from("a\nb")), 50..60).into()).unwrap();
+        h.insert(((2, String::from("c")), 0..10).into()).unwrap();
+        h.insert_temporary(((3, String::from("d")), 20..30).into(), 100).unwrap();
+
+        let mut out: Vec<u8> = Vec::new();
+        h.export_jsonl(&mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+
+        assert_eq!(vec![
+            r#"{"index":0,"size":10,"value":[2,"c"]}"#,
+            r#"{"index":50,"size":10,"value":[1,"a\nb"]}"#,
+        ], out.lines().collect::<Vec<_>>());
+
+        let h: BumpyVector<(u32, String)> = BumpyVector::import_jsonl(format!("\n{}\n", out).as_bytes(), 100).unwrap();
+        let entries: Vec<_> = h.iter().map(|e| (e.entry.clone(), e.range.clone())).collect();
+        assert_eq!(vec![((2, String::from("c")), 0..10), ((1, String::from("a\nb")), 50..60)], entries);
+    }
+
+    #[test]
+    fn test_jsonl_errors() {
+        let load = |input: &str| BumpyVector::<String>::import_jsonl(input.as_bytes(), 100);
+
+        assert!(load(r#"{"index":0,"size":10,"value":"a"}"#).is_ok());
+
+        // Not JSON, or missing something
+        assert!(load("0,10,a").is_err());
+        assert!(matches!(load(r#"{"index":0,"value":"a"}"#), Err(BumpyError::Line(1, e)) if matches!(*e, BumpyError::MissingField("size"))));
+        assert!(load(r#"{"index":-1,"size":10,"value":"a"}"#).is_err());
+        assert!(load(r#"{"index":0,"size":10,"value":5}"#).is_err());
+
+        // Doesn't fit
+        assert!(matches!(load("{\"index\":0,\"size\":10,\"value\":\"a\"}\n{\"index\":5,\"size\":10,\"value\":\"b\"}"), Err(BumpyError::Line(2, _))));
+        assert!(load(r#"{"index":95,"size":10,"value":"a"}"#).is_err());
+        assert!(load(r#"{"index":18446744073709551615,"size":10,"value":"a"}"#).is_err());
+
+        // JSON keys have to be strings, so this can't be exported
+        let mut h: BumpyVector<HashMap<Vec<u8>, u32>> = BumpyVector::new(100);
+        h.insert((HashMap::new(), 0..10).into()).unwrap();
+        h.insert((vec![(vec![1], 2)].into_iter().collect(), 10..20).into()).unwrap();
+
+        let mut out: Vec<u8> = Vec::new();
+        assert!(matches!(h.export_jsonl(&mut out), Err(BumpyError::Other(_))));
+        assert!(String::from_utf8(out).unwrap().starts_with("{\"index\":0,\"size\":10,\"value\":{}}\n"));
+    }
+}
@@ -0,0 +1,260 @@
+//! Separate layers of entries, one per plugin.
+//!
+//! Different analyzers often want to annotate the same bytes - a string
+//! finder and a disassembler can both have opinions about the same range.
+//! Rather than making them agree, each one gets its own named layer: entries
+//! can't overlap other entries in the same layer, but layers don't care about
+//! each other. Reads can look at one layer, or at all of them at once.
+//!
+//! Every layer is the same size, so `layer()` hands out a `LayerMut` rather
+//! than the vector itself: it can read anything, and change entries, but
+//! not do anything (like `shrink_to()`) that would change the layer's size.
+
+use std::collections::BTreeMap;
+use std::ops::{Deref, Range};
+
+#[cfg(feature = "serialize")]
+use serde::{Serialize, Deserialize};
+
+use crate::{BumpyEntry, BumpyVector, InsertError};
+use crate::types::TypeTag;
+
+/// A set of `BumpyVector`s of the same size, indexed by name.
+///
+/// # Example
+///
+/// ```
+/// use bumpy_vector::layers::LayeredBumpyVector;
+///
+/// let mut v: LayeredBumpyVector<&str> = LayeredBumpyVector::new(100);
+///
+/// v.layer("strings-analyzer").insert(("\"hello\"", 0..6).into()).unwrap();
+/// v.layer("disassembler").insert(("push rbp", 0..1).into()).unwrap();
+/// v.layer("disassembler").insert(("mov rbp, rsp", 1..4).into()).unwrap();
+///
+/// // Each layer has its own rules
+/// assert!(v.layer("disassembler").insert(("nope", 2..3).into()).is_err());
+///
+/// // But they can be read together
+/// let at: Vec<(&str, &str)> = v.get(2).into_iter().map(|(layer, e)| (layer, e.entry)).collect();
+/// assert_eq!(vec![("disassembler", "mov rbp, rsp"), ("strings-analyzer", "\"hello\"")], at);
+/// ```
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct LayeredBumpyVector<T> {
+    /// The size of every layer.
+    max_size: usize,
+
+    /// The layers, by name.
+    layers: BTreeMap<String, BumpyVector<T>>,
+}
+
+/// A mutable view of one layer; see `LayeredBumpyVector::layer()`.
+///
+/// It can be read just like the layer itself. The changes it allows are
+/// the ones that leave the layer the same size as the others.
+#[derive(Debug)]
+pub struct LayerMut<'a, T> {
+    vector: &'a mut BumpyVector<T>,
+}
+
+impl<'a, T> Deref for LayerMut<'a, T> {
+    type Target = BumpyVector<T>;
+
+    fn deref(&self) -> &BumpyVector<T> {
+        self.vector
+    }
+}
+
+impl<'a, T> LayerMut<'a, T> {
+    /// Insert an entry; see `BumpyVector::insert()`.
+    pub fn insert(&mut self, entry: BumpyEntry<T>) -> Result<(), InsertError<T>> {
+        self.vector.insert(entry)
+    }
+
+    /// Insert an entry with a type tag; see `BumpyVector::insert_typed()`.
+    pub fn insert_typed(&mut self, entry: BumpyEntry<T>, tag: TypeTag) -> Result<(), InsertError<T>> {
+        self.vector.insert_typed(entry, tag)
+    }
+
+    /// Remove the entry at `index`; see `BumpyVector::remove()`.
+    pub fn remove(&mut self, index: usize) -> Option<BumpyEntry<T>> {
+        self.vector.remove(index)
+    }
+
+    /// Remove the entries within `range`; see `BumpyVector::remove_range()`.
+    pub fn remove_range(&mut self, range: Range<usize>) -> Vec<BumpyEntry<T>> {
+        self.vector.remove_range(range)
+    }
+
+    /// Keep only the entries `f` returns `true` for; see
+    /// `BumpyVector::retain()`.
+    pub fn retain<F>(&mut self, f: F)
+    where
+        F: FnMut(&BumpyEntry<T>) -> bool
+    {
+        self.vector.retain(f)
+    }
+
+    /// Remove every entry; see `BumpyVector::clear()`.
+    pub fn clear(&mut self) {
+        self.vector.clear()
+    }
+
+    /// Return a mutable reference to the value at `index`; see
+    /// `BumpyVector::get_mut()`.
+    pub fn get_mut(&mut self, index: usize) -> Option<BumpyEntry<&mut T>> {
+        self.vector.get_mut(index)
+    }
+
+    /// Iterate over mutable references to every value; see
+    /// `BumpyVector::iter_mut()`.
+    pub fn iter_mut(&mut self) -> std::vec::IntoIter<BumpyEntry<&mut T>> {
+        self.vector.iter_mut()
+    }
+
+    /// Change the type tag of the entry at `index`; see
+    /// `BumpyVector::set_type()`.
+    pub fn set_type(&mut self, index: usize, tag: TypeTag) -> bool {
+        self.vector.set_type(index, tag)
+    }
+
+    /// Lock `range`; see `BumpyVector::lock_range()`.
+    pub fn lock_range(&mut self, range: Range<usize>) {
+        self.vector.lock_range(range)
+    }
+
+    /// Unlock `range`; see `BumpyVector::unlock_range()`.
+    pub fn unlock_range(&mut self, range: Range<usize>) {
+        self.vector.unlock_range(range)
+    }
+
+    /// Pin the entry at `index`; see `BumpyVector::pin()`.
+    pub fn pin(&mut self, index: usize) -> bool {
+        self.vector.pin(index)
+    }
+
+    /// Unpin the entry at `index`; see `BumpyVector::unpin()`.
+    pub fn unpin(&mut self, index: usize) {
+        self.vector.unpin(index)
+    }
+
+    /// Make the layer read-only, or not; see `BumpyVector::set_read_only()`.
+    pub fn set_read_only(&mut self, read_only: bool) {
+        self.vector.set_read_only(read_only)
+    }
+}
+
+impl<T> LayeredBumpyVector<T> {
+    /// Create a new set of layers, each `max_size` long.
+    ///
+    /// There aren't any layers until one is asked for.
+    pub fn new(max_size: usize) -> Self {
+        LayeredBumpyVector {
+            max_size,
+            layers: BTreeMap::new(),
+        }
+    }
+
+    /// Return the layer called `name`, creating an empty one if it doesn't
+    /// exist yet.
+    ///
+    /// The layer can be changed through the `LayerMut`, as long as it stays
+    /// `max_size` long.
+    pub fn layer(&mut self, name: &str) -> LayerMut<'_, T> {
+        let max_size = self.max_size;
+
+        LayerMut {
+            vector: self.layers.entry(name.to_string()).or_insert_with(|| BumpyVector::new(max_size)),
+        }
+    }
+
+    /// Return the layer called `name`, if it exists.
+    pub fn get_layer(&self, name: &str) -> Option<&BumpyVector<T>> {
+        self.layers.get(name)
+    }
+
+    /// Remove the layer called `name`, and everything in it.
+    pub fn remove_layer(&mut self, name: &str) -> Option<BumpyVector<T>> {
+        self.layers.remove(name)
+    }
+
+    /// Iterate over the names of every layer, in order.
+    pub fn layer_names(&self) -> impl Iterator<Item=&str> {
+        self.layers.keys().map(|name| &name[..])
+    }
+
+    /// Return the entry at `index` in each layer that has one, along with
+    /// the layer's name, ordered by layer name.
+    pub fn get(&self, index: usize) -> Vec<(&str, &BumpyEntry<T>)> {
+        self.layers.iter().filter_map(|(name, layer)| {
+            Some((&name[..], layer.get(index)?))
+        }).collect()
+    }
+
+    /// Return the entries within `range` in every layer, along with the
+    /// layer's name.
+    ///
+    /// They're ordered by where they start, then by layer name.
+    pub fn get_range(&self, range: Range<usize>) -> Vec<(&str, &BumpyEntry<T>)> {
+        let mut result: Vec<(&str, &BumpyEntry<T>)> = self.layers.iter().flat_map(|(name, layer)| {
+            layer.iter_range(range.clone()).map(move |e| (&name[..], e))
+        }).collect();
+
+        // The sort is stable, so layers stay in order
+        result.sort_by_key(|(_, e)| e.range.start);
+
+        result
+    }
+
+    /// Return the size of every layer.
+    pub fn max_size(&self) -> usize {
+        self.max_size
+    }
+
+    /// Return the number of entries, in all layers.
+    pub fn len(&self) -> usize {
+        self.layers.values().map(|layer| layer.len()).sum()
+    }
+
+    /// Return `true` if there are no entries in any layer.
+    pub fn is_empty(&self) -> bool {
+        self.layers.values().all(|layer| layer.is_empty())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_layers() {
+        let mut h: LayeredBumpyVector<&str> = LayeredBumpyVector::new(100);
+        assert!(h.is_empty());
+        assert!(h.get_layer("a").is_none());
+
+        h.layer("b").insert(("b1", 0..10).into()).unwrap();
+        h.layer("b").insert(("b2", 20..30).into()).unwrap();
+        h.layer("a").insert(("a1", 5..25).into()).unwrap();
+        assert!(h.layer("a").insert(("a2", 24..26).into()).is_err());
+        assert!(h.layer("c").insert(("c1", 95..105).into()).is_err());
+
+        assert_eq!(3, h.len());
+        assert_eq!(vec!["a", "b", "c"], h.layer_names().collect::<Vec<_>>());
+        assert_eq!(100, h.get_layer("c").unwrap().max_size());
+        assert_eq!(2, h.layer("b").len());
+
+        let at: Vec<(&str, &str)> = h.get(7).into_iter().map(|(l, e)| (l, e.entry)).collect();
+        assert_eq!(vec![("a", "a1"), ("b", "b1")], at);
+        assert_eq!(0, h.get(50).len());
+
+        let within: Vec<(&str, &str)> = h.get_range(5..21).into_iter().map(|(l, e)| (l, e.entry)).collect();
+        assert_eq!(vec![("b", "b1"), ("a", "a1"), ("b", "b2")], within);
+
+        // Removing a layer takes everything in it
+        assert_eq!(2, h.remove_layer("b").unwrap().len());
+        assert_eq!(1, h.len());
+        assert!(h.remove_layer("b").is_none());
+    }
+}
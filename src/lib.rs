@@ -1,5 +1,3 @@
-#![feature(range_is_empty)]
-
 //! [![Crate](https://img.shields.io/crates/v/bumpy_vector.svg)](https://crates.io/crates/bumpy_vector)
 //!
 //! **NOTE: This is deprecated and moved right into libh2gb!**
@@ -46,6 +44,15 @@
 //! assert_eq!(1, v.len());
 //! ```
 //!
+//! # Performance
+//!
+//! Entries never overlap, so ordering them by where they start is all an
+//! interval tree would buy us: the entry covering an index is the last one
+//! that starts at or before it, and the entries overlapping a range are that
+//! one plus everything that starts inside the range. Both are a lookup in an
+//! ordered map, so `get()` is O(log n) and `get_range()` is O(log n + k) for
+//! k results, no matter how big or sparse the vector is.
+//!
 //! # Serialize / deserialize
 //!
 //! When installed with the 'serialize' feature:
@@ -74,14 +81,72 @@
 //! }
 //! ```
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet};
+use std::convert::{TryFrom, TryInto};
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::iter::FromIterator;
 use std::ops::Range;
 
-use simple_error::{SimpleResult, bail};
-
 #[cfg(feature = "serialize")]
 use serde::{Serialize, Deserialize};
 
+pub use error::{BulkInsertError, BumpyError, BumpyResult, CasError, InsertError};
+
+pub mod access;
+pub mod builder;
+pub mod bulk;
+pub mod bytes;
+mod cache;
+pub mod changes;
+pub mod compact;
+pub mod coverage;
+pub mod crdt;
+pub mod error;
+pub mod fingerprint;
+pub mod gaps;
+pub mod intern;
+pub mod layers;
+pub mod iter;
+pub mod locks;
+pub mod neighbors;
+pub mod offsets;
+pub mod pins;
+pub mod placement;
+pub mod provenance;
+pub mod ranges;
+pub mod readonly;
+pub mod relocate;
+pub mod reader;
+pub mod size_hint;
+pub mod slice;
+pub mod slot;
+pub mod snapshot;
+pub mod split;
+pub mod strings;
+pub mod temporary;
+pub mod types;
+pub mod view;
+pub mod visit;
+
+#[cfg(feature = "futures")]
+pub mod stream;
+
+#[cfg(feature = "io")]
+pub mod io;
+
+#[cfg(feature = "jsonl")]
+pub mod jsonl;
+
+#[cfg(feature = "serialize")]
+mod serialize;
+
+#[cfg(feature = "rand")]
+pub mod sample;
+
+#[cfg(feature = "testing")]
+pub mod testing;
+
 /// Represents a single entry.
 ///
 /// An entry is comprised of an object of type `T`, and a
@@ -159,11 +224,87 @@ pub struct BumpyEntry<T> {
 /// // Insert it
 /// assert!(h.insert_auto(entry).is_ok());
 /// ```
-
 pub trait AutoBumpyEntry {
     fn range(&self) -> Range<usize>;
 }
 
+/// Entries are equal if they have the same range and equal values.
+impl<T> PartialEq for BumpyEntry<T>
+where
+    T: PartialEq
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.range == other.range && self.entry == other.entry
+    }
+}
+
+impl<T> Eq for BumpyEntry<T>
+where
+    T: Eq
+{
+}
+
+/// Entries are ordered by where they start, then by size, then by value.
+///
+/// # Example
+///
+/// ```
+/// use bumpy_vector::BumpyEntry;
+///
+/// let mut entries: Vec<BumpyEntry<&str>> = vec![
+///     ("b", 5..6).into(),
+///     ("a", 0..4).into(),
+///     ("z", 0..2).into(),
+///     ("y", 0..2).into(),
+/// ];
+/// entries.sort();
+///
+/// let sorted: Vec<&str> = entries.iter().map(|e| e.entry).collect();
+/// assert_eq!(vec!["y", "z", "a", "b"], sorted);
+/// ```
+impl<T> PartialOrd for BumpyEntry<T>
+where
+    T: PartialOrd
+{
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        match (self.range.start, self.range.len()).cmp(&(other.range.start, other.range.len())) {
+            std::cmp::Ordering::Equal => self.entry.partial_cmp(&other.entry),
+            ordering => Some(ordering),
+        }
+    }
+}
+
+impl<T> Ord for BumpyEntry<T>
+where
+    T: Ord
+{
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        (self.range.start, self.range.len()).cmp(&(other.range.start, other.range.len()))
+            .then_with(|| self.entry.cmp(&other.entry))
+    }
+}
+
+/// Hashes the range and the value, to match `PartialEq`.
+impl<T> Hash for BumpyEntry<T>
+where
+    T: Hash
+{
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.range.hash(state);
+        self.entry.hash(state);
+    }
+}
+
+/// Formats an entry as `start..end: value`.
+impl<T> fmt::Display for BumpyEntry<T>
+where
+    T: fmt::Display
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}..{}: {}", self.range.start, self.range.end, self.entry)
+    }
+}
+
 impl<T> From<(T, Range<usize>)> for BumpyEntry<T> {
     fn from(o: (T, Range<usize>)) -> Self {
         BumpyEntry {
@@ -173,6 +314,85 @@ impl<T> From<(T, Range<usize>)> for BumpyEntry<T> {
     }
 }
 
+impl<T> BumpyEntry<T> {
+    /// Create an entry from an index and size of any integer type.
+    ///
+    /// Offsets parsed out of a file header are often `u64` or `i64`, and
+    /// casting them with `as usize` silently truncates (or wraps negative
+    /// numbers). This checks instead.
+    ///
+    /// # Return
+    ///
+    /// Returns an error if `index` or `size` doesn't fit in a `usize`, or if
+    /// the entry would end past `usize::MAX`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bumpy_vector::BumpyEntry;
+    ///
+    /// let e = BumpyEntry::try_entry("header", 0x10u64, 8i64).unwrap();
+    /// assert_eq!(0x10..0x18, e.range);
+    ///
+    /// assert!(BumpyEntry::try_entry("error", -1i64, 8u8).is_err());
+    /// ```
+    pub fn try_entry<I, S>(entry: T, index: I, size: S) -> BumpyResult<Self>
+    where
+        I: TryInto<usize>,
+        S: TryInto<usize>,
+    {
+        let index: usize = match index.try_into() {
+            Ok(i) => i,
+            Err(_) => return Err(BumpyError::Overflow),
+        };
+
+        let size: usize = match size.try_into() {
+            Ok(s) => s,
+            Err(_) => return Err(BumpyError::Overflow),
+        };
+
+        let end = match index.checked_add(size) {
+            Some(e) => e,
+            None => return Err(BumpyError::Overflow),
+        };
+
+        Ok(BumpyEntry {
+            entry,
+            range: index..end,
+        })
+    }
+}
+
+/// Create an entry from a (T, Range) tuple with `u64` offsets, making sure
+/// they fit.
+impl<T> TryFrom<(T, Range<u64>)> for BumpyEntry<T> {
+    type Error = BumpyError;
+
+    fn try_from(o: (T, Range<u64>)) -> BumpyResult<Self> {
+        let size = match o.1.end.checked_sub(o.1.start) {
+            Some(s) => s,
+            None => return Err(BumpyError::InvalidRange),
+        };
+
+        Self::try_entry(o.0, o.1.start, size)
+    }
+}
+
+/// Create an entry from a (T, Range) tuple with `i64` offsets, making sure
+/// they fit.
+impl<T> TryFrom<(T, Range<i64>)> for BumpyEntry<T> {
+    type Error = BumpyError;
+
+    fn try_from(o: (T, Range<i64>)) -> BumpyResult<Self> {
+        let size = match o.1.end.checked_sub(o.1.start) {
+            Some(s) if s >= 0 => s,
+            _ => return Err(BumpyError::InvalidRange),
+        };
+
+        Self::try_entry(o.0, o.1.start, size)
+    }
+}
+
 impl<T> From<T> for BumpyEntry<T>
 where
     T: AutoBumpyEntry
@@ -185,34 +405,219 @@ where
         // Just use the built-in range. There might be a better way to do this?
         BumpyEntry {
           entry: o,
-          range: range,
+          range,
         }
     }
 }
 
+/// An entry, along with the size of the empty space on either side of it.
+///
+/// See `BumpyVector::get_annotated_range()`.
+#[derive(Debug, Clone)]
+pub struct AnnotatedEntry<'a, T> {
+    pub entry: &'a BumpyEntry<T>,
+
+    /// How many empty indexes there are between the previous entry (or the
+    /// start of the vector) and this one.
+    pub gap_before: usize,
+
+    /// How many empty indexes there are between this entry and the next one
+    /// (or `max_size`).
+    pub gap_after: usize,
+}
+
+/// A run of entries with only small gaps between them.
+///
+/// See `BumpyVector::get_range_merged()`.
+#[derive(Debug, Clone)]
+pub struct MergedBlock<'a, T> {
+    /// From the start of the first entry to the end of the last one.
+    pub range: Range<usize>,
+
+    /// The entries in the block, in order.
+    pub entries: Vec<&'a BumpyEntry<T>>,
+}
+
 /// Represents an instance of a Bumpy Vector
 #[derive(Debug, Clone)]
-#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serialize", derive(Deserialize))]
+#[cfg_attr(feature = "serialize", serde(try_from = "serialize::SerializedBumpyVector<T>"))]
 pub struct BumpyVector<T> {
-    /// The data is represented by a HashMap, where the index is the key and
-    /// a BumpyEntry is the object.
-    data: HashMap<usize, BumpyEntry<T>>,
+    /// The data is represented by a BTreeMap, where the index is the key and
+    /// a BumpyEntry is the object. Keeping it ordered means the entry covering
+    /// any index can be found without scanning.
+    data: BTreeMap<usize, BumpyEntry<T>>,
+
+    /// How many indexes are covered by entries, kept up to date as entries
+    /// come and go (see `occupied_size()`).
+    #[cfg_attr(feature = "serialize", serde(skip))]
+    occupied: usize,
 
     /// The maximum size.
     max_size: usize,
+
+    /// Incremented every time the vector changes.
+    #[cfg_attr(feature = "serialize", serde(skip))]
+    generation: u64,
+
+    /// If set, nothing can be changed.
+    #[cfg_attr(feature = "serialize", serde(default))]
+    read_only: bool,
+
+    /// Ranges that can't be inserted into or removed from.
+    #[cfg_attr(feature = "serialize", serde(default))]
+    locks: Vec<Range<usize>>,
+
+    /// Where the entries that don't move start.
+    #[cfg_attr(feature = "serialize", serde(default))]
+    pins: BTreeSet<usize>,
+
+    /// How often each entry is looked up, if that's being tracked.
+    #[cfg_attr(feature = "serialize", serde(skip))]
+    access: Option<access::AccessTracker>,
+
+    /// What's changed at each generation, if that's being tracked.
+    #[cfg_attr(feature = "serialize", serde(skip))]
+    changes: Option<changes::ChangeTracker>,
+
+    /// When each entry was created and modified, if that's being tracked.
+    #[cfg_attr(feature = "serialize", serde(default))]
+    provenance: Option<provenance::ProvenanceTracker>,
+
+    /// The last entry that was looked up.
+    #[cfg_attr(feature = "serialize", serde(skip))]
+    cache: cache::LookupCache,
+
+    /// When each temporary entry expires.
+    #[cfg_attr(feature = "serialize", serde(skip))]
+    temporary: temporary::TemporaryEntries,
+
+    /// What type each tagged entry is.
+    #[cfg_attr(feature = "serialize", serde(skip))]
+    types: types::TypeTags,
+
+    /// Which entries the `_visible` reads return. This isn't serialized
+    /// either, but it's skipped by `try_from` rather than `serde(skip)`,
+    /// which would need `T: Default`.
+    view: view::ViewFilter<T>,
 }
 
 /// Implement the object.
-impl<'a, T> BumpyVector<T> {
+impl<T> BumpyVector<T> {
     /// Create a new instance of BumpyVector.
     ///
     /// The range of the vector goes from `0` to `max_size - 1`. If any
     /// elements beyond the end are accessed, an error will be returned.
     pub fn new(max_size: usize) -> Self {
         BumpyVector {
-            data: HashMap::new(),
-            max_size: max_size,
+            data: BTreeMap::new(),
+            occupied: 0,
+            max_size,
+            generation: 0,
+            read_only: false,
+            locks: Vec::new(),
+            pins: BTreeSet::new(),
+            access: None,
+            changes: None,
+            provenance: None,
+            cache: cache::LookupCache::default(),
+            temporary: temporary::TemporaryEntries::default(),
+            types: types::TypeTags::default(),
+            view: view::ViewFilter::default(),
+        }
+    }
+
+    /// Create a new instance of BumpyVector from an iterator of entries.
+    ///
+    /// Unlike `collect()`, which sizes the vector to fit and skips entries
+    /// that overlap, this checks every entry like `insert()` does.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bumpy_vector::{BumpyEntry, BumpyError, BumpyVector};
+    ///
+    /// let parsed = vec![("a", 0..4), ("b", 4..6)];
+    /// let v = BumpyVector::from_iter_with_max(parsed.into_iter().map(BumpyEntry::from), 10).unwrap();
+    /// assert_eq!(2, v.len());
+    ///
+    /// let parsed = vec![("a", 0..4), ("b", 2..6)];
+    /// let e = BumpyVector::from_iter_with_max(parsed.into_iter().map(BumpyEntry::from), 10).unwrap_err();
+    /// assert!(matches!(e.reason, BumpyError::Overlap));
+    /// ```
+    ///
+    /// # Return
+    ///
+    /// Returns the first entry that couldn't be inserted, and why, if there
+    /// is one.
+    pub fn from_iter_with_max<I>(iter: I, max_size: usize) -> Result<Self, InsertError<T>>
+    where
+        I: IntoIterator<Item=BumpyEntry<T>>
+    {
+        let mut v = Self::new(max_size);
+
+        for entry in iter {
+            v.insert(entry)?;
+        }
+
+        Ok(v)
+    }
+
+    /// Create a new instance of BumpyVector by parsing a buffer.
+    ///
+    /// The `max_size` is the length of `data`. Starting at offset 0, the
+    /// `parser` is called with the rest of the buffer and the current offset,
+    /// and returns either the parsed value and how many bytes it consumed, or
+    /// `None` if there's nothing there. Each value is recorded as an entry at
+    /// its offset, and parsing continues right after it.
+    ///
+    /// When the parser returns `None` (or consumes 0 bytes, or more bytes than
+    /// are left), that byte is skipped and parsing continues at the next one.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bumpy_vector::BumpyVector;
+    ///
+    /// // Length-prefixed strings, with a bit of junk in the middle
+    /// let data = b"\x05hello\xff\x05world";
+    ///
+    /// let v: BumpyVector<String> = BumpyVector::parse(data, |buffer, _offset| {
+    ///     let length = *buffer.first()? as usize;
+    ///     let s = buffer.get(1..=length)?;
+    ///
+    ///     Some((String::from_utf8_lossy(s).to_string(), length + 1))
+    /// });
+    ///
+    /// assert_eq!(2, v.len());
+    /// assert_eq!(13, v.max_size());
+    /// assert_eq!("hello", v.get(0).unwrap().entry);
+    /// assert!(v.get(6).is_none());
+    /// assert_eq!(7..13, v.get(7).unwrap().range);
+    /// ```
+    pub fn parse<F>(data: &[u8], mut parser: F) -> Self
+    where
+        F: FnMut(&[u8], usize) -> Option<(T, usize)>
+    {
+        let mut result = Self::new(data.len());
+        let mut offset = 0;
+
+        while offset < data.len() {
+            match parser(&data[offset..], offset) {
+                Some((entry, size)) if size > 0 && size <= data.len() - offset => {
+                    // This can't fail, since we're always past the last entry
+                    result.insert_unchecked(BumpyEntry {
+                        entry,
+                        range: offset..(offset + size),
+                    });
+
+                    offset += size;
+                },
+                _ => offset += 1,
+            }
         }
+
+        result
     }
 
     /// Get the object that starts at or overlaps the starting index.
@@ -221,41 +626,24 @@ impl<'a, T> BumpyVector<T> {
     /// offset within the BumpyVector, determine which entry exists in it (even
     /// if the entry starts to the "left").
     ///
-    /// The initial implementation is somewhat naive: loop from the
-    /// `starting_index` to 0, searching for an object. If found, check the
-    /// object's size to ensure it overlaps the `starting_index`.
+    /// Since the entries are ordered by where they start, this is the last
+    /// entry that starts at or before `starting_index` - all that's left is to
+    /// check whether it reaches that far.
     ///
-    /// This will be a good place to optimize later.
+    /// Lookups tend to come in runs through the same entry, so the last one
+    /// found is remembered and checked first.
     fn get_entry_start(&self, starting_index: usize) -> Option<usize> {
-        // Keep a handle to the starting index
-        let mut index = starting_index;
-
-        // Loop right to zero
-        loop {
-            // Check if we have data at the index
-            match self.data.get(&index) {
-                // If there's a value, we're set!
-                Some(d) => {
-                    // If we've found a value that doesn't overlap with the
-                    // index we're looking for, that means the original index
-                    // had nothing
-                    if !d.range.contains(&starting_index) {
-                        return None;
-                    }
-
-                    // Otherwise, we have the real index!
-                    return Some(index);
-                },
+        if let Some(index) = self.cache.get(starting_index) {
+            return Some(index);
+        }
 
-                // If there's no value, we keep going
-                None => {
-                    if index == 0 {
-                        return None;
-                    }
+        match self.data.range(..=starting_index).next_back() {
+            Some((index, e)) if e.range.contains(&starting_index) => {
+                self.cache.set(e.range.clone());
 
-                    index -= 1;
-                },
-            };
+                Some(*index)
+            },
+            _ => None,
         }
     }
 
@@ -263,9 +651,11 @@ impl<'a, T> BumpyVector<T> {
     ///
     /// # Return
     ///
-    /// Returns `Ok(())` if successfully inserted. If it would overlap another
-    /// entry or exceed `max_size`, return `Err(&str)` with a descriptive error
-    /// string.
+    /// Returns `Ok(())` if successfully inserted. Otherwise, returns an
+    /// `InsertError` that gives the entry back, along with a `BumpyError`
+    /// saying why it didn't fit: `Overlap` if it would overlap another
+    /// entry, `OutOfBounds` if it would exceed `max_size`, `ZeroSize` if it's
+    /// empty, `Locked` if it touches a locked range, or `ReadOnly`.
     ///
     /// Size must be at least 1.
     ///
@@ -292,149 +682,335 @@ impl<'a, T> BumpyVector<T> {
     /// // Fail to insert a value that would go out of bounds
     /// assert!(v.insert(("hello", 100..1).into()).is_err());
     /// ```
-    pub fn insert(&mut self, entry: BumpyEntry<T>) -> SimpleResult<()> {
+    pub fn insert(&mut self, entry: BumpyEntry<T>) -> Result<(), InsertError<T>> {
+        if let Err(reason) = self.check_insert(&entry) {
+            return Err(InsertError { entry, reason });
+        }
+
+        // We're good, so create an entry!
+        self.insert_unchecked(entry);
+
+        Ok(())
+    }
+
+    /// Make sure `entry` can be inserted.
+    fn check_insert(&self, entry: &BumpyEntry<T>) -> BumpyResult<()> {
+        if self.read_only {
+            return Err(BumpyError::ReadOnly);
+        }
+
         if entry.range.is_empty() {
-            bail!("An entry can't have an empty range");
+            return Err(BumpyError::ZeroSize);
         }
 
         if entry.range.end > self.max_size {
-            bail!("Invalid entry: entry exceeds max size");
+            return Err(BumpyError::OutOfBounds);
+        }
+
+        if self.is_locked(&entry.range) {
+            return Err(BumpyError::Locked);
         }
 
         // Check if there's a conflict on the left
         if self.get_entry_start(entry.range.start).is_some() {
-            bail!("Invalid entry: overlaps another object");
+            return Err(BumpyError::Overlap);
         }
 
         // Check if there's a conflict on the right
-        for x in entry.range.clone() {
-            if self.data.contains_key(&x) {
-                bail!("Invalid entry: overlaps another object");
-            }
+        if self.data.range(entry.range.clone()).next().is_some() {
+            return Err(BumpyError::Overlap);
         }
 
-        // We're good, so create an entry!
-        self.data.insert(entry.range.start, entry);
-
         Ok(())
     }
 
-    /// Insert an entry that implemented `AutoBumpyEntry`.
+    /// Insert a new entry, unless an identical one is already there.
     ///
-    /// The advantage of using this is that the entry knows its own size and
-    /// index, so the insertion code is much cleaner.
+    /// This is for re-running an analysis over data that's already been
+    /// annotated: if there's already an entry with the same range and an
+    /// equal value, nothing changes and this succeeds. Anything else works
+    /// like `insert()`.
     ///
-    /// See `insert()` for return and errors.
+    /// # Example
     ///
     /// ```
-    /// use bumpy_vector::{BumpyVector, AutoBumpyEntry};
-    /// use std::ops::Range;
-    ///
-    /// // A struct that has an index and size, then whatever other data
-    /// struct MyAutoEntry {
-    ///     size: usize,
-    ///     index: usize,
-    ///     // ...anything else
-    /// };
-    ///
-    /// // Simplest possible implementation for MyAutoEntry
-    /// impl AutoBumpyEntry for MyAutoEntry {
-    ///     fn range(&self) -> Range<usize> {
-    ///         self.index..(self.index + self.size)
-    ///     }
-    /// }
+    /// use bumpy_vector::BumpyVector;
     ///
-    /// // Create an entry
-    /// let entry = MyAutoEntry { index: 0, size: 10 };
+    /// let mut v: BumpyVector<&str> = BumpyVector::new(10);
+    /// assert!(v.insert_idempotent(("hello", 0..2).into()).is_ok());
     ///
-    /// // Create a BumpyVector with the type we created
-    /// let mut h: BumpyVector<MyAutoEntry> = BumpyVector::new(10);
+    /// // Doing it again is fine
+    /// assert!(v.insert_idempotent(("hello", 0..2).into()).is_ok());
+    /// assert_eq!(1, v.len());
     ///
-    /// // Insert it
-    /// assert!(h.insert_auto(entry).is_ok());
+    /// // But a different value, or a different size, still conflicts
+    /// assert!(v.insert_idempotent(("goodbye", 0..2).into()).is_err());
+    /// assert!(v.insert_idempotent(("hello", 0..3).into()).is_err());
     /// ```
-    pub fn insert_auto(&mut self, entry: T) -> SimpleResult<()>
-    where T: AutoBumpyEntry {
-        self.insert(entry.into())
+    pub fn insert_idempotent(&mut self, entry: BumpyEntry<T>) -> Result<(), InsertError<T>>
+    where
+        T: PartialEq
+    {
+        if let Some(existing) = self.data.get(&entry.range.start) {
+            if existing.range == entry.range && existing.entry == entry.entry {
+                return Ok(());
+            }
+        }
+
+        self.insert(entry)
     }
 
-    /// Remove and return the entry at `index`.
+    /// Insert a new entry, or replace the value of an entry with exactly the
+    /// same range.
     ///
-    /// Note that the entry doesn't necessarily need to *start* at `index`,
-    /// just overlap it.
+    /// # Return
+    ///
+    /// Returns `Ok(Some(old_value))` if an entry was replaced, or `Ok(None)`
+    /// if the entry was inserted. If it only partly overlaps existing
+    /// entries, returns an error just like `insert()`.
     ///
     /// # Example
     ///
     /// ```
     /// use bumpy_vector::BumpyVector;
     ///
-    /// // Create a 10-byte `BumpyVector`
     /// let mut v: BumpyVector<&str> = BumpyVector::new(10);
+    /// assert_eq!(None, v.upsert(("old", 0..2).into()).unwrap());
     ///
-    /// // Insert some data
-    /// v.insert(("hello", 0..4).into()).unwrap();
-    /// v.insert(("hello", 4..8).into()).unwrap();
-    ///
-    /// assert!(v.remove(0).is_some());
-    /// assert!(v.remove(0).is_none());
+    /// // Same range, so it's replaced
+    /// assert_eq!(Some("old"), v.upsert(("new", 0..2).into()).unwrap());
+    /// assert_eq!("new", v.get(0).unwrap().entry);
     ///
-    /// assert!(v.remove(6).is_some());
-    /// assert!(v.remove(6).is_none());
+    /// // Different range, so it's an overlap
+    /// assert!(v.upsert(("error", 0..3).into()).is_err());
     /// ```
-    pub fn remove(&mut self, index: usize) -> Option<BumpyEntry<T>> {
-        // Try to get the real offset
-        let real_offset = self.get_entry_start(index);
+    pub fn upsert(&mut self, entry: BumpyEntry<T>) -> Result<Option<T>, InsertError<T>> {
+        if self.read_only {
+            return Err(InsertError { entry, reason: BumpyError::ReadOnly });
+        }
 
-        // If there's no element, return none
-        if let Some(o) = real_offset {
-            // Remove it!
-            if let Some(d) = self.data.remove(&o) {
-                return Some(d);
+        let start = entry.range.start;
+
+        if self.data.get(&start).is_some_and(|e| e.range == entry.range) {
+            self.touch(start);
+
+            if let Some(existing) = self.data.get_mut(&start) {
+                return Ok(Some(std::mem::replace(&mut existing.entry, entry.entry)));
             }
         }
 
-        None
+        self.insert(entry)?;
+
+        Ok(None)
     }
 
-    /// Remove and return a range of entries.
+    /// Insert an entry, removing anything it overlaps to make room.
+    ///
+    /// # Return
     ///
-    /// Ranges work as expected for Rust ranges; see `std::ops::Range`.
+    /// Returns the entries that were removed, in order. If the entry is empty
+    /// or out of bounds, the vector is read-only, or any part of the entry or
+    /// the entries in its way is locked, nothing changes and the entry is
+    /// handed back in the error.
     ///
     /// # Example
     ///
     /// ```
     /// use bumpy_vector::BumpyVector;
     ///
-    /// // Create a 10-byte `BumpyVector`
     /// let mut v: BumpyVector<&str> = BumpyVector::new(10);
+    /// v.insert(("a", 0..2).into()).unwrap();
+    /// v.insert(("b", 2..4).into()).unwrap();
+    /// v.insert(("c", 6..8).into()).unwrap();
     ///
-    /// // Insert some data
-    /// v.insert(("hello", 0..4).into()).unwrap();
-    /// v.insert(("hello", 4..8).into()).unwrap();
+    /// let displaced = v.insert_or_replace(("d", 1..5).into()).unwrap();
+    /// assert_eq!(vec!["a", "b"], displaced.iter().map(|e| e.entry).collect::<Vec<_>>());
     ///
-    /// assert_eq!(2, v.remove_range(0..10).len());
-    /// assert_eq!(0, v.remove_range(0..10).len());
+    /// assert_eq!(2, v.len());
+    /// assert_eq!("d", v.get(1).unwrap().entry);
     /// ```
-    pub fn remove_range(&mut self, range: Range<usize>) -> Vec<BumpyEntry<T>> {
-        let mut result: Vec<BumpyEntry<T>> = Vec::new();
+    pub fn insert_or_replace(&mut self, entry: BumpyEntry<T>) -> Result<Vec<BumpyEntry<T>>, InsertError<T>> {
+        let in_the_way: Vec<Range<usize>> = self.iter_range(entry.range.clone()).map(|e| e.range.clone()).collect();
+
+        let reason = if self.read_only {
+            Some(BumpyError::ReadOnly)
+        } else if entry.range.is_empty() {
+            Some(BumpyError::ZeroSize)
+        } else if entry.range.end > self.max_size {
+            Some(BumpyError::OutOfBounds)
+        } else if self.is_locked(&entry.range) || in_the_way.iter().any(|r| self.is_locked(r)) {
+            Some(BumpyError::Locked)
+        } else {
+            None
+        };
 
-        for i in range {
-            if let Some(e) = self.remove(i) {
-                result.push(e);
-            }
+        if let Some(reason) = reason {
+            return Err(InsertError { entry, reason });
         }
 
-        result
+        let displaced = in_the_way.into_iter().filter_map(|r| self.remove_start(r.start)).collect();
+        self.insert_unchecked(entry);
+
+        Ok(displaced)
     }
 
-    /// Return a reference to an entry at the given index.
-    ///
-    /// Note that the entry doesn't necessarily need to *start* at the given
-    /// index, it can simply be contained there.
+    /// Add an entry without checking whether it fits.
     ///
-    /// # Example
+    /// Every insert goes through here, so this is where we keep track of
+    /// changes.
+    fn insert_unchecked(&mut self, entry: BumpyEntry<T>) {
+        let start = entry.range.start;
+        self.link(entry);
+        self.expire_temporary(start);
+    }
+
+    /// Add an entry without checking whether it fits, and without counting
+    /// it as a change for temporary entries (see `insert_temporary()`).
+    fn link(&mut self, entry: BumpyEntry<T>) {
+        self.generation += 1;
+
+        if let Some(tracker) = &mut self.changes {
+            tracker.on_change(entry.range.start, self.generation);
+        }
+
+        if let Some(tracker) = &mut self.provenance {
+            tracker.on_insert(entry.range.start);
+        }
+
+        self.occupied += entry.range.len();
+        self.data.insert(entry.range.start, entry);
+    }
+
+    /// Remove the entry that starts exactly at `start`.
     ///
-    /// ```
+    /// Every removal goes through here, so this is where we keep track of
+    /// changes.
+    fn remove_start(&mut self, start: usize) -> Option<BumpyEntry<T>> {
+        let entry = self.unlink(start)?;
+        self.expire_temporary(start);
+
+        Some(entry)
+    }
+
+    /// Remove the entry that starts exactly at `start`, without counting
+    /// it as a change for temporary entries (see `insert_temporary()`).
+    fn unlink(&mut self, start: usize) -> Option<BumpyEntry<T>> {
+        let entry = self.data.remove(&start)?;
+        self.occupied -= entry.range.len();
+        self.generation += 1;
+        self.cache.on_remove(start);
+        self.pins.remove(&start);
+        self.temporary.on_remove(start);
+        self.types.on_remove(start, entry.range.len());
+
+        if let Some(tracker) = &mut self.changes {
+            tracker.on_remove(entry.range.clone(), self.generation);
+        }
+
+        if let Some(tracker) = &mut self.provenance {
+            tracker.on_remove(start);
+        }
+
+        if let Some(tracker) = &self.access {
+            tracker.on_remove(start);
+        }
+
+        Some(entry)
+    }
+
+    /// Note that the entry starting at `start` might be about to change.
+    ///
+    /// Every mutable reference we hand out goes through here - we can't tell
+    /// whether the caller actually changes anything, so assume they do.
+    fn touch(&mut self, start: usize) {
+        self.mark_modified(start);
+        self.expire_temporary(start);
+    }
+
+    /// Note that the entry starting at `start` might be about to change,
+    /// without counting it as a change for temporary entries (see
+    /// `insert_temporary()`).
+    fn mark_modified(&mut self, start: usize) {
+        self.generation += 1;
+
+        if let Some(tracker) = &mut self.changes {
+            tracker.on_change(start, self.generation);
+        }
+
+        if let Some(tracker) = &mut self.provenance {
+            tracker.on_modify(start);
+        }
+    }
+
+    /// Insert an entry that's known to fit, along with its tag, pin, and
+    /// expiry, without counting it as a change for temporary entries.
+    fn restore(&mut self, entry: BumpyEntry<T>, tag: Option<types::TypeTag>, pinned: bool, expires: Option<u64>) {
+        let (start, size) = (entry.range.start, entry.range.len());
+        self.link(entry);
+
+        if let Some(expires) = expires {
+            self.temporary.set_expiry(start, expires);
+        }
+
+        if let Some(tag) = tag {
+            self.types.set(start, size, tag);
+        }
+
+        if pinned {
+            self.pins.insert(start);
+        }
+    }
+
+    /// Insert an entry that implemented `AutoBumpyEntry`.
+    ///
+    /// The advantage of using this is that the entry knows its own size and
+    /// index, so the insertion code is much cleaner.
+    ///
+    /// See `insert()` for return and errors.
+    ///
+    /// ```
+    /// use bumpy_vector::{BumpyVector, AutoBumpyEntry};
+    /// use std::ops::Range;
+    ///
+    /// // A struct that has an index and size, then whatever other data
+    /// struct MyAutoEntry {
+    ///     size: usize,
+    ///     index: usize,
+    ///     // ...anything else
+    /// };
+    ///
+    /// // Simplest possible implementation for MyAutoEntry
+    /// impl AutoBumpyEntry for MyAutoEntry {
+    ///     fn range(&self) -> Range<usize> {
+    ///         self.index..(self.index + self.size)
+    ///     }
+    /// }
+    ///
+    /// // Create an entry
+    /// let entry = MyAutoEntry { index: 0, size: 10 };
+    ///
+    /// // Create a BumpyVector with the type we created
+    /// let mut h: BumpyVector<MyAutoEntry> = BumpyVector::new(10);
+    ///
+    /// // Insert it
+    /// assert!(h.insert_auto(entry).is_ok());
+    /// ```
+    pub fn insert_auto(&mut self, entry: T) -> Result<(), InsertError<T>>
+    where T: AutoBumpyEntry {
+        self.insert(entry.into())
+    }
+
+    /// Remove and return the entry at `index`.
+    ///
+    /// Note that the entry doesn't necessarily need to *start* at `index`,
+    /// just overlap it.
+    ///
+    /// Returns `None` if there's no entry there, or if it's locked (see
+    /// `lock_range()`).
+    ///
+    /// # Example
+    ///
+    /// ```
     /// use bumpy_vector::BumpyVector;
     ///
     /// // Create a 10-byte `BumpyVector`
@@ -442,71 +1018,77 @@ impl<'a, T> BumpyVector<T> {
     ///
     /// // Insert some data
     /// v.insert(("hello", 0..4).into()).unwrap();
+    /// v.insert(("hello", 4..8).into()).unwrap();
     ///
-    /// assert!(v.get(0).is_some());
-    /// assert!(v.get(1).is_some());
-    /// assert!(v.get(2).is_some());
-    /// assert!(v.get(3).is_some());
-    /// assert!(v.get(4).is_none());
-    /// assert!(v.get(5).is_none());
+    /// assert!(v.remove(0).is_some());
+    /// assert!(v.remove(0).is_none());
     ///
-    /// assert_eq!("hello", v.get(0).unwrap().entry);
-    /// assert_eq!("hello", v.get(1).unwrap().entry);
-    /// assert_eq!("hello", v.get(2).unwrap().entry);
-    /// assert_eq!("hello", v.get(3).unwrap().entry);
+    /// assert!(v.remove(6).is_some());
+    /// assert!(v.remove(6).is_none());
     /// ```
-    pub fn get(&self, index: usize) -> Option<&BumpyEntry<T>> {
+    pub fn remove(&mut self, index: usize) -> Option<BumpyEntry<T>> {
+        if self.read_only {
+            return None;
+        }
+
         // Try to get the real offset
         let real_offset = self.get_entry_start(index);
 
         // If there's no element, return none
         if let Some(o) = real_offset {
-            // Get the entry itself from the address
-            return self.data.get(&o);
+            // Leave it alone if it's locked
+            if self.data.get(&o).is_some_and(|e| self.is_locked(&e.range)) {
+                return None;
+            }
+
+            // Remove it!
+            if let Some(d) = self.remove_start(o) {
+                return Some(d);
+            }
         }
 
         None
     }
 
-    /// Return a mutable reference to an entry at the given index.
+    /// Remove and return the entry that *starts at* `index`.
+    ///
+    /// Unlike `remove()`, an entry that merely overlaps `index` is left
+    /// alone. Returns `None` if nothing starts there, or if the entry is
+    /// locked or the vector is read-only.
     ///
     /// # Example
     ///
     /// ```
     /// use bumpy_vector::BumpyVector;
     ///
-    /// // Create a small BumpyVector
-    /// let mut h: BumpyVector<String> = BumpyVector::new(10);
-    ///
-    /// // Insert a string to the start
-    /// h.insert((String::from("hello"), 0..2).into()).unwrap();
-    /// assert_eq!("hello", h.get(0).unwrap().entry);
-    /// assert_eq!("hello", h.get(1).unwrap().entry);
-    ///
-    /// // Get a mutable reference to the string
-    /// let s = h.get_mut(1).unwrap();
+    /// let mut v: BumpyVector<&str> = BumpyVector::new(10);
+    /// v.insert(("hello", 0..4).into()).unwrap();
     ///
-    /// // Modify it somehow
-    /// s.entry.make_ascii_uppercase();
+    /// assert!(v.remove_exact(2).is_none());
+    /// assert_eq!(1, v.len());
     ///
-    /// // Verify it's changed
-    /// assert_eq!("HELLO", h.get(0).unwrap().entry);
-    /// assert_eq!("HELLO", h.get(1).unwrap().entry);
+    /// assert_eq!("hello", v.remove_exact(0).unwrap().entry);
+    /// assert_eq!(0, v.len());
     /// ```
-    pub fn get_mut(&mut self, index: usize) -> Option<&mut BumpyEntry<T>> {
-        // Try to get the real offset
-        let real_offset = self.get_entry_start(index);
+    pub fn remove_exact(&mut self, index: usize) -> Option<BumpyEntry<T>> {
+        if self.read_only {
+            return None;
+        }
 
-        // If there's no element, return none
-        if let Some(o) = real_offset {
-            // Get the entry itself from the address
-            return self.data.get_mut(&o);
+        if self.is_locked(&self.data.get(&index)?.range) {
+            return None;
         }
 
-        None
+        self.remove_start(index)
     }
 
-    /// Return a reference to an entry that *starts at* the given index.
+    /// Remove and return a range of entries.
+    ///
+    /// Locked entries are skipped.
+    ///
+    /// Ranges work as expected for Rust ranges; see `std::ops::Range`. Any
+    /// part of the range past `max_size` is ignored; use
+    /// `remove_range_strict()` to treat that as an error instead.
     ///
     /// # Example
     ///
@@ -518,133 +1100,1382 @@ impl<'a, T> BumpyVector<T> {
     ///
     /// // Insert some data
     /// v.insert(("hello", 0..4).into()).unwrap();
+    /// v.insert(("hello", 4..8).into()).unwrap();
     ///
-    /// assert!(v.get_exact(0).is_some());
-    /// assert!(v.get_exact(1).is_none());
-    /// assert!(v.get_exact(2).is_none());
-    /// assert!(v.get_exact(3).is_none());
-    /// assert!(v.get_exact(4).is_none());
-    /// assert!(v.get_exact(5).is_none());
-    ///
-    /// assert_eq!("hello", v.get_exact(0).unwrap().entry);
+    /// assert_eq!(2, v.remove_range(0..10).len());
+    /// assert_eq!(0, v.remove_range(0..10).len());
     /// ```
-    pub fn get_exact(&self, index: usize) -> Option<&BumpyEntry<T>> {
-        self.data.get(&index)
+    pub fn remove_range(&mut self, range: Range<usize>) -> Vec<BumpyEntry<T>> {
+        let starts: Vec<usize> = self.get_range(range).iter().map(|e| e.range.start).collect();
+
+        starts.into_iter().filter_map(|start| self.remove(start)).collect()
     }
 
-    /// Return a mutable reference to an entry at exactly the given index.
+    /// Remove and return a range of entries, like `remove_range()`, but
+    /// refuse a range that goes past `max_size` or ends before it starts.
     ///
     /// # Example
     ///
     /// ```
     /// use bumpy_vector::BumpyVector;
     ///
-    /// // Create a small BumpyVector
-    /// let mut h: BumpyVector<String> = BumpyVector::new(10);
+    /// let mut v: BumpyVector<&str> = BumpyVector::new(10);
+    /// v.insert(("hello", 0..4).into()).unwrap();
     ///
-    /// // Insert a string to the start
-    /// h.insert((String::from("hello"), 0..2).into()).unwrap();
-    /// assert_eq!("hello", h.get_exact(0).unwrap().entry);
-    /// assert!(h.get_exact(1).is_none());
+    /// assert!(v.remove_range_strict(0..11).is_err());
+    /// assert_eq!(1, v.len());
     ///
-    /// // Get a mutable reference to the string
-    /// let s = h.get_exact_mut(0).unwrap();
+    /// assert_eq!(1, v.remove_range_strict(0..10).unwrap().len());
+    /// ```
+    pub fn remove_range_strict(&mut self, range: Range<usize>) -> BumpyResult<Vec<BumpyEntry<T>>> {
+        self.check_range(&range)?;
+
+        Ok(self.remove_range(range))
+    }
+
+    /// Keep only the entries that `f` returns `true` for, and remove the
+    /// rest.
     ///
-    /// // Modify it somehow
-    /// s.entry.make_ascii_uppercase();
+    /// The entries are visited once each, in order. Like `remove_range()`,
+    /// locked entries are kept no matter what `f` says, and nothing is
+    /// removed from a read-only vector.
     ///
-    /// // Verify it's changed
-    /// assert_eq!("HELLO", h.get_exact(0).unwrap().entry);
-    /// assert!(h.get_exact(1).is_none());
+    /// # Example
+    ///
+    /// ```
+    /// use bumpy_vector::BumpyVector;
+    ///
+    /// let mut v: BumpyVector<&str> = BumpyVector::new(10);
+    /// v.insert(("undefined", 0..1).into()).unwrap();
+    /// v.insert(("header", 1..4).into()).unwrap();
+    /// v.insert(("undefined", 4..5).into()).unwrap();
+    ///
+    /// v.retain(|e| e.entry != "undefined");
+    ///
+    /// assert_eq!(1, v.len());
+    /// assert_eq!("header", v.get(1).unwrap().entry);
     /// ```
-    pub fn get_exact_mut(&mut self, index: usize) -> Option<&mut BumpyEntry<T>> {
-        self.data.get_mut(&index)
+    pub fn retain<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&BumpyEntry<T>) -> bool
+    {
+        if self.read_only {
+            return;
+        }
+
+        let starts: Vec<usize> = self.data.values()
+            .filter(|e| !f(e) && !self.is_locked(&e.range))
+            .map(|e| e.range.start)
+            .collect();
+
+        for start in starts {
+            self.remove_start(start);
+        }
     }
 
-    /// Return a vector of entries within the given range.
+    /// Remove the entries that `f` returns `true` for, and return them in
+    /// order.
     ///
-    /// Note that the first entry doesn't need to *start* at the given start
-    /// index it can simply be contained therein.
+    /// This is the opposite of `retain()`, except that the removed entries
+    /// are handed back instead of dropped. As with `retain()`, locked entries
+    /// are kept, and nothing is removed from a read-only vector.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bumpy_vector::BumpyVector;
+    ///
+    /// let mut v: BumpyVector<(&str, u32)> = BumpyVector::new(10);
+    /// v.insert((("a", 1), 0..2).into()).unwrap();
+    /// v.insert((("b", 2), 2..4).into()).unwrap();
+    /// v.insert((("c", 1), 4..6).into()).unwrap();
+    ///
+    /// // Move everything from pass 1 into its own vector
+    /// let mut archive: BumpyVector<(&str, u32)> = BumpyVector::new(10);
+    /// for e in v.extract_if(|e| e.entry.1 == 1) {
+    ///     archive.insert(e).unwrap();
+    /// }
+    ///
+    /// assert_eq!(1, v.len());
+    /// assert_eq!(2, archive.len());
+    /// ```
+    pub fn extract_if<F>(&mut self, mut f: F) -> std::vec::IntoIter<BumpyEntry<T>>
+    where
+        F: FnMut(&BumpyEntry<T>) -> bool
+    {
+        if self.read_only {
+            return Vec::new().into_iter();
+        }
+
+        let starts: Vec<usize> = self.data.values()
+            .filter(|e| f(e) && !self.is_locked(&e.range))
+            .map(|e| e.range.start)
+            .collect();
+
+        let result: Vec<BumpyEntry<T>> = starts.into_iter().filter_map(|start| self.remove_start(start)).collect();
+
+        result.into_iter()
+    }
+
+    /// Remove every entry, keeping `max_size`.
     ///
-    /// Ranges work as expected for Rust ranges; see `std::ops::Range`.
+    /// If any part of the vector is locked, the locked entries are kept, as
+    /// with `remove_range()`. Otherwise this drops everything at once,
+    /// without looking up or removing entries one at a time. Nothing is
+    /// removed from a read-only vector.
     ///
     /// # Example
     ///
     /// ```
     /// use bumpy_vector::BumpyVector;
     ///
-    /// // Create a 10-byte `BumpyVector`
     /// let mut v: BumpyVector<&str> = BumpyVector::new(10);
+    /// v.insert(("hello", 0..4).into()).unwrap();
+    /// v.insert(("world", 4..8).into()).unwrap();
     ///
-    /// // Insert some data with a gap in the middle
-    /// v.insert(("hello", 0..2).into()).unwrap();
-    /// v.insert(("hello", 4..6).into()).unwrap();
+    /// v.clear();
+    /// assert_eq!(0, v.len());
+    /// assert_eq!(10, v.max_size());
+    /// ```
+    pub fn clear(&mut self) {
+        self.drain();
+    }
+
+    /// Remove every entry, keeping `max_size`, and return them in order.
+    ///
+    /// This works like `clear()`, including keeping locked entries, but
+    /// hands the entries back instead of dropping them. Nothing is cloned.
+    ///
+    /// # Example
     ///
-    /// assert_eq!(1, v.get_range(0..1).len());
-    /// assert_eq!(1, v.get_range(0..2).len());
-    /// assert_eq!(1, v.get_range(0..3).len());
-    /// assert_eq!(1, v.get_range(0..4).len());
-    /// assert_eq!(2, v.get_range(0..5).len());
     /// ```
+    /// use bumpy_vector::{BumpyEntry, BumpyVector};
     ///
-    /// # Panics
+    /// let mut v: BumpyVector<String> = BumpyVector::new(10);
+    /// v.insert((String::from("world"), 4..8).into()).unwrap();
+    /// v.insert((String::from("hello"), 0..4).into()).unwrap();
     ///
-    /// Panics if an entry's size is 0. That shouldn't be possible short of
-    /// tinkering with internal state.
-    pub fn get_range(&self, range: Range<usize>) -> Vec<&BumpyEntry<T>> {
-        // We're stuffing all of our data into a vector to iterate over it
-        let mut result: Vec<&BumpyEntry<T>> = Vec::new();
+    /// // Move everything into a bigger vector
+    /// let mut bigger: BumpyVector<String> = BumpyVector::new(100);
+    /// for e in v.drain() {
+    ///     bigger.insert(e).unwrap();
+    /// }
+    ///
+    /// assert_eq!(0, v.len());
+    /// assert_eq!("hello", bigger.get(0).unwrap().entry);
+    /// ```
+    pub fn drain(&mut self) -> std::vec::IntoIter<BumpyEntry<T>> {
+        if self.read_only || self.data.is_empty() {
+            return Vec::new().into_iter();
+        }
 
-        // Start at the first entry left of what they wanted, if it exists
-        let mut i = match self.get_entry_start(range.start) {
-            Some(e) => e,
-            None    => range.start,
-        };
+        if !self.locks.is_empty() {
+            let starts: Vec<usize> = self.data.values()
+                .filter(|e| !self.is_locked(&e.range))
+                .map(|e| e.range.start)
+                .collect();
 
-        // Loop up to <length> bytes after the starting index
-        while i < range.end && i < self.max_size {
-            // Pull the entry out, if it exists
-            if let Some(e) = self.data.get(&i) {
-                // Add the entry to the vector, and jump over it
-                result.push(e);
+            let result: Vec<BumpyEntry<T>> = starts.into_iter().filter_map(|start| self.remove_start(start)).collect();
 
-                // Prevent an infinite loop
-                if e.range.is_empty() {
-                    panic!("Entry cannot be empty!");
-                }
+            return result.into_iter();
+        }
 
-                // Skip to the next range
-                i = e.range.end;
-            } else {
-                i += 1;
-            }
+        let data = std::mem::take(&mut self.data);
+        self.occupied = 0;
+        self.generation += 1;
+        self.cache.on_clear();
+        self.pins.clear();
+        self.temporary.on_clear();
+        self.types.on_clear();
+
+        if let Some(tracker) = &mut self.changes {
+            tracker.on_clear(data.values().map(|e| e.range.clone()), self.generation);
         }
 
-        result
-    }
+        if let Some(tracker) = &mut self.provenance {
+            tracker.on_clear();
+        }
 
-    /// Returns the number of entries.
-    pub fn len(&self) -> usize {
-        // Return the number of entries
-        return self.data.len();
+        if let Some(tracker) = &self.access {
+            tracker.on_clear();
+        }
+
+        let result: Vec<BumpyEntry<T>> = data.into_values().collect();
+
+        result.into_iter()
     }
 
-    pub fn max_size(&self) -> usize {
-        return self.max_size;
+    /// Return a reference to an entry at the given index.
+    ///
+    /// Note that the entry doesn't necessarily need to *start* at the given
+    /// index, it can simply be contained there.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bumpy_vector::BumpyVector;
+    ///
+    /// // Create a 10-byte `BumpyVector`
+    /// let mut v: BumpyVector<&str> = BumpyVector::new(10);
+    ///
+    /// // Insert some data
+    /// v.insert(("hello", 0..4).into()).unwrap();
+    ///
+    /// assert!(v.get(0).is_some());
+    /// assert!(v.get(1).is_some());
+    /// assert!(v.get(2).is_some());
+    /// assert!(v.get(3).is_some());
+    /// assert!(v.get(4).is_none());
+    /// assert!(v.get(5).is_none());
+    ///
+    /// assert_eq!("hello", v.get(0).unwrap().entry);
+    /// assert_eq!("hello", v.get(1).unwrap().entry);
+    /// assert_eq!("hello", v.get(2).unwrap().entry);
+    /// assert_eq!("hello", v.get(3).unwrap().entry);
+    /// ```
+    pub fn get(&self, index: usize) -> Option<&BumpyEntry<T>> {
+        // Try to get the real offset
+        let real_offset = self.get_entry_start(index);
+
+        // If there's no element, return none
+        if let Some(o) = real_offset {
+            // Get the entry itself from the address
+            self.record_access(o);
+            return self.data.get(&o);
+        }
+
+        None
+    }
+
+    /// Look up the entry at `index`, like `get()`, and return `f` of its
+    /// value.
+    ///
+    /// Handy when only part of the value is needed, since the result doesn't
+    /// borrow the vector.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bumpy_vector::BumpyVector;
+    ///
+    /// let mut v: BumpyVector<String> = BumpyVector::new(10);
+    /// v.insert((String::from("hello"), 0..4).into()).unwrap();
+    ///
+    /// let length = v.get_map(2, |s| s.len());
+    /// v.insert((String::from("world"), 4..8).into()).unwrap();
+    ///
+    /// assert_eq!(Some(5), length);
+    /// assert_eq!(None, v.get_map(9, |s| s.len()));
+    /// ```
+    pub fn get_map<U, F>(&self, index: usize, f: F) -> Option<U>
+    where
+        F: FnOnce(&T) -> U
+    {
+        self.get(index).map(|e| f(&e.entry))
+    }
+
+    /// Return a mutable reference to the value of the entry at the given
+    /// index.
+    ///
+    /// Like `get()`, the entry doesn't need to *start* at the given index. The
+    /// range is a copy - changing it wouldn't move the entry, it would just
+    /// break the vector, so only the value can be modified.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bumpy_vector::BumpyVector;
+    ///
+    /// // Create a small BumpyVector
+    /// let mut h: BumpyVector<String> = BumpyVector::new(10);
+    ///
+    /// // Insert a string to the start
+    /// h.insert((String::from("hello"), 0..2).into()).unwrap();
+    /// assert_eq!("hello", h.get(0).unwrap().entry);
+    /// assert_eq!("hello", h.get(1).unwrap().entry);
+    ///
+    /// // Get a mutable reference to the string
+    /// let s = h.get_mut(1).unwrap();
+    ///
+    /// // Modify it somehow
+    /// s.entry.make_ascii_uppercase();
+    ///
+    /// // Verify it's changed
+    /// assert_eq!("HELLO", h.get(0).unwrap().entry);
+    /// assert_eq!("HELLO", h.get(1).unwrap().entry);
+    /// ```
+    pub fn get_mut(&mut self, index: usize) -> Option<BumpyEntry<&mut T>> {
+        if self.read_only {
+            return None;
+        }
+
+        // Try to get the real offset
+        let real_offset = self.get_entry_start(index);
+
+        // If there's no element, return none
+        if let Some(o) = real_offset {
+            // Get the entry itself from the address
+            self.record_access(o);
+            self.touch(o);
+            return self.data.get_mut(&o).map(|e| BumpyEntry {
+                entry: &mut e.entry,
+                range: e.range.clone(),
+            });
+        }
+
+        None
+    }
+
+    /// Replace the value of the entry at `index`, keeping its range.
+    ///
+    /// Note that the entry doesn't necessarily need to *start* at `index`,
+    /// just overlap it. Locks don't matter, since the layout doesn't change.
+    ///
+    /// # Return
+    ///
+    /// Returns the old value, or `None` (dropping `value`) if there's no
+    /// entry there or the vector is read-only.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bumpy_vector::BumpyVector;
+    ///
+    /// let mut v: BumpyVector<&str> = BumpyVector::new(10);
+    /// v.insert(("hello", 0..4).into()).unwrap();
+    ///
+    /// assert_eq!(Some("hello"), v.replace_value(2, "world"));
+    /// assert_eq!("world", v.get(0).unwrap().entry);
+    /// assert_eq!(0..4, v.get(0).unwrap().range);
+    ///
+    /// assert_eq!(None, v.replace_value(5, "nothing"));
+    /// ```
+    pub fn replace_value(&mut self, index: usize, value: T) -> Option<T> {
+        self.get_mut(index).map(|e| std::mem::replace(e.entry, value))
+    }
+
+    /// Replace the value of the entry covering `index` with `value`, but only
+    /// if it's still equal to `expected`.
+    ///
+    /// This is for when a value is read, something slow is worked out from
+    /// it, and the result is written back: if the value changed in the
+    /// meantime (say, the user edited it), the result is stale and shouldn't
+    /// clobber the change.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bumpy_vector::{BumpyError, BumpyVector};
+    ///
+    /// let mut v: BumpyVector<String> = BumpyVector::new(10);
+    /// v.insert((String::from("guess"), 0..4).into()).unwrap();
+    ///
+    /// let read = v.get(0).unwrap().entry.clone();
+    ///
+    /// // The user edits it while the worker is busy
+    /// v.replace_value(0, String::from("user's edit"));
+    ///
+    /// let e = v.compare_and_replace(0, &read, String::from("worker's result")).unwrap_err();
+    /// assert!(matches!(e.reason, BumpyError::Mismatch));
+    /// assert_eq!("user's edit", v.get(0).unwrap().entry);
+    /// ```
+    ///
+    /// # Return
+    ///
+    /// Returns the old value. Otherwise, returns a `CasError` with `value`
+    /// and `Mismatch` if the value isn't `expected`, `NotFound` if there's no
+    /// entry, or `ReadOnly` if the vector is read-only.
+    pub fn compare_and_replace(&mut self, index: usize, expected: &T, value: T) -> Result<T, CasError<T>>
+    where
+        T: PartialEq
+    {
+        if self.read_only {
+            return Err(CasError { value, reason: BumpyError::ReadOnly });
+        }
+
+        let start = match self.get_entry_start(index) {
+            Some(s) => s,
+            None => return Err(CasError { value, reason: BumpyError::NotFound }),
+        };
+
+        if self.data[&start].entry != *expected {
+            return Err(CasError { value, reason: BumpyError::Mismatch });
+        }
+
+        match self.get_exact_mut(start) {
+            Some(e) => Ok(std::mem::replace(e.entry, value)),
+            None => unreachable!("Verified entry disappeared"),
+        }
+    }
+
+    /// Return a reference to an entry that *starts at* the given index.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bumpy_vector::BumpyVector;
+    ///
+    /// // Create a 10-byte `BumpyVector`
+    /// let mut v: BumpyVector<&str> = BumpyVector::new(10);
+    ///
+    /// // Insert some data
+    /// v.insert(("hello", 0..4).into()).unwrap();
+    ///
+    /// assert!(v.get_exact(0).is_some());
+    /// assert!(v.get_exact(1).is_none());
+    /// assert!(v.get_exact(2).is_none());
+    /// assert!(v.get_exact(3).is_none());
+    /// assert!(v.get_exact(4).is_none());
+    /// assert!(v.get_exact(5).is_none());
+    ///
+    /// assert_eq!("hello", v.get_exact(0).unwrap().entry);
+    /// ```
+    pub fn get_exact(&self, index: usize) -> Option<&BumpyEntry<T>> {
+        let entry = self.data.get(&index)?;
+        self.record_access(index);
+
+        Some(entry)
+    }
+
+    /// Return a mutable reference to the value of the entry that *starts at*
+    /// the given index.
+    ///
+    /// As with `get_mut()`, only the value can be modified.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bumpy_vector::BumpyVector;
+    ///
+    /// // Create a small BumpyVector
+    /// let mut h: BumpyVector<String> = BumpyVector::new(10);
+    ///
+    /// // Insert a string to the start
+    /// h.insert((String::from("hello"), 0..2).into()).unwrap();
+    /// assert_eq!("hello", h.get_exact(0).unwrap().entry);
+    /// assert!(h.get_exact(1).is_none());
+    ///
+    /// // Get a mutable reference to the string
+    /// let s = h.get_exact_mut(0).unwrap();
+    ///
+    /// // Modify it somehow
+    /// s.entry.make_ascii_uppercase();
+    ///
+    /// // Verify it's changed
+    /// assert_eq!("HELLO", h.get_exact(0).unwrap().entry);
+    /// assert!(h.get_exact(1).is_none());
+    /// ```
+    pub fn get_exact_mut(&mut self, index: usize) -> Option<BumpyEntry<&mut T>> {
+        if self.read_only || !self.data.contains_key(&index) {
+            return None;
+        }
+
+        self.record_access(index);
+        self.touch(index);
+        self.data.get_mut(&index).map(|e| BumpyEntry {
+            entry: &mut e.entry,
+            range: e.range.clone(),
+        })
+    }
+
+    /// Binary search the entries, in order, with a comparison function.
+    ///
+    /// Like `slice::binary_search_by()`, `f` should return whether an entry
+    /// is `Less`, `Equal`, or `Greater` than the target, and entries must be
+    /// sorted by it - which is the case when the values have a key that goes
+    /// up with their index, like timestamps in a capture. Gaps are skipped,
+    /// so this only calls `f` about `log2(max_size)` times.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bumpy_vector::BumpyVector;
+    ///
+    /// // Packets, by timestamp
+    /// let mut v: BumpyVector<u64> = BumpyVector::new(1000);
+    /// v.insert((100, 0..60).into()).unwrap();
+    /// v.insert((250, 200..264).into()).unwrap();
+    /// v.insert((300, 500..540).into()).unwrap();
+    ///
+    /// assert_eq!(200..264, v.binary_search_by(|e| e.entry.cmp(&250)).unwrap().range);
+    ///
+    /// // A packet at 260 would go after the one at 250
+    /// assert_eq!(Err(264), v.binary_search_by(|e| e.entry.cmp(&260)).map(|e| e.range.start));
+    /// ```
+    ///
+    /// # Return
+    ///
+    /// Returns the matching entry if there is one. If there are several, any
+    /// of them could be returned. Otherwise, returns the index where a
+    /// matching entry could start: the end of the last entry that's `Less`,
+    /// or `0`.
+    pub fn binary_search_by<F>(&self, mut f: F) -> Result<&BumpyEntry<T>, usize>
+    where
+        F: FnMut(&BumpyEntry<T>) -> std::cmp::Ordering
+    {
+        // Everything starting before `low` is `Less`, and everything starting
+        // at or after `high` is `Greater`
+        let mut low = 0;
+        let mut high = self.max_size;
+
+        while low < high {
+            let middle = low + (high - low) / 2;
+
+            // Search the top half for an entry to compare with
+            let e = match self.data.range(middle..high).next() {
+                Some((_, e)) => e,
+                None => {
+                    high = middle;
+                    continue;
+                },
+            };
+
+            match f(e) {
+                std::cmp::Ordering::Less    => low = e.range.end,
+                std::cmp::Ordering::Equal   => return Ok(e),
+                std::cmp::Ordering::Greater => high = e.range.start,
+            }
+        }
+
+        Err(low)
+    }
+
+    /// Iterate over every entry, in order, with a mutable reference to each
+    /// value.
+    ///
+    /// As with `get_mut()`, the ranges are copies, and only the values can be
+    /// modified.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bumpy_vector::BumpyVector;
+    ///
+    /// let mut v: BumpyVector<String> = BumpyVector::new(10);
+    /// v.insert((String::from("hello"), 0..2).into()).unwrap();
+    /// v.insert((String::from("world"), 5..8).into()).unwrap();
+    ///
+    /// for e in v.iter_mut() {
+    ///     e.entry.push_str(&format!(" @ {}", e.range.start));
+    /// }
+    ///
+    /// assert_eq!("hello @ 0", v.get(1).unwrap().entry);
+    /// assert_eq!("world @ 5", v.get(6).unwrap().entry);
+    /// ```
+    pub fn iter_mut(&mut self) -> std::vec::IntoIter<BumpyEntry<&mut T>> {
+        if self.read_only {
+            return Vec::new().into_iter();
+        }
+
+        // We can't tell which ones the caller will change, so assume all of
+        // them - but it's still only one change for temporary entries
+        let starts: Vec<usize> = self.data.keys().copied().collect();
+        for start in &starts {
+            self.mark_modified(*start);
+        }
+        self.expire_temporary_except(|s| starts.binary_search(&s).is_ok());
+
+        let result: Vec<BumpyEntry<&mut T>> = self.data.values_mut().map(|e| BumpyEntry {
+            entry: &mut e.entry,
+            range: e.range.clone(),
+        }).collect();
+
+        result.into_iter()
+    }
+
+    /// Call `f` on every value, in order, along with where its entry starts
+    /// and ends.
+    ///
+    /// This is for passes that rewrite every value, like upgrading old
+    /// annotations to a new format. It's the same as looping over
+    /// `iter_mut()`, but without building a list of every entry first.
+    /// Nothing happens if the vector is read-only.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bumpy_vector::BumpyVector;
+    ///
+    /// let mut v: BumpyVector<String> = BumpyVector::new(10);
+    /// v.insert((String::from("a"), 0..2).into()).unwrap();
+    /// v.insert((String::from("b"), 5..8).into()).unwrap();
+    ///
+    /// v.normalize_values(|value, start, end| *value = format!("{} @ {}..{}", value, start, end));
+    ///
+    /// assert_eq!("a @ 0..2", v.get(1).unwrap().entry);
+    /// assert_eq!("b @ 5..8", v.get(6).unwrap().entry);
+    /// ```
+    pub fn normalize_values<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&mut T, usize, usize)
+    {
+        if self.read_only {
+            return;
+        }
+
+        for e in self.data.values_mut() {
+            f(&mut e.entry, e.range.start, e.range.end);
+        }
+
+        // Everything might have changed, but it's only one change for
+        // temporary entries
+        let starts: Vec<usize> = self.data.keys().copied().collect();
+        for start in &starts {
+            self.mark_modified(*start);
+        }
+        self.expire_temporary_except(|s| starts.binary_search(&s).is_ok());
+    }
+
+    /// Return a vector of entries within the given range.
+    ///
+    /// Note that the first entry doesn't need to *start* at the given start
+    /// index it can simply be contained therein.
+    ///
+    /// Ranges work as expected for Rust ranges; see `std::ops::Range`. Any
+    /// part of the range past `max_size` is ignored; use `get_range_strict()`
+    /// to treat that as an error instead. An empty range never contains
+    /// anything, even if it's inside an entry.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bumpy_vector::BumpyVector;
+    ///
+    /// // Create a 10-byte `BumpyVector`
+    /// let mut v: BumpyVector<&str> = BumpyVector::new(10);
+    ///
+    /// // Insert some data with a gap in the middle
+    /// v.insert(("hello", 0..2).into()).unwrap();
+    /// v.insert(("hello", 4..6).into()).unwrap();
+    ///
+    /// assert_eq!(1, v.get_range(0..1).len());
+    /// assert_eq!(1, v.get_range(0..2).len());
+    /// assert_eq!(1, v.get_range(0..3).len());
+    /// assert_eq!(1, v.get_range(0..4).len());
+    /// assert_eq!(2, v.get_range(0..5).len());
+    /// ```
+    pub fn get_range(&self, range: Range<usize>) -> Vec<&BumpyEntry<T>> {
+        // An empty range can't contain anything (even if it's inside an entry)
+        if range.is_empty() {
+            return Vec::new();
+        }
+
+        // Start at the first entry left of what they wanted, if it exists
+        let start = match self.get_entry_start(range.start) {
+            Some(e) => e,
+            None    => range.start,
+        };
+        let end = std::cmp::min(range.end, self.max_size);
+
+        if start >= end {
+            return Vec::new();
+        }
+
+        self.data.range(start..end).map(|(_, e)| e).collect()
+    }
+
+    /// Return a vector of entries within the given range, like
+    /// `get_range()`, but refuse a range that goes past `max_size` or ends
+    /// before it starts.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bumpy_vector::BumpyVector;
+    ///
+    /// let mut v: BumpyVector<&str> = BumpyVector::new(10);
+    /// v.insert(("hello", 0..4).into()).unwrap();
+    ///
+    /// assert_eq!(1, v.get_range_strict(0..10).unwrap().len());
+    /// assert!(v.get_range_strict(0..11).is_err());
+    /// assert!(v.get_range_strict(100..usize::MAX).is_err());
+    /// ```
+    pub fn get_range_strict(&self, range: Range<usize>) -> BumpyResult<Vec<&BumpyEntry<T>>> {
+        self.check_range(&range)?;
+
+        Ok(self.get_range(range))
+    }
+
+    /// Return the entries within the given range, like `get_range()`, with
+    /// each value replaced by `f` of it.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bumpy_vector::BumpyVector;
+    ///
+    /// let mut v: BumpyVector<String> = BumpyVector::new(10);
+    /// v.insert((String::from("hello"), 0..2).into()).unwrap();
+    /// v.insert((String::from("hi"), 4..6).into()).unwrap();
+    ///
+    /// let lengths = v.get_range_map(0..10, |s| s.len());
+    /// assert_eq!(5, lengths[0].entry);
+    /// assert_eq!(4..6, lengths[1].range);
+    /// ```
+    pub fn get_range_map<U, F>(&self, range: Range<usize>, mut f: F) -> Vec<BumpyEntry<U>>
+    where
+        F: FnMut(&T) -> U
+    {
+        self.iter_range(range).map(|e| BumpyEntry {
+            entry: f(&e.entry),
+            range: e.range.clone(),
+        }).collect()
+    }
+
+    /// Return the entries within the given range, like `get_range()`, plus
+    /// up to `pad_entries` more on each side.
+    ///
+    /// The extra entries are the closest ones, however far away they are.
+    /// For an empty range, that's just the entries on either side of
+    /// `range.start`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bumpy_vector::BumpyVector;
+    ///
+    /// let mut v: BumpyVector<&str> = BumpyVector::new(100);
+    /// v.insert(("a", 0..10).into()).unwrap();
+    /// v.insert(("b", 10..20).into()).unwrap();
+    /// v.insert(("c", 20..30).into()).unwrap();
+    /// v.insert(("d", 50..60).into()).unwrap();
+    /// v.insert(("e", 90..100).into()).unwrap();
+    ///
+    /// let window: Vec<&str> = v.get_range_padded(25..30, 2).iter().map(|e| e.entry).collect();
+    /// assert_eq!(vec!["a", "b", "c", "d", "e"], window);
+    ///
+    /// let window: Vec<&str> = v.get_range_padded(55..56, 1).iter().map(|e| e.entry).collect();
+    /// assert_eq!(vec!["c", "d", "e"], window);
+    /// ```
+    pub fn get_range_padded(&self, range: Range<usize>, pad_entries: usize) -> Vec<&BumpyEntry<T>> {
+        let (start, end) = if range.is_empty() {
+            let start = std::cmp::min(range.start, self.max_size);
+
+            (start, start)
+        } else {
+            let start = self.get_entry_start(range.start).unwrap_or(range.start);
+
+            (start, std::cmp::max(start, std::cmp::min(range.end, self.max_size)))
+        };
+
+        let mut result: Vec<&BumpyEntry<T>> = self.data.range(..start).rev().take(pad_entries).map(|(_, e)| e).collect();
+        result.reverse();
+
+        result.extend(self.data.range(start..end).map(|(_, e)| e));
+        result.extend(self.data.range(end..).take(pad_entries).map(|(_, e)| e));
+
+        result
+    }
+
+    /// Build the range of `len` indexes starting at `start`, making sure it
+    /// fits in the vector.
+    ///
+    /// # Return
+    ///
+    /// Returns `BumpyError::Overflow` if `start + len` doesn't fit in a
+    /// `usize`, or `BumpyError::OutOfBounds` if the range goes past
+    /// `max_size`. An empty range is fine, as long as it's not past the end.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bumpy_vector::BumpyVector;
+    ///
+    /// let v: BumpyVector<&str> = BumpyVector::new(100);
+    ///
+    /// assert_eq!(90..100, v.range_from(90, 10).unwrap());
+    /// assert!(v.range_from(90, 11).is_err());
+    /// assert!(v.range_from(usize::MAX, 2).is_err());
+    /// ```
+    pub fn range_from(&self, start: usize, len: usize) -> BumpyResult<Range<usize>> {
+        let end = match start.checked_add(len) {
+            Some(e) => e,
+            None    => return Err(BumpyError::Overflow),
+        };
+
+        let range = start..end;
+        self.check_range(&range)?;
+
+        Ok(range)
+    }
+
+    /// Build the range of `len` indexes starting at `start`, cut off at
+    /// `max_size`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bumpy_vector::BumpyVector;
+    ///
+    /// let v: BumpyVector<&str> = BumpyVector::new(100);
+    ///
+    /// assert_eq!(90..100, v.range_from_saturating(90, 11));
+    /// assert_eq!(100..100, v.range_from_saturating(usize::MAX, 2));
+    /// ```
+    pub fn range_from_saturating(&self, start: usize, len: usize) -> Range<usize> {
+        let end = std::cmp::min(start.saturating_add(len), self.max_size);
+
+        std::cmp::min(start, end)..end
+    }
+
+    /// Make sure `range` is entirely within the vector.
+    fn check_range(&self, range: &Range<usize>) -> BumpyResult<()> {
+        if range.start > range.end {
+            return Err(BumpyError::InvalidRange);
+        }
+
+        if range.end > self.max_size {
+            return Err(BumpyError::OutOfBounds);
+        }
+
+        Ok(())
+    }
+
+    /// Return the entries within each of several ranges, in one pass.
+    ///
+    /// The result has one list per range, in the same order as `ranges`, and
+    /// each list is in order. An entry that overlaps more than one of the
+    /// ranges is only returned once, in the list for the first range (in the
+    /// order given) that it overlaps.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bumpy_vector::BumpyVector;
+    ///
+    /// let mut v: BumpyVector<&str> = BumpyVector::new(100);
+    /// v.insert(("a", 0..10).into()).unwrap();
+    /// v.insert(("b", 10..30).into()).unwrap();
+    /// v.insert(("c", 50..60).into()).unwrap();
+    ///
+    /// let result = v.get_multi_ranges(&[5..15, 20..25, 55..56]);
+    /// assert_eq!(3, result.len());
+    ///
+    /// // "b" is in the first two ranges, but only shows up once
+    /// assert_eq!(vec!["a", "b"], result[0].iter().map(|e| e.entry).collect::<Vec<_>>());
+    /// assert_eq!(0, result[1].len());
+    /// assert_eq!(vec!["c"], result[2].iter().map(|e| e.entry).collect::<Vec<_>>());
+    /// ```
+    pub fn get_multi_ranges(&self, ranges: &[Range<usize>]) -> Vec<Vec<&BumpyEntry<T>>> {
+        let mut result: Vec<Vec<&BumpyEntry<T>>> = vec![Vec::new(); ranges.len()];
+
+        // Merge the ranges into sorted, non-overlapping spans, so we only
+        // walk over each part of the vector once
+        let mut sorted: Vec<&Range<usize>> = ranges.iter().filter(|r| !r.is_empty()).collect();
+        sorted.sort_by_key(|r| r.start);
+
+        let mut spans: Vec<Range<usize>> = Vec::new();
+        for r in sorted {
+            match spans.last_mut() {
+                Some(last) if r.start <= last.end => last.end = std::cmp::max(last.end, r.end),
+                _ => spans.push(r.clone()),
+            }
+        }
+
+        // An entry can still straddle the gap between two spans, so keep
+        // track of how far we've gotten
+        let mut done = 0;
+
+        for span in spans {
+            for e in self.get_range(span) {
+                if e.range.start < done {
+                    continue;
+                }
+                done = e.range.end;
+
+                if let Some(i) = ranges.iter().position(|r| crate::ranges::overlaps(r, &e.range)) {
+                    result[i].push(e);
+                }
+            }
+        }
+
+        result
+    }
+
+    /// Return the entries within the given range, each with the size of the
+    /// gaps before and after it.
+    ///
+    /// This is the same set of entries as `get_range()`. The gaps are
+    /// measured to the neighbouring entries even if those are outside of
+    /// `range`, so the first and last entries have the right spacing too.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bumpy_vector::BumpyVector;
+    ///
+    /// let mut v: BumpyVector<&str> = BumpyVector::new(20);
+    /// v.insert(("a", 0..2).into()).unwrap();
+    /// v.insert(("b", 5..6).into()).unwrap();
+    /// v.insert(("c", 6..10).into()).unwrap();
+    ///
+    /// let result = v.get_annotated_range(4..7);
+    /// assert_eq!(2, result.len());
+    ///
+    /// assert_eq!("b", result[0].entry.entry);
+    /// assert_eq!(3,   result[0].gap_before);
+    /// assert_eq!(0,   result[0].gap_after);
+    ///
+    /// assert_eq!("c", result[1].entry.entry);
+    /// assert_eq!(0,   result[1].gap_before);
+    /// assert_eq!(10,  result[1].gap_after);
+    /// ```
+    pub fn get_annotated_range(&self, range: Range<usize>) -> Vec<AnnotatedEntry<'_, T>> {
+        let entries = self.get_range(range);
+
+        let mut result: Vec<AnnotatedEntry<'_, T>> = Vec::with_capacity(entries.len());
+
+        // Only the first entry needs to go looking for its left neighbour
+        let mut previous_end = match entries.first() {
+            Some(e) => self.entry_before(e.range.start).map_or(0, |p| p.range.end),
+            None    => return result,
+        };
+
+        for (i, e) in entries.iter().enumerate() {
+            // Likewise, only the last entry needs to look right
+            let next_start = match entries.get(i + 1) {
+                Some(next) => next.range.start,
+                None       => self.entry_after(e.range.end).map_or(self.max_size, |n| n.range.start),
+            };
+
+            result.push(AnnotatedEntry {
+                entry: e,
+                gap_before: e.range.start - previous_end,
+                gap_after: next_start - e.range.end,
+            });
+
+            previous_end = e.range.end;
+        }
+
+        result
+    }
+
+    /// Return the entries within the given range, grouped into blocks
+    /// wherever the gap between two entries is smaller than
+    /// `gap_merge_threshold`.
+    ///
+    /// This is for drawing things like a minimap, where a lot of tiny holes
+    /// are just noise. The entries are the same ones `get_range()` returns;
+    /// a threshold of 0 puts each one in its own block, and 1 merges entries
+    /// that are right next to each other.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bumpy_vector::BumpyVector;
+    ///
+    /// let mut v: BumpyVector<&str> = BumpyVector::new(100);
+    /// v.insert(("a", 0..10).into()).unwrap();
+    /// v.insert(("b", 12..20).into()).unwrap();
+    /// v.insert(("c", 50..60).into()).unwrap();
+    ///
+    /// let blocks = v.get_range_merged(0..100, 4);
+    /// assert_eq!(2, blocks.len());
+    ///
+    /// assert_eq!(0..20, blocks[0].range);
+    /// assert_eq!(2, blocks[0].entries.len());
+    ///
+    /// assert_eq!(50..60, blocks[1].range);
+    /// assert_eq!(1, blocks[1].entries.len());
+    /// ```
+    pub fn get_range_merged(&self, range: Range<usize>, gap_merge_threshold: usize) -> Vec<MergedBlock<'_, T>> {
+        let mut result: Vec<MergedBlock<'_, T>> = Vec::new();
+
+        for e in self.get_range(range) {
+            match result.last_mut() {
+                Some(block) if e.range.start - block.range.end < gap_merge_threshold => {
+                    block.range.end = e.range.end;
+                    block.entries.push(e);
+                },
+                _ => result.push(MergedBlock {
+                    range: e.range.clone(),
+                    entries: vec![e],
+                }),
+            }
+        }
+
+        result
+    }
+
+    /// Find the closest entry that starts before `index`.
+    fn entry_before(&self, index: usize) -> Option<&BumpyEntry<T>> {
+        self.data.range(..index).next_back().map(|(_, e)| e)
+    }
+
+    /// Find the closest entry that starts at or after `index`.
+    fn entry_after(&self, index: usize) -> Option<&BumpyEntry<T>> {
+        self.data.range(index..).next().map(|(_, e)| e)
+    }
+
+    /// Returns the `(index, size)` of every entry, sorted by index.
+    ///
+    /// This is a compact copy of the layout that doesn't borrow the vector,
+    /// so it can be handed off to something like a search or a minimap. Since
+    /// it's sorted, it can be binary searched.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bumpy_vector::BumpyVector;
+    ///
+    /// let mut v: BumpyVector<&str> = BumpyVector::new(100);
+    /// v.insert(("b", 10..15).into()).unwrap();
+    /// v.insert(("a", 0..2).into()).unwrap();
+    ///
+    /// let index = v.index_snapshot();
+    /// assert_eq!(vec![(0, 2), (10, 5)], index);
+    ///
+    /// // Find the entry covering index 12
+    /// let i = match index.binary_search_by_key(&12, |(start, _)| *start) {
+    ///     Ok(i) => i,
+    ///     Err(i) => i - 1,
+    /// };
+    /// assert_eq!((10, 5), index[i]);
+    /// ```
+    pub fn index_snapshot(&self) -> Vec<(usize, usize)> {
+        self.data.values().map(|e| (e.range.start, e.range.len())).collect()
+    }
+
+    /// Returns the number of entries.
+    pub fn len(&self) -> usize {
+        // Return the number of entries
+        self.data.len()
+    }
+
+    /// Returns `true` if there are no entries.
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// Returns a counter that goes up every time the vector changes.
+    ///
+    /// Every successful insert or remove increments it, as does handing out a
+    /// mutable reference (since we can't tell whether it was used). A cache
+    /// built from the vector's contents can remember the generation it was
+    /// built at, and is stale as soon as the generation doesn't match.
+    ///
+    /// The generation isn't serialized, and starts over at 0 when a vector is
+    /// deserialized.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bumpy_vector::BumpyVector;
+    ///
+    /// let mut v: BumpyVector<&str> = BumpyVector::new(10);
+    /// let generation = v.generation();
+    ///
+    /// // Failures and reads don't change anything
+    /// assert!(v.insert(("hello", 5..100).into()).is_err());
+    /// assert!(v.get(0).is_none());
+    /// assert_eq!(generation, v.generation());
+    ///
+    /// // But successful changes do
+    /// v.insert(("hello", 0..2).into()).unwrap();
+    /// assert!(v.generation() > generation);
+    /// ```
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Shrink `max_size` to `new_max`, taking out any entries that don't fit
+    /// anymore.
+    ///
+    /// Entries that cross `new_max` don't fit either. Locked entries can't be
+    /// taken out, though, so the vector only shrinks as far as the end of the
+    /// last locked entry past `new_max`; locks are cut off at the new end.
+    /// This never grows the vector.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bumpy_vector::BumpyVector;
+    ///
+    /// let mut v: BumpyVector<&str> = BumpyVector::new(100);
+    /// v.insert(("header", 0..10).into()).unwrap();
+    /// v.insert(("body", 10..60).into()).unwrap();
+    /// v.insert(("trailer", 90..100).into()).unwrap();
+    ///
+    /// let evicted = v.shrink_to(50);
+    /// assert_eq!(vec!["body", "trailer"], evicted.iter().map(|e| e.entry).collect::<Vec<_>>());
+    /// assert_eq!(50, v.max_size());
+    /// assert_eq!(1, v.len());
+    /// ```
+    ///
+    /// # Return
+    ///
+    /// Returns the entries that were taken out, in order. If the vector is
+    /// read-only, nothing changes and nothing is returned.
+    pub fn shrink_to(&mut self, new_max: usize) -> Vec<BumpyEntry<T>> {
+        if self.read_only || new_max >= self.max_size {
+            return Vec::new();
+        }
+
+        let new_max = self.iter_range(new_max..self.max_size)
+            .filter(|e| self.is_locked(&e.range))
+            .map(|e| e.range.end)
+            .max()
+            .unwrap_or(new_max);
+
+        let starts: Vec<usize> = self.iter_range(new_max..self.max_size)
+            .filter(|e| e.range.end > new_max)
+            .map(|e| e.range.start)
+            .collect();
+        let evicted = starts.into_iter().filter_map(|s| self.remove_start(s)).collect();
+
+        self.max_size = new_max;
+        self.locks = self.locks.iter().filter_map(|r| ranges::intersection(r, &(0..new_max))).collect();
+
+        evicted
+    }
+
+    /// Returns the maximum size, as passed to `new()` (or adjusted since,
+    /// by `apply_offset_map()` or `shrink_to()`).
+    ///
+    /// Every entry ends at or before this.
+    pub fn max_size(&self) -> usize {
+        self.max_size
+    }
+}
+
+/// Convert into an iterator.
+///
+/// Iterates across all entries, in order; see `BumpyVector::iter()`.
+impl<'a, T> IntoIterator for &'a BumpyVector<T> {
+    type Item = &'a BumpyEntry<T>;
+    type IntoIter = iter::Iter<'a, T>;
+
+    fn into_iter(self) -> iter::Iter<'a, T> {
+        self.iter()
+    }
+}
+
+/// Convert into an iterator that takes ownership of the entries.
+///
+/// The entries are moved out in order, without cloning anything.
+///
+/// # Example
+///
+/// ```
+/// use bumpy_vector::{BumpyEntry, BumpyVector};
+///
+/// let mut v: BumpyVector<String> = BumpyVector::new(10);
+/// v.insert((String::from("world"), 5..8).into()).unwrap();
+/// v.insert((String::from("hello"), 0..2).into()).unwrap();
+///
+/// let entries: Vec<BumpyEntry<String>> = v.into_iter().collect();
+/// assert_eq!("hello", entries[0].entry);
+/// assert_eq!("world", entries[1].entry);
+/// ```
+impl<T> IntoIterator for BumpyVector<T> {
+    type Item = BumpyEntry<T>;
+    type IntoIter = std::vec::IntoIter<BumpyEntry<T>>;
+
+    fn into_iter(self) -> std::vec::IntoIter<BumpyEntry<T>> {
+        let result: Vec<BumpyEntry<T>> = self.data.into_values().collect();
+
+        result.into_iter()
+    }
+}
+
+/// Return the value of the entry covering `index`, like `get()`.
+///
+/// # Panics
+///
+/// Panics if there's no entry at `index`, the same way indexing past the end
+/// of a slice does.
+///
+/// # Example
+///
+/// ```
+/// use bumpy_vector::BumpyVector;
+///
+/// let mut v: BumpyVector<&str> = BumpyVector::new(10);
+/// v.insert(("hello", 2..6).into()).unwrap();
+///
+/// assert_eq!("hello", v[4]);
+/// ```
+impl<T> std::ops::Index<usize> for BumpyVector<T> {
+    type Output = T;
+
+    fn index(&self, index: usize) -> &T {
+        match self.get(index) {
+            Some(e) => &e.entry,
+            None => panic!("No entry at index {}", index),
+        }
+    }
+}
+
+/// Formats the layout as one `start..end: value` line per entry, in order.
+///
+/// The alternate form (`{:#}`) also has a line for each gap.
+///
+/// # Example
+///
+/// ```
+/// use bumpy_vector::BumpyVector;
+///
+/// let mut v: BumpyVector<&str> = BumpyVector::new(10);
+/// v.insert(("header", 0..4).into()).unwrap();
+/// v.insert(("body", 6..8).into()).unwrap();
+///
+/// assert_eq!("0..4: header\n6..8: body\n", v.to_string());
+/// assert_eq!("0..4: header\n4..6: (gap)\n6..8: body\n8..10: (gap)\n", format!("{:#}", v));
+/// ```
+impl<T> fmt::Display for BumpyVector<T>
+where
+    T: fmt::Display
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut last_end = 0;
+
+        for e in self.iter() {
+            if f.alternate() && e.range.start > last_end {
+                writeln!(f, "{}..{}: (gap)", last_end, e.range.start)?;
+            }
+
+            writeln!(f, "{}", e)?;
+            last_end = e.range.end;
+        }
+
+        if f.alternate() && self.max_size > last_end {
+            writeln!(f, "{}..{}: (gap)", last_end, self.max_size)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Vectors are equal if they have the same `max_size` and the same entries.
+///
+/// Nothing else is compared - not locks, pins, read-only mode, or any of the
+/// history.
+///
+/// # Example
+///
+/// ```
+/// use bumpy_vector::BumpyVector;
+///
+/// let mut a: BumpyVector<&str> = BumpyVector::new(10);
+/// a.insert(("hello", 0..5).into()).unwrap();
+/// a.insert(("world", 5..10).into()).unwrap();
+///
+/// let mut b: BumpyVector<&str> = BumpyVector::new(10);
+/// b.insert(("world", 5..10).into()).unwrap();
+/// b.insert(("hello", 0..5).into()).unwrap();
+///
+/// assert_eq!(a, b);
+/// ```
+impl<T> PartialEq for BumpyVector<T>
+where
+    T: PartialEq
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.max_size == other.max_size && self.data == other.data
+    }
+}
+
+impl<T> Eq for BumpyVector<T>
+where
+    T: Eq
+{
+}
+
+/// Hashes `max_size` and the entries, in order, to match `PartialEq`; see
+/// also `BumpyVector::content_hash()`.
+impl<T> Hash for BumpyVector<T>
+where
+    T: Hash
+{
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.max_size.hash(state);
+        self.len().hash(state);
+
+        for e in self.data.values() {
+            e.hash(state);
+        }
+    }
+}
+
+/// Insert every entry from an iterator.
+///
+/// Entries that don't fit - because they overlap, are out of bounds, or
+/// anything else `insert()` would reject - are skipped. Use `try_extend()` to
+/// find out which ones.
+///
+/// # Example
+///
+/// ```
+/// use bumpy_vector::BumpyVector;
+///
+/// let mut v: BumpyVector<&str> = BumpyVector::new(10);
+/// v.extend(vec![("a", 0..4).into(), ("b", 2..6).into(), ("c", 6..10).into()]);
+///
+/// // "b" overlapped "a"
+/// assert_eq!(2, v.len());
+/// ```
+impl<T> Extend<BumpyEntry<T>> for BumpyVector<T> {
+    fn extend<I>(&mut self, iter: I)
+    where
+        I: IntoIterator<Item=BumpyEntry<T>>
+    {
+        for entry in iter {
+            let _ = self.insert(entry);
+        }
+    }
+}
+
+/// Create a vector of the given `max_size` from a list of entries, checking
+/// each one like `insert()` does; see `BumpyVector::from_iter_with_max()`.
+///
+/// # Example
+///
+/// ```
+/// use std::convert::TryFrom;
+/// use bumpy_vector::{BumpyError, BumpyVector};
+///
+/// let v = BumpyVector::try_from((10, vec![("a", 0..4).into(), ("b", 4..6).into()])).unwrap();
+/// assert_eq!(2, v.len());
+///
+/// let e = BumpyVector::try_from((10, vec![("a", 0..4).into(), ("b", 8..12).into()])).unwrap_err();
+/// assert_eq!("b", e.entry.entry);
+/// assert!(matches!(e.reason, BumpyError::OutOfBounds));
+/// ```
+impl<T> TryFrom<(usize, Vec<BumpyEntry<T>>)> for BumpyVector<T> {
+    type Error = InsertError<T>;
+
+    fn try_from(o: (usize, Vec<BumpyEntry<T>>)) -> Result<Self, InsertError<T>> {
+        Self::from_iter_with_max(o.1, o.0)
     }
 }
 
-/// Convert into an iterator.
+/// Create a vector from an iterator of entries.
 ///
-/// Naively iterate across all entries, move them into a `Vec<_>`, and convert
-/// that vector into an iterator.
+/// The vector is just big enough to fit every entry. Like `extend()`, entries
+/// that overlap an earlier one are skipped; use `from_iter_with_max()` to
+/// check them instead.
 ///
-impl<'a, T> IntoIterator for &'a BumpyVector<T> {
-    type Item = &'a BumpyEntry<T>;
-    type IntoIter = std::vec::IntoIter<&'a BumpyEntry<T>>;
+/// # Example
+///
+/// ```
+/// use bumpy_vector::{BumpyEntry, BumpyVector};
+///
+/// let v: BumpyVector<&str> = vec![("a", 0..4), ("b", 6..8)].into_iter().map(BumpyEntry::from).collect();
+/// assert_eq!(8, v.max_size());
+/// assert_eq!(2, v.len());
+/// ```
+impl<T> FromIterator<BumpyEntry<T>> for BumpyVector<T> {
+    fn from_iter<I>(iter: I) -> Self
+    where
+        I: IntoIterator<Item=BumpyEntry<T>>
+    {
+        let entries: Vec<BumpyEntry<T>> = iter.into_iter().collect();
+        let max_size = entries.iter().map(|e| e.range.end).max().unwrap_or(0);
 
-    fn into_iter(self) -> std::vec::IntoIter<&'a BumpyEntry<T>> {
-        return self.get_range(0..self.max_size).into_iter();
+        let mut v = Self::new(max_size);
+        v.extend(entries);
+
+        v
     }
 }
 
@@ -690,6 +2521,386 @@ mod tests {
         assert_eq!(1, h.len());
     }
 
+    #[test]
+    fn test_try_entry() {
+        let e = BumpyEntry::try_entry("a", 1u8, 2u64).unwrap();
+        assert_eq!(1..3, e.range);
+
+        assert!(BumpyEntry::try_entry("a", -1i32, 2u64).is_err());
+        assert!(BumpyEntry::try_entry("a", 1i32, -2i64).is_err());
+        assert!(BumpyEntry::try_entry("a", usize::MAX, 1).is_err());
+        assert!(BumpyEntry::try_entry("a", usize::MAX, 0).is_ok());
+
+        let e = BumpyEntry::try_from(("a", 10u64..20u64)).unwrap();
+        assert_eq!(10..20, e.range);
+        assert!(BumpyEntry::try_from(("a", Range { start: 20u64, end: 10 })).is_err());
+
+        let e = BumpyEntry::try_from(("a", 10i64..20i64)).unwrap();
+        assert_eq!(10..20, e.range);
+        assert!(BumpyEntry::try_from(("a", -10i64..20i64)).is_err());
+        assert!(BumpyEntry::try_from(("a", Range { start: 20i64, end: 10 })).is_err());
+        assert!(BumpyEntry::try_from(("a", i64::MIN..i64::MAX)).is_err());
+
+        // Only on platforms where usize is smaller than u64
+        if std::mem::size_of::<usize>() < 8 {
+            assert!(BumpyEntry::try_entry("a", u64::MAX, 1u8).is_err());
+        }
+    }
+
+    #[test]
+    fn test_entry_ordering() {
+        let a: BumpyEntry<u32> = (2, 0..4).into();
+        let b: BumpyEntry<u32> = (1, 0..4).into();
+        let c: BumpyEntry<u32> = (9, 0..2).into();
+        let d: BumpyEntry<u32> = (0, 1..2).into();
+
+        let mut entries = vec![d.clone(), a.clone(), b.clone(), c.clone(), a.clone()];
+        entries.sort();
+        assert_eq!(vec![c.clone(), b.clone(), a.clone(), a.clone(), d.clone()], entries);
+
+        // So they can be deduplicated, or go in a set
+        entries.dedup();
+        assert_eq!(4, entries.len());
+        let set: std::collections::BTreeSet<BumpyEntry<u32>> = entries.into_iter().collect();
+        assert_eq!(Some(&c), set.iter().next());
+
+        assert_ne!(a, b);
+        assert_eq!(a, (2, 0..4).into());
+
+        // Values that can't be compared still order by range
+        let x: BumpyEntry<f64> = (f64::NAN, 0..1).into();
+        let y: BumpyEntry<f64> = (f64::NAN, 0..2).into();
+        assert!(x < y);
+        assert_eq!(None, x.partial_cmp(&x));
+    }
+
+    #[test]
+    fn test_from_iter() {
+        let entries: Vec<BumpyEntry<&str>> = vec![
+            ("a", 2..4).into(),
+            ("b", 10..12).into(),
+            ("c", 3..5).into(),
+        ];
+
+        let h: BumpyVector<&str> = entries.iter().cloned().collect();
+        assert_eq!(12, h.max_size());
+        assert_eq!(vec!["a", "b"], h.iter().map(|e| e.entry).collect::<Vec<_>>());
+
+        let e = BumpyVector::from_iter_with_max(entries.iter().cloned(), 20).unwrap_err();
+        assert_eq!("c", e.entry.entry);
+
+        let e = BumpyVector::from_iter_with_max(entries.iter().take(2).cloned(), 11).unwrap_err();
+        assert!(matches!(e.reason, BumpyError::OutOfBounds));
+
+        let h = BumpyVector::from_iter_with_max(entries.into_iter().take(2), 12).unwrap();
+        assert_eq!(2, h.len());
+
+        let h: BumpyVector<&str> = std::iter::empty().collect();
+        assert_eq!(0, h.max_size());
+
+        let e = BumpyVector::try_from((100, vec![("a", 0..10).into(), ("b", 5..5).into()])).unwrap_err();
+        assert!(matches!(e, InsertError { reason: BumpyError::ZeroSize, .. }));
+        assert_eq!(1, BumpyVector::try_from((100, vec![("a", 0..10).into()])).unwrap().len());
+    }
+
+    #[test]
+    fn test_binary_search_by() {
+        let mut h: BumpyVector<u32> = BumpyVector::new(100);
+        h.insert((10, 0..2).into()).unwrap();
+        h.insert((20, 2..5).into()).unwrap();
+        h.insert((30, 40..41).into()).unwrap();
+        h.insert((40, 41..50).into()).unwrap();
+        h.insert((50, 97..100).into()).unwrap();
+
+        for (value, range) in &[(10, 0..2), (20, 2..5), (30, 40..41), (40, 41..50), (50, 97..100)] {
+            assert_eq!(Ok(range.clone()), h.binary_search_by(|e| e.entry.cmp(value)).map(|e| e.range.clone()));
+        }
+
+        assert_eq!(Err(0), h.binary_search_by(|e| e.entry.cmp(&5)).map(|_| ()));
+        assert_eq!(Err(5), h.binary_search_by(|e| e.entry.cmp(&25)).map(|_| ()));
+        assert_eq!(Err(41), h.binary_search_by(|e| e.entry.cmp(&35)).map(|_| ()));
+        assert_eq!(Err(50), h.binary_search_by(|e| e.entry.cmp(&45)).map(|_| ()));
+        assert_eq!(Err(100), h.binary_search_by(|e| e.entry.cmp(&55)).map(|_| ()));
+
+        // It doesn't need to look at everything
+        let mut calls = 0;
+        let big: BumpyVector<usize> = (0..1000).map(|i| BumpyEntry::from((i, (i * 10)..(i * 10 + 5)))).collect();
+        assert_eq!(Ok(3210), big.binary_search_by(|e| { calls += 1; e.entry.cmp(&321) }).map(|e| e.range.start));
+        assert!(calls < 20, "{} calls", calls);
+
+        let h: BumpyVector<u32> = BumpyVector::new(100);
+        assert_eq!(Err(0), h.binary_search_by(|e| e.entry.cmp(&5)).map(|_| ()));
+    }
+
+    #[test]
+    fn test_get_range_padded() {
+        let mut h: BumpyVector<&str> = BumpyVector::new(10);
+        h.insert(("a", 0..1).into()).unwrap();
+        h.insert(("b", 2..4).into()).unwrap();
+        h.insert(("c", 4..5).into()).unwrap();
+        h.insert(("d", 7..9).into()).unwrap();
+
+        let padded = |range: Range<usize>, pad: usize| -> Vec<&str> {
+            h.get_range_padded(range, pad).iter().map(|e| e.entry).collect()
+        };
+
+        // No padding is just get_range()
+        for start in 0..12 {
+            for end in start..12 {
+                let expected: Vec<&str> = h.get_range(start..end).iter().map(|e| e.entry).collect();
+                assert_eq!(expected, padded(start..end, 0), "range {}..{}", start, end);
+            }
+        }
+
+        assert_eq!(vec!["a", "b", "c", "d"], padded(3..5, 1));
+        assert_eq!(vec!["b", "c", "d"], padded(4..5, 1));
+        assert_eq!(vec!["a", "b", "c", "d"], padded(4..5, 10));
+        assert_eq!(vec!["c", "d"], padded(5..7, 1));
+        assert_eq!(vec!["d"], padded(9..10, 1));
+        assert_eq!(vec!["d"], padded(20..30, 1));
+
+        // Empty ranges are between entries
+        assert_eq!(vec!["b", "c"], padded(4..4, 1));
+        assert_eq!(vec!["b", "c"], padded(3..3, 1));
+    }
+
+    #[test]
+    fn test_shrink_to() {
+        let mut h: BumpyVector<&str> = BumpyVector::new(100);
+        h.insert(("a", 0..10).into()).unwrap();
+        h.insert(("b", 10..20).into()).unwrap();
+        h.insert(("c", 40..60).into()).unwrap();
+        h.insert(("d", 70..80).into()).unwrap();
+        h.lock_range(45..46);
+        h.lock_range(90..100);
+
+        // Growing doesn't happen
+        assert_eq!(0, h.shrink_to(200).len());
+        assert_eq!(100, h.max_size());
+
+        // "c" is locked, so it stays
+        let evicted: Vec<&str> = h.shrink_to(15).iter().map(|e| e.entry).collect();
+        assert_eq!(vec!["d"], evicted);
+        assert_eq!(60, h.max_size());
+        assert_eq!(std::slice::from_ref(&(45..46)), h.locked_ranges());
+
+        h.unlock_range(0..100);
+        let evicted: Vec<&str> = h.shrink_to(15).iter().map(|e| e.entry).collect();
+        assert_eq!(vec!["b", "c"], evicted);
+        assert_eq!(15, h.max_size());
+        assert!(h.insert(("e", 10..16).into()).is_err());
+
+        h.set_read_only(true);
+        assert_eq!(0, h.shrink_to(0).len());
+        assert_eq!(15, h.max_size());
+        h.set_read_only(false);
+
+        assert_eq!(1, h.shrink_to(0).len());
+        assert!(h.is_empty());
+    }
+
+    #[test]
+    fn test_eq() {
+        let mut a: BumpyVector<u32> = BumpyVector::new(10);
+        a.insert((1, 0..2).into()).unwrap();
+        a.insert((2, 5..6).into()).unwrap();
+
+        let mut b = a.clone();
+        assert_eq!(a, b);
+
+        // Only the layout matters
+        b.lock_range(0..10);
+        b.set_read_only(true);
+        assert_eq!(a, b);
+        b.set_read_only(false);
+        b.unlock_range(0..10);
+
+        b.replace_value(5, 3);
+        assert_ne!(a, b);
+        b.replace_value(5, 2);
+
+        b.remove(0);
+        b.insert((1, 0..1).into()).unwrap();
+        assert_ne!(a, b);
+
+        assert_ne!(BumpyVector::<u32>::new(10), BumpyVector::<u32>::new(11));
+        assert_eq!(BumpyVector::<u32>::new(10), BumpyVector::<u32>::new(10));
+    }
+
+    #[test]
+    fn test_index() {
+        let mut h: BumpyVector<&str> = BumpyVector::new(10);
+        h.insert(("a", 0..2).into()).unwrap();
+        h.insert(("b", 5..10).into()).unwrap();
+
+        assert_eq!("a", h[0]);
+        assert_eq!("a", h[1]);
+        assert_eq!("b", h[9]);
+    }
+
+    #[test]
+    #[should_panic(expected = "No entry at index 3")]
+    fn test_index_empty() {
+        let mut h: BumpyVector<&str> = BumpyVector::new(10);
+        h.insert(("a", 0..2).into()).unwrap();
+
+        let _ = h[3];
+    }
+
+    #[test]
+    fn test_compare_and_replace() {
+        let mut h: BumpyVector<u32> = BumpyVector::new(10);
+        h.insert((1, 0..4).into()).unwrap();
+        let generation = h.generation();
+
+        assert!(matches!(h.compare_and_replace(2, &2, 3), Err(CasError { value: 3, reason: BumpyError::Mismatch })));
+        assert!(matches!(h.compare_and_replace(5, &1, 3), Err(CasError { value: 3, reason: BumpyError::NotFound })));
+        assert_eq!(generation, h.generation());
+
+        assert_eq!(1, h.compare_and_replace(2, &1, 3).unwrap());
+        assert_eq!(3, h.get(0).unwrap().entry);
+        assert!(h.generation() > generation);
+
+        h.set_read_only(true);
+        let e = h.compare_and_replace(2, &3, 4).unwrap_err();
+        assert!(matches!(e.reason, BumpyError::ReadOnly));
+        assert_eq!("Vector is read-only", e.to_string());
+        assert_eq!(3, h.get(0).unwrap().entry);
+    }
+
+    #[test]
+    fn test_display() {
+        let mut h: BumpyVector<String> = BumpyVector::new(10);
+        assert_eq!("", h.to_string());
+        assert_eq!("0..10: (gap)\n", format!("{:#}", h));
+
+        h.insert((String::from("b"), 5..10).into()).unwrap();
+        h.insert((String::from("a"), 0..2).into()).unwrap();
+        h.insert((String::from("c"), 2..5).into()).unwrap();
+
+        assert_eq!("0..2: a\n2..5: c\n5..10: b\n", h.to_string());
+        assert_eq!(h.to_string(), format!("{:#}", h));
+        assert_eq!("2..5: c", h.get(3).unwrap().to_string());
+
+        h.remove(3);
+        assert_eq!("0..2: a\n2..5: (gap)\n5..10: b\n", format!("{:#}", h));
+    }
+
+    #[test]
+    fn test_parse() {
+        // A simple length-prefixed format, where 0xff means "skip me"
+        let data = b"\x02ab\xff\x03cde\x05xy";
+
+        let h: BumpyVector<Vec<u8>> = BumpyVector::parse(data, |buffer, _offset| {
+            match buffer[0] {
+                0xff => None,
+                length => Some((buffer.get(1..(length as usize + 1))?.to_vec(), length as usize + 1)),
+            }
+        });
+
+        assert_eq!(11, h.max_size());
+
+        // The truncated item at the end is skipped, byte by byte
+        let result: Vec<(Vec<u8>, Range<usize>)> = h.into_iter().map(|e| (e.entry.clone(), e.range.clone())).collect();
+        assert_eq!(vec![
+            (b"ab".to_vec(),  0..3),
+            (b"cde".to_vec(), 4..8),
+        ], result);
+
+        // The offset is passed along as well
+        let h: BumpyVector<usize> = BumpyVector::parse(b"abcd", |_buffer, offset| Some((offset, 2)));
+        assert_eq!(0, h.get(1).unwrap().entry);
+        assert_eq!(2, h.get(3).unwrap().entry);
+
+        // Consuming nothing, or too much, is skipped
+        let h: BumpyVector<usize> = BumpyVector::parse(b"abcd", |_buffer, offset| Some((offset, 0)));
+        assert_eq!(0, h.len());
+
+        let h: BumpyVector<usize> = BumpyVector::parse(b"abcd", |_buffer, offset| Some((offset, 3)));
+        assert_eq!(1, h.len());
+        assert_eq!(0..3, h.get(0).unwrap().range);
+    }
+
+    #[test]
+    fn test_generation() {
+        let mut h: BumpyVector<&str> = BumpyVector::new(100);
+        let mut last = h.generation();
+
+        // Each of these should bump the generation exactly once
+        h.insert(("a", 0..10).into()).unwrap();
+        assert_eq!(last + 1, h.generation());
+        last = h.generation();
+
+        h.insert(("b", 10..20).into()).unwrap();
+        assert_eq!(last + 1, h.generation());
+        last = h.generation();
+
+        h.get_mut(5).unwrap();
+        assert_eq!(last + 1, h.generation());
+        last = h.generation();
+
+        h.get_exact_mut(10).unwrap();
+        assert_eq!(last + 1, h.generation());
+        last = h.generation();
+
+        h.remove(5).unwrap();
+        assert_eq!(last + 1, h.generation());
+        last = h.generation();
+
+        h.remove_range(0..100);
+        assert_eq!(last + 1, h.generation());
+        last = h.generation();
+
+        // And none of these should
+        assert!(h.insert(("c", 0..1000).into()).is_err());
+        assert!(h.remove(5).is_none());
+        assert!(h.get_mut(5).is_none());
+        assert!(h.get_exact_mut(5).is_none());
+        assert_eq!(0, h.remove_range(0..100).len());
+        assert_eq!(last, h.generation());
+    }
+
+    #[test]
+    fn test_insert_idempotent() {
+        let mut h: BumpyVector<String> = BumpyVector::new(100);
+        h.insert_idempotent((String::from("a"), 10..15).into()).unwrap();
+        h.insert_idempotent((String::from("b"), 15..16).into()).unwrap();
+        let generation = h.generation();
+
+        // Identical entries don't change anything
+        h.insert_idempotent((String::from("a"), 10..15).into()).unwrap();
+        h.insert_idempotent((String::from("b"), 15..16).into()).unwrap();
+        assert_eq!(2, h.len());
+        assert_eq!(generation, h.generation());
+
+        // Anything else is still an overlap
+        assert!(h.insert_idempotent((String::from("A"), 10..15).into()).is_err());
+        assert!(h.insert_idempotent((String::from("a"), 10..14).into()).is_err());
+        assert!(h.insert_idempotent((String::from("a"), 11..15).into()).is_err());
+        assert!(h.insert_idempotent((String::from("a"), 10..10).into()).is_err());
+        assert_eq!(2, h.len());
+    }
+
+    #[test]
+    fn test_upsert() {
+        let mut h: BumpyVector<&str> = BumpyVector::new(100);
+        assert_eq!(None, h.upsert(("a", 10..15).into()).unwrap());
+        assert_eq!(None, h.upsert(("b", 15..16).into()).unwrap());
+
+        assert_eq!(Some("a"), h.upsert(("A", 10..15).into()).unwrap());
+        assert_eq!(Some("A"), h.upsert(("AA", 10..15).into()).unwrap());
+        assert_eq!("AA", h.get(12).unwrap().entry);
+        assert_eq!(2, h.len());
+
+        // Partial overlaps fail and don't change anything
+        assert!(h.upsert(("error", 10..16).into()).is_err());
+        assert!(h.upsert(("error", 11..15).into()).is_err());
+        assert!(h.upsert(("error", 9..11).into()).is_err());
+        assert!(h.upsert(("error", 15..15).into()).is_err());
+        assert_eq!("AA", h.get(12).unwrap().entry);
+        assert_eq!("b", h.get(15).unwrap().entry);
+    }
+
     #[test]
     fn test_zero_sized_insert() {
         let mut h: BumpyVector<&str> = BumpyVector::new(100);
@@ -893,6 +3104,79 @@ mod tests {
         assert_eq!(10..12,  result[1].range);
     }
 
+    #[test]
+    fn test_range_from() {
+        let h: BumpyVector<&str> = BumpyVector::new(100);
+
+        assert_eq!(0..100, h.range_from(0, 100).unwrap());
+        assert_eq!(10..10, h.range_from(10, 0).unwrap());
+        assert_eq!(100..100, h.range_from(100, 0).unwrap());
+        assert!(matches!(h.range_from(101, 0), Err(BumpyError::OutOfBounds)));
+        assert!(matches!(h.range_from(1, 100), Err(BumpyError::OutOfBounds)));
+        assert!(matches!(h.range_from(usize::MAX, 1), Err(BumpyError::Overflow)));
+        assert!(matches!(h.range_from(1, usize::MAX), Err(BumpyError::Overflow)));
+
+        assert_eq!(0..100, h.range_from_saturating(0, usize::MAX));
+        assert_eq!(50..60, h.range_from_saturating(50, 10));
+        assert_eq!(100..100, h.range_from_saturating(usize::MAX, usize::MAX));
+
+        // A vector that covers everything can still overflow
+        let h: BumpyVector<&str> = BumpyVector::new(usize::MAX);
+        assert_eq!((usize::MAX - 1)..usize::MAX, h.range_from(usize::MAX - 1, 1).unwrap());
+        assert!(matches!(h.range_from(usize::MAX - 1, 2), Err(BumpyError::Overflow)));
+    }
+
+    #[test]
+    fn test_remove_exact() {
+        let mut h: BumpyVector<&str> = BumpyVector::new(100);
+        h.insert(("a", 8..10).into()).unwrap();
+        h.insert(("b", 10..12).into()).unwrap();
+        h.insert(("c", 20..30).into()).unwrap();
+
+        assert!(h.remove_exact(9).is_none());
+        assert!(h.remove_exact(0).is_none());
+        assert_eq!(3, h.len());
+
+        let e = h.remove_exact(10).unwrap();
+        assert_eq!("b", e.entry);
+        assert_eq!(10..12, e.range);
+        assert!(h.remove_exact(10).is_none());
+        assert_eq!("a", h.get(9).unwrap().entry);
+
+        // Locks and read-only mode still apply
+        h.lock_range(25..26);
+        assert!(h.remove_exact(20).is_none());
+        h.set_read_only(true);
+        assert!(h.remove_exact(8).is_none());
+        assert_eq!(2, h.len());
+    }
+
+    #[test]
+    fn test_out_of_range_queries() {
+        let mut h: BumpyVector<&str> = BumpyVector::new(100);
+        h.insert(("a", 8..10).into()).unwrap();
+        h.insert(("b", 90..100).into()).unwrap();
+
+        // Anything past the end is clamped off, even if it's huge
+        assert_eq!(1, h.get_range(95..usize::MAX).len());
+        assert_eq!(0, h.get_range(100..usize::MAX).len());
+        assert_eq!(0, h.get_range(usize::MAX..usize::MAX).len());
+        assert_eq!(0, h.get_range(Range { start: 50, end: 10 }).len());
+        assert_eq!(1, h.iter_range(95..usize::MAX).count());
+
+        // Strict versions refuse
+        assert!(h.get_range_strict(95..101).is_err());
+        assert!(h.get_range_strict(Range { start: 50, end: 10 }).is_err());
+        assert_eq!(2, h.get_range_strict(0..100).unwrap().len());
+        assert_eq!(0, h.get_range_strict(100..100).unwrap().len());
+
+        assert!(h.remove_range_strict(0..usize::MAX).is_err());
+        assert_eq!(2, h.len());
+        assert_eq!(1, h.remove_range(95..usize::MAX).len());
+        assert_eq!(1, h.remove_range_strict(0..100).unwrap().len());
+        assert_eq!(0, h.len());
+    }
+
     #[test]
     fn test_get() {
         // Create an object
@@ -914,10 +3198,36 @@ mod tests {
 
         // Get a mutable reference
         let s = h.get_mut(9).unwrap();
+        assert_eq!(8..10, s.range);
         s.entry.make_ascii_uppercase();
 
         let s2 = h.get(8).unwrap();
         assert_eq!("HELLO", s2.entry);
+
+        // Replace the value outright
+        *h.get_mut(8).unwrap().entry = String::from("bye");
+        assert_eq!("bye", h.get(9).unwrap().entry);
+        assert!(h.get_mut(10).is_none());
+    }
+
+    #[test]
+    fn test_iter_mut() {
+        let mut h: BumpyVector<usize> = BumpyVector::new(10);
+        h.insert((0, 6..9).into()).unwrap();
+        h.insert((0, 1..3).into()).unwrap();
+        h.insert((0, 3..4).into()).unwrap();
+
+        // Entries come out in order
+        for (i, e) in h.iter_mut().enumerate() {
+            *e.entry = i * 100 + e.range.len();
+        }
+
+        let result: Vec<usize> = h.iter().map(|e| e.entry).collect();
+        assert_eq!(vec![2, 101, 203], result);
+
+        // An empty vector has nothing to iterate
+        let mut h: BumpyVector<usize> = BumpyVector::new(10);
+        assert_eq!(0, h.iter_mut().count());
     }
 
     #[test]
@@ -944,10 +3254,15 @@ mod tests {
 
         // Get a mutable reference
         let s = h.get_exact_mut(8).unwrap();
+        assert_eq!(8..10, s.range);
         s.entry.make_ascii_uppercase();
 
         let s = h.get_exact(8).unwrap();
         assert_eq!("HELLO", s.entry);
+
+        // Replace the value outright
+        *h.get_exact_mut(8).unwrap().entry = String::from("bye");
+        assert_eq!("bye", h.get(9).unwrap().entry);
     }
 
     #[test]
@@ -984,6 +3299,112 @@ mod tests {
         assert_eq!(3, result.len());
     }
 
+    #[test]
+    fn test_get_range_empty() {
+        // Same layout as test_get_range()
+        let mut h: BumpyVector<&str> = BumpyVector::new(10);
+        h.insert(("a", 1..3).into()).unwrap();
+        h.insert(("b", 3..4).into()).unwrap();
+        h.insert(("c", 6..9).into()).unwrap();
+
+        // Empty ranges don't contain anything, even within an entry
+        assert_eq!(0, h.get_range(2..2).len());
+        assert_eq!(0, h.get_range(5..5).len());
+        assert_eq!(0, h.get_range(1..1).len());
+    }
+
+    #[test]
+    fn test_get_annotated_range() {
+        // Same layout as test_get_range
+        let mut h: BumpyVector<&str> = BumpyVector::new(10);
+        h.insert(("a", 1..3).into()).unwrap();
+        h.insert(("b", 3..4).into()).unwrap();
+        h.insert(("c", 6..9).into()).unwrap();
+
+        let result: Vec<(&str, usize, usize)> = h.get_annotated_range(0..10).iter().map(|e| {
+            (e.entry.entry, e.gap_before, e.gap_after)
+        }).collect();
+        assert_eq!(vec![("a", 1, 0), ("b", 0, 2), ("c", 2, 1)], result);
+
+        // A range with nothing in it has nothing to annotate
+        assert_eq!(0, h.get_annotated_range(4..5).len());
+
+        // Neighbours outside the range are still counted
+        let result = h.get_annotated_range(3..4);
+        assert_eq!(1, result.len());
+        assert_eq!("b", result[0].entry.entry);
+        assert_eq!(0, result[0].gap_before);
+        assert_eq!(2, result[0].gap_after);
+
+    }
+
+    #[test]
+    fn test_get_range_merged() {
+        // Same layout as test_get_range
+        let mut h: BumpyVector<&str> = BumpyVector::new(10);
+        h.insert(("a", 1..3).into()).unwrap();
+        h.insert(("b", 3..4).into()).unwrap();
+        h.insert(("c", 6..9).into()).unwrap();
+
+        let blocks = |range: Range<usize>, threshold: usize| -> Vec<(Range<usize>, Vec<&'static str>)> {
+            h.get_range_merged(range, threshold).iter().map(|b| {
+                (b.range.clone(), b.entries.iter().map(|e| e.entry).collect())
+            }).collect()
+        };
+
+        assert_eq!(vec![(1..3, vec!["a"]), (3..4, vec!["b"]), (6..9, vec!["c"])], blocks(0..10, 0));
+        assert_eq!(vec![(1..4, vec!["a", "b"]), (6..9, vec!["c"])], blocks(0..10, 1));
+        assert_eq!(vec![(1..4, vec!["a", "b"]), (6..9, vec!["c"])], blocks(0..10, 2));
+        assert_eq!(vec![(1..9, vec!["a", "b", "c"])], blocks(0..10, 3));
+
+        // Only the entries in the range are merged
+        assert_eq!(vec![(3..9, vec!["b", "c"])], blocks(3..7, 100));
+        assert_eq!(0, blocks(4..6, 100).len());
+    }
+
+    #[test]
+    fn test_get_multi_ranges() {
+        // Same layout as test_get_range
+        let mut h: BumpyVector<&str> = BumpyVector::new(10);
+        h.insert(("a", 1..3).into()).unwrap();
+        h.insert(("b", 3..4).into()).unwrap();
+        h.insert(("c", 6..9).into()).unwrap();
+
+        let names = |result: Vec<Vec<&BumpyEntry<&'static str>>>| -> Vec<Vec<&'static str>> {
+            result.iter().map(|group| group.iter().map(|e| e.entry).collect()).collect()
+        };
+
+        // Each range on its own matches get_range()
+        assert_eq!(vec![vec!["a", "b"], vec!["c"]], names(h.get_multi_ranges(&[2..6, 7..8])));
+
+        // Out of order and overlapping ranges
+        assert_eq!(vec![vec!["c"], vec!["a", "b"], vec![]], names(h.get_multi_ranges(&[6..100, 0..4, 3..7])));
+
+        // "c" straddles the gap between both ranges
+        assert_eq!(vec![vec!["c"], vec![]], names(h.get_multi_ranges(&[6..7, 8..9])));
+
+        // Empty ranges and no ranges at all
+        assert_eq!(vec![Vec::<&str>::new(), vec![]], names(h.get_multi_ranges(&[2..2, 9..9])));
+        assert_eq!(0, h.get_multi_ranges(&[]).len());
+    }
+
+    #[test]
+    fn test_index_snapshot() {
+        let mut h: BumpyVector<&str> = BumpyVector::new(10);
+        assert_eq!(Vec::<(usize, usize)>::new(), h.index_snapshot());
+
+        h.insert(("c", 6..9).into()).unwrap();
+        h.insert(("a", 1..3).into()).unwrap();
+        h.insert(("b", 3..4).into()).unwrap();
+        assert_eq!(vec![(1, 2), (3, 1), (6, 3)], h.index_snapshot());
+
+        // It's a copy, so it doesn't change with the vector
+        let snapshot = h.index_snapshot();
+        h.remove(3);
+        assert_eq!(3, snapshot.len());
+        assert_eq!(vec![(1, 2), (6, 3)], h.index_snapshot());
+    }
+
     #[test]
     fn test_iterator() {
         // Create a BumpyVector that looks like:
@@ -1019,6 +3440,27 @@ mod tests {
         assert!(iter.next().is_none());
     }
 
+    #[test]
+    fn test_owning_iterator() {
+        let mut h: BumpyVector<String> = BumpyVector::new(10);
+        h.insert((String::from("c"), 6..9).into()).unwrap();
+        h.insert((String::from("a"), 1..3).into()).unwrap();
+        h.insert((String::from("b"), 3..4).into()).unwrap();
+
+        // Borrowing first doesn't use it up
+        assert_eq!(3, h.iter().count());
+
+        let result: Vec<(String, Range<usize>)> = h.into_iter().map(|e| (e.entry, e.range)).collect();
+        assert_eq!(vec![
+            (String::from("a"), 1..3),
+            (String::from("b"), 3..4),
+            (String::from("c"), 6..9),
+        ], result);
+
+        let h: BumpyVector<String> = BumpyVector::new(10);
+        assert_eq!(0, h.into_iter().count());
+    }
+
     #[test]
     #[cfg(feature = "serialize")] // Only test if we enable serialization
     fn test_serialize() {
@@ -1068,13 +3510,13 @@ mod tests {
         struct Test {
             size: usize,
             index: usize,
-        };
+        }
 
         impl AutoBumpyEntry for Test {
             fn range(&self) -> Range<usize> {
                 self.index..(self.index+self.size)
             }
-        };
+        }
 
         let good_entry1 = Test {
             index: 0,
@@ -1103,4 +3545,240 @@ mod tests {
         assert!(h.insert(bad_entry.into()).is_err());
         assert_eq!(2, h.len());
     }
+
+    #[test]
+    fn test_sparse_lookups() {
+        // A handful of entries spread over a big address space
+        let mut h: BumpyVector<u32> = BumpyVector::new(usize::MAX);
+        h.insert((1, 0..10).into()).unwrap();
+        h.insert((2, 1_000_000_000..1_000_000_010).into()).unwrap();
+        h.insert((3, (usize::MAX - 10)..usize::MAX).into()).unwrap();
+
+        assert_eq!(2, h.get(1_000_000_005).unwrap().entry);
+        assert_eq!(3, h.get(usize::MAX - 1).unwrap().entry);
+        assert!(h.get(usize::MAX - 11).is_none());
+        assert!(h.get(500_000_000).is_none());
+
+        assert!(h.insert((4, 5..1_000_000_001).into()).is_err());
+        assert!(h.insert((4, 10..1_000_000_001).into()).is_err());
+        assert!(h.insert((4, 10..1_000_000_000).into()).is_ok());
+
+        assert_eq!(vec![2, 3], h.get_range(1_000_000_000..usize::MAX).iter().map(|e| e.entry).collect::<Vec<_>>());
+        assert_eq!(Some(4), h.remove(10).map(|e| e.entry));
+        assert_eq!(3, h.len());
+    }
+
+    #[test]
+    fn test_retain() {
+        let mut h: BumpyVector<u32> = BumpyVector::new(100);
+        for i in 0..10 {
+            h.insert((i, (i as usize * 10)..(i as usize * 10 + 5)).into()).unwrap();
+        }
+        h.lock_range(40..41);
+
+        // Everything is visited once, in order
+        let mut seen = Vec::new();
+        h.retain(|e| {
+            seen.push(e.entry);
+            e.entry % 2 == 1
+        });
+        assert_eq!((0..10).collect::<Vec<u32>>(), seen);
+
+        // Locked entries survive
+        assert_eq!(vec![1, 3, 4, 5, 7, 9], h.iter().map(|e| e.entry).collect::<Vec<_>>());
+        assert!(h.get(0).is_none());
+
+        h.set_read_only(true);
+        h.retain(|_| false);
+        assert_eq!(6, h.len());
+    }
+
+    #[test]
+    fn test_replace_value() {
+        let mut h: BumpyVector<String> = BumpyVector::new(100);
+        h.insert((String::from("a"), 10..20).into()).unwrap();
+        h.lock_range(0..100);
+        let generation = h.generation();
+
+        assert_eq!(Some(String::from("a")), h.replace_value(15, String::from("b")));
+        assert_eq!(Some(String::from("b")), h.replace_value(10, String::from("c")));
+        assert_eq!(None, h.replace_value(20, String::from("d")));
+        assert_eq!("c", h.get(19).unwrap().entry);
+        assert_eq!(10..20, h.get(19).unwrap().range);
+        assert!(h.generation() > generation);
+
+        h.set_read_only(true);
+        assert_eq!(None, h.replace_value(15, String::from("e")));
+        assert_eq!("c", h.get(15).unwrap().entry);
+    }
+
+    #[test]
+    fn test_insert_or_replace() {
+        let mut h: BumpyVector<&str> = BumpyVector::new(100);
+        h.insert(("a", 0..10).into()).unwrap();
+        h.insert(("b", 10..20).into()).unwrap();
+        h.insert(("c", 30..40).into()).unwrap();
+
+        // Nothing in the way
+        assert_eq!(0, h.insert_or_replace(("d", 20..30).into()).unwrap().len());
+
+        // Partly overlapping entries go entirely
+        let displaced: Vec<&str> = h.insert_or_replace(("e", 5..25).into()).unwrap().iter().map(|e| e.entry).collect();
+        assert_eq!(vec!["a", "b", "d"], displaced);
+        assert_eq!(vec!["e", "c"], h.iter().map(|e| e.entry).collect::<Vec<_>>());
+
+        // Errors don't remove anything
+        assert!(matches!(h.insert_or_replace(("f", 30..30).into()), Err(InsertError { reason: BumpyError::ZeroSize, .. })));
+        assert!(matches!(h.insert_or_replace(("f", 30..101).into()), Err(InsertError { reason: BumpyError::OutOfBounds, .. })));
+
+        h.lock_range(39..40);
+        let e = h.insert_or_replace(("f", 20..31).into()).unwrap_err();
+        assert!(matches!(e.reason, BumpyError::Locked));
+        assert_eq!("f", e.entry.entry);
+
+        h.unlock_range(0..100);
+        h.set_read_only(true);
+        assert!(matches!(h.insert_or_replace(("f", 20..31).into()), Err(InsertError { reason: BumpyError::ReadOnly, .. })));
+        assert_eq!(2, h.len());
+    }
+
+    #[test]
+    fn test_extract_if() {
+        let mut h: BumpyVector<u32> = BumpyVector::new(100);
+        for i in 0..10 {
+            h.insert((i, (i as usize * 10)..(i as usize * 10 + 5)).into()).unwrap();
+        }
+        h.lock_range(40..41);
+
+        let extracted: Vec<(u32, Range<usize>)> = h.extract_if(|e| e.entry % 2 == 0).map(|e| (e.entry, e.range)).collect();
+        assert_eq!(vec![(0, 0..5), (2, 20..25), (6, 60..65), (8, 80..85)], extracted);
+        assert_eq!(vec![1, 3, 4, 5, 7, 9], h.iter().map(|e| e.entry).collect::<Vec<_>>());
+
+        assert_eq!(0, h.extract_if(|_| false).len());
+
+        h.set_read_only(true);
+        assert_eq!(0, h.extract_if(|_| true).len());
+        assert_eq!(6, h.len());
+    }
+
+    #[test]
+    fn test_normalize_values() {
+        let mut h: BumpyVector<(u32, usize)> = BumpyVector::new(100);
+        h.insert(((2, 0), 50..60).into()).unwrap();
+        h.insert(((1, 0), 0..10).into()).unwrap();
+        h.track_changes();
+        let generation = h.generation();
+
+        let mut order = Vec::new();
+        h.normalize_values(|value, start, end| {
+            order.push(start);
+            value.1 = end - start;
+        });
+
+        assert_eq!(vec![0, 50], order);
+        assert_eq!((1, 10), h.get(5).unwrap().entry);
+        assert_eq!((2, 10), h.get(55).unwrap().entry);
+        assert!(h.generation() > generation);
+        assert_eq!(2, h.changes_since(generation).modified.len());
+
+        h.set_read_only(true);
+        h.normalize_values(|_, _, _| panic!("read-only"));
+    }
+
+    #[test]
+    fn test_is_empty() {
+        let mut h: BumpyVector<&str> = BumpyVector::new(100);
+        assert!(h.is_empty());
+        assert_eq!(100, h.max_size());
+
+        h.insert(("a", 0..10).into()).unwrap();
+        assert!(!h.is_empty());
+
+        h.remove(5);
+        assert!(h.is_empty());
+    }
+
+    #[test]
+    fn test_get_map() {
+        let mut h: BumpyVector<(u32, &str)> = BumpyVector::new(100);
+        h.insert(((1, "a"), 0..10).into()).unwrap();
+        h.insert(((2, "b"), 20..30).into()).unwrap();
+        h.track_access();
+
+        assert_eq!(Some(1), h.get_map(5, |v| v.0));
+        assert_eq!(Some("b"), h.get_map(20, |v| v.1));
+        assert_eq!(None, h.get_map(15, |v| v.0));
+
+        // It's a lookup like any other
+        assert_eq!(1, h.access_stats(0).unwrap().hits);
+
+        let projected: Vec<(u32, Range<usize>)> = h.get_range_map(5..25, |v| v.0).into_iter().map(|e| (e.entry, e.range)).collect();
+        assert_eq!(vec![(1, 0..10), (2, 20..30)], projected);
+        assert_eq!(0, h.get_range_map(10..20, |v| v.0).len());
+        assert_eq!(1, h.get_range_map(25..1000, |v| v.0).len());
+    }
+
+    #[test]
+    fn test_drain() {
+        let mut h: BumpyVector<String> = BumpyVector::new(100);
+        h.insert((String::from("c"), 50..60).into()).unwrap();
+        h.insert((String::from("a"), 0..10).into()).unwrap();
+        h.insert((String::from("b"), 10..20).into()).unwrap();
+
+        let drained: Vec<(String, Range<usize>)> = h.drain().map(|e| (e.entry, e.range)).collect();
+        assert_eq!(vec![
+            (String::from("a"), 0..10),
+            (String::from("b"), 10..20),
+            (String::from("c"), 50..60),
+        ], drained);
+        assert!(h.is_empty());
+        assert_eq!(0, h.drain().len());
+
+        // Locked entries stay behind
+        h.insert((String::from("d"), 0..10).into()).unwrap();
+        h.insert((String::from("e"), 10..20).into()).unwrap();
+        h.lock_range(5..6);
+        assert_eq!(vec!["e"], h.drain().map(|e| e.entry).collect::<Vec<_>>());
+        assert_eq!(1, h.len());
+
+        h.unlock_range(0..100);
+        h.set_read_only(true);
+        assert_eq!(0, h.drain().len());
+        assert_eq!(1, h.len());
+    }
+
+    #[test]
+    fn test_clear() {
+        let mut h: BumpyVector<&str> = BumpyVector::new(100);
+        h.insert(("a", 0..10).into()).unwrap();
+        h.insert(("b", 10..20).into()).unwrap();
+        h.insert(("c", 50..60).into()).unwrap();
+        h.track_changes();
+        h.track_access();
+        let generation = h.generation();
+
+        // Warm up the lookup cache
+        assert_eq!("b", h.get(15).unwrap().entry);
+
+        h.clear();
+        assert_eq!(0, h.len());
+        assert_eq!(100, h.max_size());
+        assert!(h.get(15).is_none());
+        assert_eq!(vec![0..10, 10..20, 50..60], h.changes_since(generation).removed);
+
+        // It's still usable, and forgot everything about the old entries
+        h.insert(("d", 10..20).into()).unwrap();
+        assert_eq!(0, h.access_stats(10).unwrap().hits);
+
+        // Locked entries are kept
+        h.insert(("e", 30..40).into()).unwrap();
+        h.lock_range(35..36);
+        h.clear();
+        assert_eq!(vec!["e"], h.iter().map(|e| e.entry).collect::<Vec<_>>());
+
+        h.unlock_range(0..100);
+        h.set_read_only(true);
+        h.clear();
+        assert_eq!(1, h.len());
+    }
 }
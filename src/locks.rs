@@ -0,0 +1,109 @@
+//! Lock ranges of a vector against changes.
+//!
+//! Once part of a file has been carefully annotated by hand, it shouldn't be
+//! re-annotated by some later automated pass. Locking a range makes any
+//! insert or remove that touches it fail, until it's unlocked again.
+//!
+//! Locks only protect the layout: values can still be changed in place with
+//! `get_mut()` and friends.
+//!
+//! # Example
+//!
+//! ```
+//! use bumpy_vector::BumpyVector;
+//!
+//! let mut v: BumpyVector<&str> = BumpyVector::new(100);
+//! v.insert(("verified", 0..10).into()).unwrap();
+//! v.lock_range(0..20);
+//!
+//! assert!(v.remove(5).is_none());
+//! assert!(v.insert(("guess", 15..25).into()).is_err());
+//!
+//! v.unlock_range(10..20);
+//! assert!(v.insert(("guess", 15..25).into()).is_ok());
+//! ```
+
+use std::ops::Range;
+
+use crate::{ranges, BumpyVector};
+
+impl<T> BumpyVector<T> {
+    /// Lock `range`, so that inserts and removes touching any part of it
+    /// fail.
+    ///
+    /// Locking part of an entry protects the whole entry.
+    pub fn lock_range(&mut self, range: Range<usize>) {
+        if range.is_empty() {
+            return;
+        }
+
+        self.locks.push(range);
+        self.locks.sort_by_key(|r| r.start);
+
+        // Merge anything that now overlaps or touches
+        let mut merged: Vec<Range<usize>> = Vec::new();
+        for r in self.locks.drain(..) {
+            match merged.last_mut() {
+                Some(last) if r.start <= last.end => last.end = std::cmp::max(last.end, r.end),
+                _ => merged.push(r),
+            }
+        }
+
+        self.locks = merged;
+    }
+
+    /// Unlock `range`.
+    ///
+    /// Any parts of other locks outside of `range` stay locked.
+    pub fn unlock_range(&mut self, range: Range<usize>) {
+        self.locks = self.locks.iter().flat_map(|r| ranges::subtract(r, &range)).collect();
+    }
+
+    /// Returns `true` if any part of `range` is locked.
+    pub fn is_locked(&self, range: &Range<usize>) -> bool {
+        self.locks.iter().any(|r| ranges::overlaps(r, range))
+    }
+
+    /// Returns the locked ranges, sorted and merged together.
+    pub fn locked_ranges(&self) -> &[Range<usize>] {
+        &self.locks
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_locks() {
+        let mut h: BumpyVector<&str> = BumpyVector::new(100);
+        h.insert(("a", 10..20).into()).unwrap();
+        h.insert(("b", 30..40).into()).unwrap();
+
+        h.lock_range(15..16);
+        h.lock_range(50..60);
+        h.lock_range(55..70);
+        h.lock_range(5..5);
+        assert_eq!(&[15..16, 50..70], h.locked_ranges());
+
+        // Anything touching a lock fails
+        assert!(h.remove(10).is_none());
+        assert!(h.insert(("error", 60..61).into()).is_err());
+        assert!(h.insert(("error", 40..51).into()).is_err());
+        assert_eq!(1, h.remove_range(0..100).len());
+        assert_eq!("a", h.get(10).unwrap().entry);
+
+        // But nothing else
+        assert!(h.insert(("ok", 40..50).into()).is_ok());
+        assert!(h.is_locked(&(10..20)));
+        assert!(!h.is_locked(&(16..50)));
+
+        // Unlocking can split a lock
+        h.unlock_range(10..20);
+        h.unlock_range(58..62);
+        assert_eq!(&[50..58, 62..70], h.locked_ranges());
+        assert!(h.remove(10).is_some());
+        assert!(h.insert(("ok", 58..62).into()).is_ok());
+    }
+}
@@ -0,0 +1,210 @@
+//! Find the entries next to an entry.
+//!
+//! Coalescing heuristics and UI commands like "extend the selection to the
+//! next object" both need to know what's on either side of an entry, whether
+//! it's directly touching or separated by a gap.
+
+use crate::{ranges, BumpyEntry, BumpyVector};
+
+/// Which side of an entry to look on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    /// Towards index 0.
+    Left,
+
+    /// Towards `max_size`.
+    Right,
+}
+
+impl<T> BumpyVector<T> {
+    /// Returns `true` if there are entries at both `index_a` and `index_b`,
+    /// and they touch - that is, one ends exactly where the other starts.
+    ///
+    /// The indexes don't need to be at the start of the entries.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bumpy_vector::BumpyVector;
+    ///
+    /// let mut v: BumpyVector<&str> = BumpyVector::new(100);
+    /// v.insert(("a", 0..10).into()).unwrap();
+    /// v.insert(("b", 10..20).into()).unwrap();
+    /// v.insert(("c", 21..30).into()).unwrap();
+    ///
+    /// assert!(v.is_adjacent(5, 15));
+    /// assert!(v.is_adjacent(15, 5));
+    ///
+    /// // There's a gap between "b" and "c"
+    /// assert!(!v.is_adjacent(15, 25));
+    ///
+    /// // And an entry isn't adjacent to itself
+    /// assert!(!v.is_adjacent(1, 2));
+    /// ```
+    pub fn is_adjacent(&self, index_a: usize, index_b: usize) -> bool {
+        match (self.get(index_a), self.get(index_b)) {
+            (Some(a), Some(b)) => ranges::is_adjacent(&a.range, &b.range),
+            _ => false,
+        }
+    }
+
+    /// Returns the closest entry to the left of the entry at `index`, however
+    /// far away it is.
+    ///
+    /// If there's no entry at `index`, returns the closest one that ends at
+    /// or before `index` instead.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bumpy_vector::BumpyVector;
+    ///
+    /// let mut v: BumpyVector<&str> = BumpyVector::new(100);
+    /// v.insert(("a", 0..10).into()).unwrap();
+    /// v.insert(("b", 20..30).into()).unwrap();
+    ///
+    /// assert_eq!("a", v.left_neighbor(25).unwrap().entry);
+    /// assert_eq!("a", v.left_neighbor(15).unwrap().entry);
+    /// assert!(v.left_neighbor(5).is_none());
+    /// ```
+    pub fn left_neighbor(&self, index: usize) -> Option<&BumpyEntry<T>> {
+        let start = self.get_entry_start(index).unwrap_or(index);
+
+        self.entry_before(start)
+    }
+
+    /// Returns the closest entry to the right of the entry at `index`,
+    /// however far away it is.
+    ///
+    /// If there's no entry at `index`, returns the closest one that starts
+    /// after `index` instead.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bumpy_vector::BumpyVector;
+    ///
+    /// let mut v: BumpyVector<&str> = BumpyVector::new(100);
+    /// v.insert(("a", 0..10).into()).unwrap();
+    /// v.insert(("b", 20..30).into()).unwrap();
+    ///
+    /// assert_eq!("b", v.right_neighbor(5).unwrap().entry);
+    /// assert_eq!("b", v.right_neighbor(15).unwrap().entry);
+    /// assert!(v.right_neighbor(25).is_none());
+    /// ```
+    pub fn right_neighbor(&self, index: usize) -> Option<&BumpyEntry<T>> {
+        let end = match self.get(index) {
+            Some(e) => e.range.end,
+            None    => index.saturating_add(1),
+        };
+
+        self.entry_after(end)
+    }
+
+    /// Returns the closest entry on one side of the entry at `index`, but
+    /// only if there are no more than `max_gap` empty indexes between them.
+    ///
+    /// A `max_gap` of 0 means the entries must be touching. If there's no
+    /// entry at `index`, there's nothing to measure from, so this returns
+    /// `None`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bumpy_vector::BumpyVector;
+    /// use bumpy_vector::neighbors::Direction;
+    ///
+    /// // Two strings, separated by 3 bytes of padding
+    /// let mut v: BumpyVector<&str> = BumpyVector::new(100);
+    /// v.insert(("hello", 0..6).into()).unwrap();
+    /// v.insert(("world", 9..15).into()).unwrap();
+    ///
+    /// assert_eq!("world", v.neighbor_within(0, Direction::Right, 4).unwrap().entry);
+    /// assert_eq!("hello", v.neighbor_within(9, Direction::Left, 3).unwrap().entry);
+    /// assert!(v.neighbor_within(9, Direction::Left, 2).is_none());
+    /// ```
+    pub fn neighbor_within(&self, index: usize, direction: Direction, max_gap: usize) -> Option<&BumpyEntry<T>> {
+        let entry = self.get(index)?;
+
+        let (neighbor, gap) = match direction {
+            Direction::Left => {
+                let n = self.entry_before(entry.range.start)?;
+                (n, entry.range.start - n.range.end)
+            },
+            Direction::Right => {
+                let n = self.entry_after(entry.range.end)?;
+                (n, n.range.start - entry.range.end)
+            },
+        };
+
+        if gap > max_gap {
+            return None;
+        }
+
+        Some(neighbor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_neighbors() {
+        // [--0-- --1-- --2-- --3-- --4-- --5-- --6-- --7-- --8-- --9--]
+        //        +-----------------            +----------------+
+        //        |   "a" (2)| "b" |            |      "c"       |
+        //        +----------+------            +----------------+
+        let mut h: BumpyVector<&str> = BumpyVector::new(10);
+        h.insert(("a", 1..3).into()).unwrap();
+        h.insert(("b", 3..4).into()).unwrap();
+        h.insert(("c", 6..9).into()).unwrap();
+
+        assert!(h.left_neighbor(0).is_none());
+        assert!(h.left_neighbor(1).is_none());
+        assert!(h.left_neighbor(2).is_none());
+        assert_eq!("a", h.left_neighbor(3).unwrap().entry);
+        assert_eq!("b", h.left_neighbor(4).unwrap().entry);
+        assert_eq!("b", h.left_neighbor(8).unwrap().entry);
+        assert_eq!("c", h.left_neighbor(9).unwrap().entry);
+
+        assert_eq!("a", h.right_neighbor(0).unwrap().entry);
+        assert_eq!("b", h.right_neighbor(1).unwrap().entry);
+        assert_eq!("c", h.right_neighbor(3).unwrap().entry);
+        assert_eq!("c", h.right_neighbor(5).unwrap().entry);
+        assert!(h.right_neighbor(6).is_none());
+        assert!(h.right_neighbor(9).is_none());
+        assert!(h.right_neighbor(100).is_none());
+
+        assert!(h.is_adjacent(2, 3));
+        assert!(h.is_adjacent(3, 1));
+        assert!(!h.is_adjacent(3, 6));
+        assert!(!h.is_adjacent(3, 4));
+        assert!(!h.is_adjacent(1, 2));
+    }
+
+    #[test]
+    fn test_neighbor_within() {
+        // Same layout as above
+        let mut h: BumpyVector<&str> = BumpyVector::new(10);
+        h.insert(("a", 1..3).into()).unwrap();
+        h.insert(("b", 3..4).into()).unwrap();
+        h.insert(("c", 6..9).into()).unwrap();
+
+        // Touching entries are within any distance
+        assert_eq!("a", h.neighbor_within(3, Direction::Left, 0).unwrap().entry);
+        assert_eq!("b", h.neighbor_within(2, Direction::Right, 0).unwrap().entry);
+
+        // "b" and "c" are two apart
+        assert!(h.neighbor_within(3, Direction::Right, 1).is_none());
+        assert_eq!("c", h.neighbor_within(3, Direction::Right, 2).unwrap().entry);
+        assert!(h.neighbor_within(7, Direction::Left, 1).is_none());
+        assert_eq!("b", h.neighbor_within(7, Direction::Left, 2).unwrap().entry);
+
+        // Nothing past the ends, or from an empty index
+        assert!(h.neighbor_within(1, Direction::Left, 100).is_none());
+        assert!(h.neighbor_within(8, Direction::Right, 100).is_none());
+        assert!(h.neighbor_within(5, Direction::Right, 100).is_none());
+    }
+}
@@ -0,0 +1,450 @@
+//! Translate offsets after cutting regions out of a buffer.
+//!
+//! When chunks are deleted from a file, everything after each chunk moves
+//! down. An `OffsetMap` is built from the ranges that were removed, and knows
+//! how to translate an old offset into a new one (and back). Applying one to
+//! a `BumpyVector` with `apply_offset_map()` moves every entry to match, so
+//! the annotations still line up with the edited file.
+//!
+//! # Example
+//!
+//! ```
+//! use bumpy_vector::BumpyVector;
+//! use bumpy_vector::offsets::OffsetMap;
+//!
+//! let mut v: BumpyVector<&str> = BumpyVector::new(100);
+//! v.insert(("header", 0..10).into()).unwrap();
+//! v.insert(("junk", 10..20).into()).unwrap();
+//! v.insert(("body", 30..50).into()).unwrap();
+//!
+//! // Cut out 10..30
+//! let map = OffsetMap::new(&[10..30]);
+//! assert_eq!(Some(5), map.map(5));
+//! assert_eq!(None, map.map(15));
+//! assert_eq!(Some(10), map.map(30));
+//! assert_eq!(30, map.unmap(10));
+//!
+//! // "junk" was in the part that was cut, so it's gone
+//! let removed = v.apply_offset_map(&map).unwrap();
+//! assert_eq!("junk", removed[0].entry);
+//!
+//! assert_eq!(80, v.max_size());
+//! assert_eq!(10..30, v.get(10).unwrap().range);
+//! ```
+
+use std::cmp::{max, min};
+use std::ops::Range;
+
+use crate::{ranges, BumpyEntry, BumpyError, BumpyResult, BumpyVector};
+use crate::types::TypeTag;
+
+/// Translates offsets from before a set of ranges was removed to after, and
+/// back.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OffsetMap {
+    /// The removed ranges, sorted and merged.
+    removed: Vec<Range<usize>>,
+
+    /// `shift[i]` is how much was removed before `removed[i]`; there's one
+    /// more than there are ranges, so the last is the total.
+    shift: Vec<usize>,
+}
+
+impl OffsetMap {
+    /// Build a map from the ranges that were removed, as offsets into the
+    /// original buffer.
+    ///
+    /// The ranges can be in any order, and can overlap or touch. Empty ranges
+    /// are ignored.
+    pub fn new(removed: &[Range<usize>]) -> Self {
+        let mut sorted: Vec<&Range<usize>> = removed.iter().filter(|r| !r.is_empty()).collect();
+        sorted.sort_by_key(|r| r.start);
+
+        let mut merged: Vec<Range<usize>> = Vec::new();
+        for r in sorted {
+            match merged.last_mut() {
+                Some(last) if r.start <= last.end => last.end = max(last.end, r.end),
+                _ => merged.push(r.clone()),
+            }
+        }
+
+        let mut shift = vec![0];
+        for r in merged.iter() {
+            shift.push(shift[shift.len() - 1] + r.len());
+        }
+
+        OffsetMap {
+            removed: merged,
+            shift,
+        }
+    }
+
+    /// The removed ranges, sorted and merged together.
+    pub fn removed(&self) -> &[Range<usize>] {
+        &self.removed
+    }
+
+    /// Translate an offset in the original buffer to the edited one.
+    ///
+    /// # Return
+    ///
+    /// Returns `None` if `old` was removed.
+    pub fn map(&self, old: usize) -> Option<usize> {
+        // How many ranges are fully before `old`?
+        let i = self.removed.partition_point(|r| r.end <= old);
+
+        if let Some(r) = self.removed.get(i) {
+            if r.contains(&old) {
+                return None;
+            }
+        }
+
+        Some(old - self.shift[i])
+    }
+
+    /// Translate an offset in the edited buffer back to the original one.
+    ///
+    /// Every offset in the edited buffer came from somewhere, so this always
+    /// succeeds.
+    pub fn unmap(&self, new: usize) -> usize {
+        // Each removed range collapses to a single point in the new buffer;
+        // binary search for how many of those points are at or before `new`
+        let mut low = 0;
+        let mut high = self.removed.len();
+
+        while low < high {
+            let middle = (low + high) / 2;
+
+            if self.removed[middle].start - self.shift[middle] <= new {
+                low = middle + 1;
+            } else {
+                high = middle;
+            }
+        }
+
+        new + self.shift[low]
+    }
+
+    /// Translate an offset in the original buffer to the edited one, where
+    /// a removed offset goes to where its removed range collapsed to.
+    fn collapse(&self, old: usize) -> usize {
+        let i = self.removed.partition_point(|r| r.end <= old);
+
+        match self.removed.get(i) {
+            Some(r) if r.contains(&old) => r.start - self.shift[i],
+            _ => old - self.shift[i],
+        }
+    }
+
+    /// Translate a range, if none of it was removed.
+    pub fn map_range(&self, old: &Range<usize>) -> Option<Range<usize>> {
+        if self.removed.iter().any(|r| ranges::overlaps(r, old)) {
+            return None;
+        }
+
+        let start = self.map(old.start)?;
+
+        Some(start..(start + old.len()))
+    }
+
+    /// Translate the size of the original buffer.
+    pub fn map_size(&self, old_size: usize) -> usize {
+        let removed: usize = self.removed.iter().map(|r| {
+            min(r.end, old_size).saturating_sub(r.start)
+        }).sum();
+
+        old_size - removed
+    }
+}
+
+impl<T> BumpyVector<T> {
+    /// Move every entry to where it is after the ranges in `map` are removed,
+    /// and shrink `max_size` to match.
+    ///
+    /// Pinned entries (see `pin()`) stay where they are. Locked ranges (see
+    /// `lock_range()`) move along with everything else, losing any parts that
+    /// were removed.
+    ///
+    /// # Return
+    ///
+    /// Entries that overlap a removed range don't have anywhere to go, so
+    /// they're taken out of the vector and returned, in order. If the vector
+    /// is read-only, nothing changes and it's `ReadOnly`.
+    ///
+    /// If a pinned entry would end up past the new `max_size`, or another
+    /// entry would move on top of one, nothing changes and an error is
+    /// returned instead. The same goes for a locked entry that would move or
+    /// be removed, which is `Locked`.
+    pub fn apply_offset_map(&mut self, map: &OffsetMap) -> BumpyResult<Vec<BumpyEntry<T>>> {
+        if self.read_only {
+            return Err(BumpyError::ReadOnly);
+        }
+
+        let max_size = map.map_size(self.max_size);
+
+        // Make sure the pinned entries can stay put before touching anything
+        let pinned: Vec<Range<usize>> = self.pins.iter().filter_map(|s| self.data.get(s)).map(|e| e.range.clone()).collect();
+        if pinned.iter().any(|r| r.end > max_size) {
+            return Err(BumpyError::OutOfBounds);
+        }
+
+        for e in self.data.values().filter(|e| !self.pins.contains(&e.range.start)) {
+            let range = map.map_range(&e.range);
+
+            if range.as_ref() != Some(&e.range) && self.is_locked(&e.range) {
+                return Err(BumpyError::Locked);
+            }
+
+            if let Some(range) = range {
+                if pinned.iter().any(|r| ranges::overlaps(r, &range)) {
+                    return Err(BumpyError::Overlap);
+                }
+            }
+        }
+
+        let starts: Vec<usize> = self.data.keys().copied().filter(|s| !self.pins.contains(s)).collect();
+
+        // Take everything out first, so nothing collides on the way back in.
+        // Nothing expires until everything's back, and it's only one change
+        let entries: Vec<(BumpyEntry<T>, Option<TypeTag>, Option<u64>)> = starts.into_iter().filter_map(|s| {
+            let tag = self.types.get(s);
+            let expires = self.temporary.expiry(s);
+
+            self.unlink(s).map(|e| (e, tag, expires))
+        }).collect();
+
+        self.max_size = max_size;
+
+        let locks = std::mem::take(&mut self.locks);
+        for r in locks {
+            self.lock_range(map.collapse(r.start)..map.collapse(r.end));
+        }
+
+        let mut dropped = Vec::new();
+        let mut moved = Vec::with_capacity(entries.len());
+        for (e, tag, expires) in entries {
+            match map.map_range(&e.range) {
+                Some(range) => {
+                    moved.push(range.start);
+                    self.restore(BumpyEntry { entry: e.entry, range }, tag, false, expires);
+                },
+                None => dropped.push(e),
+            }
+        }
+
+        // Moved entries are in order, since the map doesn't reorder anything
+        self.expire_temporary_except(|s| moved.binary_search(&s).is_ok());
+
+        Ok(dropped)
+    }
+
+    /// Rescale every offset from units of `from_unit_bytes` bytes to units
+    /// of `to_unit_bytes` bytes.
+    ///
+    /// Disk images often have tables that count in sectors, while everything
+    /// else counts in bytes; this converts a vector from one to the other.
+    /// Every entry, lock, and `max_size` is rescaled, and pins and type tags
+    /// come along. Like `apply_offset_map()`, entries lose their history and
+    /// stop being temporary.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bumpy_vector::BumpyVector;
+    ///
+    /// // 8 sectors of 512 bytes
+    /// let mut v: BumpyVector<&str> = BumpyVector::new(8);
+    /// v.insert(("boot", 0..1).into()).unwrap();
+    /// v.insert(("partition", 2..6).into()).unwrap();
+    ///
+    /// v.convert_units(512, 1).unwrap();
+    /// assert_eq!(4096, v.max_size());
+    /// assert_eq!(1024..3072, v.get(2000).unwrap().range);
+    ///
+    /// // And back
+    /// v.convert_units(1, 512).unwrap();
+    /// assert_eq!(2..6, v.get(2).unwrap().range);
+    /// ```
+    ///
+    /// # Return
+    ///
+    /// If any offset isn't a whole number of new units, nothing changes and
+    /// `Misaligned` is returned. It's `Overflow` if an offset gets too big,
+    /// `ReadOnly` if the vector is read-only, and `Other` if either unit is
+    /// `0`.
+    pub fn convert_units(&mut self, from_unit_bytes: usize, to_unit_bytes: usize) -> BumpyResult<()> {
+        if self.read_only {
+            return Err(BumpyError::ReadOnly);
+        }
+
+        if from_unit_bytes == 0 || to_unit_bytes == 0 {
+            return Err(BumpyError::Other(String::from("Units can't be 0 bytes")));
+        }
+
+        let convert = |offset: usize| -> BumpyResult<usize> {
+            let bytes = offset.checked_mul(from_unit_bytes).ok_or(BumpyError::Overflow)?;
+
+            match bytes % to_unit_bytes {
+                0 => Ok(bytes / to_unit_bytes),
+                _ => Err(BumpyError::Misaligned),
+            }
+        };
+        let convert_range = |r: &Range<usize>| -> BumpyResult<Range<usize>> {
+            Ok(convert(r.start)?..convert(r.end)?)
+        };
+
+        // Work out where everything goes before changing anything
+        let max_size = convert(self.max_size)?;
+        let locks = self.locks.iter().map(convert_range).collect::<BumpyResult<Vec<_>>>()?;
+        let moves = self.data.values().map(|e| convert_range(&e.range)).collect::<BumpyResult<Vec<_>>>()?;
+
+        // Nothing expires until everything's back, and it's only one change
+        let starts: Vec<usize> = self.data.keys().copied().collect();
+        let mut moved = Vec::with_capacity(starts.len());
+        for (start, range) in starts.into_iter().zip(moves) {
+            let pinned = self.pins.contains(&start);
+            let tag = self.types.get(start);
+            let expires = self.temporary.expiry(start);
+
+            if let Some(e) = self.unlink(start) {
+                moved.push((BumpyEntry { entry: e.entry, range }, pinned, tag, expires));
+            }
+        }
+
+        self.max_size = max_size;
+        self.locks = locks;
+
+        for (e, pinned, tag, expires) in moved {
+            self.restore(e, tag, pinned, expires);
+        }
+
+        // Every entry moved, so none of them expire
+        self.expire_temporary_except(|_| true);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_offset_map() {
+        // Overlapping and out of order ranges get merged
+        let map = OffsetMap::new(&[20..25, 2..4, 3..5, 10..10]);
+        assert_eq!(&[2..5, 20..25], map.removed());
+
+        assert_eq!(Some(0), map.map(0));
+        assert_eq!(Some(1), map.map(1));
+        assert_eq!(None, map.map(2));
+        assert_eq!(None, map.map(4));
+        assert_eq!(Some(2), map.map(5));
+        assert_eq!(Some(16), map.map(19));
+        assert_eq!(None, map.map(20));
+        assert_eq!(Some(17), map.map(25));
+        assert_eq!(Some(95), map.map(103));
+
+        // Going back the other way
+        for old in [0, 1, 5, 19, 25, 103] {
+            assert_eq!(old, map.unmap(map.map(old).unwrap()));
+        }
+
+        assert_eq!(Some(2..6), map.map_range(&(5..9)));
+        assert_eq!(None, map.map_range(&(0..3)));
+        assert_eq!(None, map.map_range(&(19..21)));
+
+        assert_eq!(92, map.map_size(100));
+        assert_eq!(17, map.map_size(22));
+        assert_eq!(0, map.map_size(0));
+
+        // An empty map doesn't change anything
+        let map = OffsetMap::new(&[]);
+        assert_eq!(Some(10), map.map(10));
+        assert_eq!(10, map.unmap(10));
+    }
+
+    #[test]
+    fn test_apply_offset_map() {
+        let mut h: BumpyVector<&str> = BumpyVector::new(10);
+        h.insert(("a", 1..3).into()).unwrap();
+        h.insert(("b", 3..4).into()).unwrap();
+        h.insert(("c", 6..9).into()).unwrap();
+
+        let dropped = h.apply_offset_map(&OffsetMap::new(&[0..1, 2..3, 4..6])).unwrap();
+        assert_eq!(vec!["a"], dropped.iter().map(|e| e.entry).collect::<Vec<_>>());
+
+        assert_eq!(6, h.max_size());
+        let result: Vec<(&str, Range<usize>)> = h.into_iter().map(|e| (e.entry, e.range.clone())).collect();
+        assert_eq!(vec![("b", 1..2), ("c", 2..5)], result);
+    }
+
+    #[test]
+    fn test_apply_offset_map_locks() {
+        let mut h: BumpyVector<&str> = BumpyVector::new(100);
+        h.insert(("a", 10..20).into()).unwrap();
+        h.insert(("b", 40..50).into()).unwrap();
+        h.lock_range(10..20);
+        h.lock_range(40..50);
+
+        // Locked entries can't be removed or moved
+        assert!(matches!(h.apply_offset_map(&OffsetMap::new(&[15..16, 90..91])), Err(BumpyError::Locked)));
+        assert!(matches!(h.apply_offset_map(&OffsetMap::new(&[0..5, 90..91])), Err(BumpyError::Locked)));
+        assert_eq!(100, h.max_size());
+        assert_eq!(&[10..20, 40..50], h.locked_ranges());
+
+        // But they can stay where they are, and the locks move with
+        // everything else
+        h.unlock_range(40..50);
+        h.lock_range(30..35);
+        h.apply_offset_map(&OffsetMap::new(&[32..38, 90..91])).unwrap();
+        assert_eq!(&[10..20, 30..32], h.locked_ranges());
+        assert_eq!(34..44, h.get(34).unwrap().range);
+        assert!(h.remove(10).is_none());
+        assert!(h.remove(34).is_some());
+    }
+
+    #[test]
+    fn test_convert_units() {
+        let mut h: BumpyVector<&str> = BumpyVector::new(12);
+        h.insert(("a", 0..2).into()).unwrap();
+        h.insert_typed(("b", 4..6).into(), 3).unwrap();
+        h.insert(("c", 6..12).into()).unwrap();
+        h.pin(4);
+        h.lock_range(6..8);
+
+        // Words to bytes
+        h.convert_units(2, 1).unwrap();
+        assert_eq!(24, h.max_size());
+        let result: Vec<(&str, Range<usize>)> = h.iter().map(|e| (e.entry, e.range.clone())).collect();
+        assert_eq!(vec![("a", 0..4), ("b", 8..12), ("c", 12..24)], result);
+        assert!(h.is_pinned(8));
+        assert_eq!(Some(3), h.type_of(8));
+        assert_eq!(std::slice::from_ref(&(12..16)), h.locked_ranges());
+
+        // Bytes to dwords doesn't divide evenly, so nothing happens
+        let before = h.clone();
+        assert!(matches!(h.convert_units(1, 8), Err(BumpyError::Misaligned)));
+        assert_eq!(before, h);
+        assert!(matches!(h.convert_units(usize::MAX, 1), Err(BumpyError::Overflow)));
+        assert!(matches!(h.convert_units(0, 1), Err(BumpyError::Other(_))));
+
+        // Bytes to dwords works once it does
+        h.remove(10);
+        h.unlock_range(0..24);
+        h.convert_units(1, 4).unwrap();
+        assert_eq!(6, h.max_size());
+        assert_eq!(vec![0..1, 3..6], h.iter().map(|e| e.range.clone()).collect::<Vec<_>>());
+
+        // Temporary entries stay temporary, and converting is one change
+        h.insert_temporary(("t", 1..2).into(), 2).unwrap();
+        h.convert_units(4, 1).unwrap();
+        assert!(h.is_temporary(4));
+        h.remove(0);
+        assert!(h.get(4).is_none());
+
+        h.set_read_only(true);
+        assert!(matches!(h.convert_units(1, 1), Err(BumpyError::ReadOnly)));
+    }
+}
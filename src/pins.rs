@@ -0,0 +1,107 @@
+//! Pin entries so they don't move.
+//!
+//! Some structures live at a fixed offset no matter what happens to the rest
+//! of the file, like an interrupt vector table. Pinning an entry keeps it
+//! where it is when the rest of the layout is moved with
+//! `apply_offset_map()`; if something else would move on top of it, the
+//! whole move fails instead.
+//!
+//! A pin belongs to an entry, so it goes away when the entry is removed.
+//!
+//! # Example
+//!
+//! ```
+//! use bumpy_vector::BumpyVector;
+//! use bumpy_vector::offsets::OffsetMap;
+//!
+//! let mut v: BumpyVector<&str> = BumpyVector::new(100);
+//! v.insert(("vectors", 0..8).into()).unwrap();
+//! v.insert(("code", 40..50).into()).unwrap();
+//! v.pin(0);
+//!
+//! v.apply_offset_map(&OffsetMap::new(&[0..4, 20..30])).unwrap();
+//!
+//! // The pinned entry stayed put, everything else moved
+//! assert_eq!(0..8, v.get(0).unwrap().range);
+//! assert_eq!(26..36, v.get(30).unwrap().range);
+//! ```
+
+use crate::BumpyVector;
+
+impl<T> BumpyVector<T> {
+    /// Pin the entry at `index`, so it isn't moved by `apply_offset_map()`.
+    ///
+    /// Note that the entry doesn't necessarily need to *start* at `index`,
+    /// just overlap it.
+    ///
+    /// # Return
+    ///
+    /// Returns `false` if there's no entry there.
+    pub fn pin(&mut self, index: usize) -> bool {
+        match self.get_entry_start(index) {
+            Some(start) => {
+                self.pins.insert(start);
+
+                true
+            },
+            None => false,
+        }
+    }
+
+    /// Unpin the entry at `index`, if it's pinned.
+    pub fn unpin(&mut self, index: usize) {
+        if let Some(start) = self.get_entry_start(index) {
+            self.pins.remove(&start);
+        }
+    }
+
+    /// Returns `true` if there's an entry at `index` and it's pinned.
+    pub fn is_pinned(&self, index: usize) -> bool {
+        self.get_entry_start(index).is_some_and(|start| self.pins.contains(&start))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use crate::offsets::OffsetMap;
+
+    #[test]
+    fn test_pins() {
+        let mut h: BumpyVector<&str> = BumpyVector::new(100);
+        h.insert(("a", 0..10).into()).unwrap();
+        h.insert(("b", 20..30).into()).unwrap();
+        h.insert(("c", 50..60).into()).unwrap();
+
+        assert!(h.pin(25));
+        assert!(!h.pin(40));
+        assert!(h.is_pinned(20));
+        assert!(!h.is_pinned(0));
+
+        // "c" would land on top of "b"
+        let generation = h.generation();
+        assert!(h.apply_offset_map(&OffsetMap::new(&[0..15, 15..30])).is_err());
+        assert_eq!(generation, h.generation());
+        assert_eq!(vec![0..10, 20..30, 50..60], h.iter().map(|e| e.range.clone()).collect::<Vec<_>>());
+
+        // "b" would be past the end
+        assert!(h.apply_offset_map(&OffsetMap::new(&[0..10, 30..100])).is_err());
+
+        // Otherwise everything else moves around it
+        let dropped = h.apply_offset_map(&OffsetMap::new(&[5..15, 30..40])).unwrap();
+        assert_eq!(vec!["a"], dropped.iter().map(|e| e.entry).collect::<Vec<_>>());
+        assert_eq!(vec![20..30, 30..40], h.iter().map(|e| e.range.clone()).collect::<Vec<_>>());
+        assert!(h.is_pinned(20));
+        assert_eq!(80, h.max_size());
+
+        // Pins go away with their entry
+        h.remove(20);
+        h.insert(("d", 20..30).into()).unwrap();
+        assert!(!h.is_pinned(20));
+
+        h.pin(35);
+        h.unpin(39);
+        assert!(!h.is_pinned(35));
+    }
+}
@@ -0,0 +1,285 @@
+//! Let the vector decide where an entry goes.
+//!
+//! When building a synthetic layout, or simulating an allocator, it doesn't
+//! matter exactly where an entry goes as long as it fits. These find an
+//! empty, unlocked run of indexes that's big enough, and insert the entry
+//! there.
+//!
+//! `insert_anywhere()` takes the first gap that fits. To compare allocation
+//! strategies, `insert_with_placement()` and `find_room()` take a
+//! `Placement` instead.
+//!
+//! # Example
+//!
+//! ```
+//! use bumpy_vector::BumpyVector;
+//!
+//! let mut v: BumpyVector<&str> = BumpyVector::new(100);
+//! v.insert(("a", 0..10).into()).unwrap();
+//! v.insert(("b", 12..20).into()).unwrap();
+//!
+//! // Too big for the gap between "a" and "b"
+//! assert_eq!(20, v.insert_anywhere("c", 5, 0).unwrap());
+//! assert_eq!(10, v.insert_anywhere("d", 2, 0).unwrap());
+//! ```
+
+use std::cmp::{max, min};
+use std::ops::Range;
+
+use crate::{BumpyEntry, BumpyError, BumpyVector, InsertError};
+
+/// How to choose between the gaps that are big enough for an entry.
+///
+/// Ties always go to the gap that comes first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Placement {
+    /// The first gap, in order.
+    FirstFit,
+
+    /// The smallest gap, leaving the least space unused.
+    BestFit,
+
+    /// The biggest gap, leaving the biggest leftover piece.
+    WorstFit,
+}
+
+/// A lazy iterator over the runs of indexes that have no entries and aren't
+/// locked; see `BumpyVector::free_space()`.
+#[derive(Debug, Clone)]
+pub(crate) struct FreeSpace<'a, T> {
+    vector: &'a BumpyVector<T>,
+
+    /// Where the next run could start.
+    position: usize,
+
+    /// The first lock that could still be in the way.
+    lock: usize,
+}
+
+impl<'a, T> Iterator for FreeSpace<'a, T> {
+    type Item = Range<usize>;
+
+    fn next(&mut self) -> Option<Range<usize>> {
+        let max_size = self.vector.max_size;
+
+        while self.position < max_size {
+            // The entry covering the position (a lock can end partway into
+            // one), or else the next one after it
+            let entry = match self.vector.get_entry_start(self.position) {
+                Some(start) => self.vector.data.get(&start),
+                None        => self.vector.data.range(self.position..).next().map(|(_, e)| e),
+            }.map(|e| e.range.clone());
+
+            // Locks are sorted and don't overlap, so skip the ones behind us
+            let locks = &self.vector.locks;
+            while self.lock < locks.len() && locks[self.lock].end <= self.position {
+                self.lock += 1;
+            }
+            let lock = locks.get(self.lock).cloned();
+
+            let obstacle = match (entry, lock) {
+                (Some(e), Some(l)) => Some(if l.start < e.start { l } else { e }),
+                (e, l) => e.or(l),
+            };
+
+            match obstacle {
+                Some(o) if o.start > self.position => {
+                    let free = self.position..min(o.start, max_size);
+                    self.position = o.end;
+
+                    return Some(free);
+                },
+                Some(o) => self.position = max(self.position, o.end),
+                None => {
+                    let free = self.position..max_size;
+                    self.position = max_size;
+
+                    return Some(free);
+                },
+            }
+        }
+
+        None
+    }
+}
+
+impl<T> BumpyVector<T> {
+    /// Iterate over every run of indexes at or after `from` that has no
+    /// entries and isn't locked, in order.
+    ///
+    /// Runs are found as the iterator goes, so stopping at the first one
+    /// that fits doesn't look at the rest of the vector.
+    pub(crate) fn free_space(&self, from: usize) -> FreeSpace<'_, T> {
+        FreeSpace {
+            vector: self,
+            position: from,
+            lock: self.locks.partition_point(|r| r.end <= from),
+        }
+    }
+
+    /// Insert `value` as a `size`-long entry in the first place it fits, at
+    /// or after `hint`.
+    ///
+    /// Pass a `hint` of 0 to search the whole vector. Locked ranges count as
+    /// full.
+    ///
+    /// # Return
+    ///
+    /// Returns where the new entry starts. If there's no room, the error
+    /// hands back the value with the range it was looking for.
+    pub fn insert_anywhere(&mut self, value: T, size: usize, hint: usize) -> Result<usize, InsertError<T>> {
+        let start = self.free_space(hint).find(|r| r.len() >= size).map(|r| r.start);
+
+        self.insert_placed(value, size, hint, start)
+    }
+
+    /// Find where a `size`-long entry would go, using `placement` to choose
+    /// between the gaps that are big enough.
+    ///
+    /// Locked ranges count as full.
+    ///
+    /// # Return
+    ///
+    /// Returns where the entry would start, or `None` if there's no room.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bumpy_vector::BumpyVector;
+    /// use bumpy_vector::placement::Placement;
+    ///
+    /// // Gaps at 0..8, 10..13, and 20..100
+    /// let mut v: BumpyVector<&str> = BumpyVector::new(100);
+    /// v.insert(("a", 8..10).into()).unwrap();
+    /// v.insert(("b", 13..20).into()).unwrap();
+    ///
+    /// assert_eq!(Some(0), v.find_room(3, Placement::FirstFit));
+    /// assert_eq!(Some(10), v.find_room(3, Placement::BestFit));
+    /// assert_eq!(Some(20), v.find_room(3, Placement::WorstFit));
+    /// assert_eq!(None, v.find_room(200, Placement::FirstFit));
+    /// ```
+    pub fn find_room(&self, size: usize, placement: Placement) -> Option<usize> {
+        let mut fits = self.free_space(0).filter(|r| r.len() >= size);
+
+        let chosen = match placement {
+            Placement::FirstFit => fits.next(),
+
+            Placement::BestFit  => fits.min_by_key(|r| r.len()),
+
+            // `max_by_key()` would take the last of a tie, so keep the first
+            // one explicitly
+            Placement::WorstFit => fits.fold(None, |best: Option<Range<usize>>, r| match best {
+                Some(b) if b.len() >= r.len() => Some(b),
+                _ => Some(r),
+            }),
+        };
+
+        chosen.map(|r| r.start)
+    }
+
+    /// Insert `value` as a `size`-long entry wherever `find_room()` says it
+    /// should go.
+    ///
+    /// # Return
+    ///
+    /// Returns where the new entry starts. If there's no room, the error
+    /// hands back the value.
+    pub fn insert_with_placement(&mut self, value: T, size: usize, placement: Placement) -> Result<usize, InsertError<T>> {
+        let start = self.find_room(size, placement);
+
+        self.insert_placed(value, size, 0, start)
+    }
+
+    /// Insert `value` at `start`, if that's somewhere, or return `NoRoom` for
+    /// a search that started at `hint`.
+    pub(crate) fn insert_placed(&mut self, value: T, size: usize, hint: usize, start: Option<usize>) -> Result<usize, InsertError<T>> {
+        let start = match start {
+            Some(start) => start,
+            None => return Err(InsertError {
+                entry: BumpyEntry { entry: value, range: hint..hint.saturating_add(size) },
+                reason: BumpyError::NoRoom,
+            }),
+        };
+
+        self.insert(BumpyEntry { entry: value, range: start..(start + size) })?;
+
+        Ok(start)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_insert_anywhere() {
+        let mut h: BumpyVector<u32> = BumpyVector::new(30);
+        h.insert((1, 5..10).into()).unwrap();
+        h.insert((2, 12..20).into()).unwrap();
+        h.lock_range(20..22);
+
+        assert_eq!(vec![0..5, 10..12, 22..30], h.free_space(0).collect::<Vec<_>>());
+        assert_eq!(vec![10..12, 22..30], h.free_space(7).collect::<Vec<_>>());
+        assert_eq!(vec![24..30], h.free_space(24).collect::<Vec<_>>());
+        assert_eq!(0, h.free_space(30).count());
+
+        // A lock that ends partway into an entry, and one past the end
+        h.lock_range(3..6);
+        h.lock_range(28..40);
+        assert_eq!(vec![0..3, 10..12, 22..28], h.free_space(0).collect::<Vec<_>>());
+        h.unlock_range(3..6);
+        h.unlock_range(28..40);
+
+        // Stopping at the first run that fits doesn't need the rest
+        let h2: BumpyVector<u32> = BumpyVector::new(usize::MAX);
+        assert_eq!(Some(0..usize::MAX), h2.free_space(0).next());
+
+        assert_eq!(Ok(22), h.insert_anywhere(3, 6, 0).map_err(|e| e.reason.to_string()));
+        assert_eq!(Ok(0), h.insert_anywhere(4, 2, 0).map_err(|e| e.reason.to_string()));
+        assert_eq!(Ok(10), h.insert_anywhere(5, 2, 6).map_err(|e| e.reason.to_string()));
+        assert_eq!(Ok(2), h.insert_anywhere(6, 3, 0).map_err(|e| e.reason.to_string()));
+
+        // Full up
+        let e = h.insert_anywhere(7, 3, 0).unwrap_err();
+        assert!(matches!(e.reason, BumpyError::NoRoom));
+        assert_eq!(7, e.entry.entry);
+        assert_eq!(Ok(28), h.insert_anywhere(8, 2, 0).map_err(|e| e.reason.to_string()));
+
+        // Errors from inserting come through as usual
+        assert!(matches!(h.insert_anywhere(9, 0, 0), Err(InsertError { reason: BumpyError::NoRoom, .. })));
+        h.remove(0);
+        assert!(matches!(h.insert_anywhere(9, 0, 0), Err(InsertError { reason: BumpyError::ZeroSize, .. })));
+        h.set_read_only(true);
+        assert!(matches!(h.insert_anywhere(9, 1, 0), Err(InsertError { reason: BumpyError::ReadOnly, .. })));
+    }
+
+    #[test]
+    fn test_placement() {
+        // Gaps: 0..4, 6..8, 10..14, 16..18, 20..24 (locked at 22)
+        let mut h: BumpyVector<u32> = BumpyVector::new(30);
+        h.insert((0, 4..6).into()).unwrap();
+        h.insert((0, 8..10).into()).unwrap();
+        h.insert((0, 14..16).into()).unwrap();
+        h.insert((0, 18..20).into()).unwrap();
+        h.insert((0, 24..30).into()).unwrap();
+        h.lock_range(22..23);
+
+        let find = |size: usize| -> (Option<usize>, Option<usize>, Option<usize>) {
+            (h.find_room(size, Placement::FirstFit), h.find_room(size, Placement::BestFit), h.find_room(size, Placement::WorstFit))
+        };
+
+        assert_eq!((Some(0), Some(6), Some(0)), find(2));
+        assert_eq!((Some(0), Some(0), Some(0)), find(3));
+        assert_eq!((Some(0), Some(0), Some(0)), find(4));
+        assert_eq!((None, None, None), find(5));
+
+        assert_eq!(Ok(6), h.insert_with_placement(1, 2, Placement::BestFit).map_err(|e| e.reason.to_string()));
+        assert_eq!(Ok(0), h.insert_with_placement(2, 2, Placement::WorstFit).map_err(|e| e.reason.to_string()));
+        assert_eq!(Ok(10), h.insert_with_placement(3, 3, Placement::WorstFit).map_err(|e| e.reason.to_string()));
+        assert_eq!(Ok(2), h.insert_with_placement(4, 1, Placement::FirstFit).map_err(|e| e.reason.to_string()));
+
+        let e = h.insert_with_placement(5, 4, Placement::BestFit).unwrap_err();
+        assert!(matches!(e.reason, BumpyError::NoRoom));
+    }
+}
@@ -0,0 +1,196 @@
+//! Track when each entry was created and last modified.
+//!
+//! This is off by default, since it costs a bit of memory per entry. Once
+//! it's turned on with `track_provenance()`, every entry is stamped with the
+//! vector's current *revision* when it's inserted, and again whenever it's
+//! handed out mutably. The revision is whatever the caller wants it to be -
+//! a save counter, a timestamp, an undo step - and is set with
+//! `set_revision()`.
+//!
+//! A sync or autosave layer can then ask for everything that's changed since
+//! it last ran with `modified_since()`, instead of diffing whole vectors.
+//!
+//! # Example
+//!
+//! ```
+//! use bumpy_vector::BumpyVector;
+//!
+//! let mut v: BumpyVector<&str> = BumpyVector::new(100);
+//! v.track_provenance();
+//!
+//! v.set_revision(1);
+//! v.insert(("old", 0..10).into()).unwrap();
+//!
+//! v.set_revision(2);
+//! v.insert(("new", 10..20).into()).unwrap();
+//!
+//! let changed = v.modified_since(1);
+//! assert_eq!(1, changed.len());
+//! assert_eq!("new", changed[0].entry);
+//!
+//! assert_eq!(1, v.provenance(5).unwrap().created);
+//! ```
+
+use std::collections::HashMap;
+
+#[cfg(feature = "serialize")]
+use serde::{Serialize, Deserialize};
+
+use crate::{BumpyEntry, BumpyVector};
+
+/// When an entry was created and last modified, as revisions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct Provenance {
+    pub created: u64,
+    pub modified: u64,
+}
+
+/// The per-vector state, once tracking is turned on.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub(crate) struct ProvenanceTracker {
+    /// The revision that changes are stamped with.
+    revision: u64,
+
+    /// Provenance for each entry, indexed by where it starts.
+    entries: HashMap<usize, Provenance>,
+}
+
+impl ProvenanceTracker {
+    /// Called when an entry is added at `start`.
+    pub(crate) fn on_insert(&mut self, start: usize) {
+        self.entries.insert(start, Provenance {
+            created: self.revision,
+            modified: self.revision,
+        });
+    }
+
+    /// Called when the entry at `start` is (or might be) changed.
+    pub(crate) fn on_modify(&mut self, start: usize) {
+        if let Some(p) = self.entries.get_mut(&start) {
+            p.modified = self.revision;
+        }
+    }
+
+    /// Called when the entry at `start` is removed.
+    pub(crate) fn on_remove(&mut self, start: usize) {
+        self.entries.remove(&start);
+    }
+
+    /// Called when every entry is removed.
+    pub(crate) fn on_clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+impl<T> BumpyVector<T> {
+    /// Start recording provenance for every entry.
+    ///
+    /// Entries that are already in the vector are stamped with the current
+    /// revision. Calling this when tracking is already on does nothing.
+    pub fn track_provenance(&mut self) {
+        if self.provenance.is_some() {
+            return;
+        }
+
+        let mut tracker = ProvenanceTracker::default();
+        for start in self.data.keys() {
+            tracker.on_insert(*start);
+        }
+
+        self.provenance = Some(tracker);
+    }
+
+    /// Set the revision that future changes are stamped with.
+    ///
+    /// Revisions don't have to go up, but `modified_since()` is only useful
+    /// if they do.
+    pub fn set_revision(&mut self, revision: u64) {
+        if let Some(tracker) = &mut self.provenance {
+            tracker.revision = revision;
+        }
+    }
+
+    /// Returns the current revision, or `None` if provenance isn't being
+    /// tracked.
+    pub fn revision(&self) -> Option<u64> {
+        self.provenance.as_ref().map(|t| t.revision)
+    }
+
+    /// Returns the provenance of the entry at `index`, if there's an entry and
+    /// provenance is being tracked.
+    pub fn provenance(&self, index: usize) -> Option<Provenance> {
+        let start = self.get_entry_start(index)?;
+
+        self.provenance.as_ref()?.entries.get(&start).copied()
+    }
+
+    /// Returns the entries that were created or modified after `revision`,
+    /// in order.
+    ///
+    /// This is empty if provenance isn't being tracked.
+    pub fn modified_since(&self, revision: u64) -> Vec<&BumpyEntry<T>> {
+        let tracker = match &self.provenance {
+            Some(t) => t,
+            None    => return vec![],
+        };
+
+        let mut result: Vec<&BumpyEntry<T>> = tracker.entries.iter()
+            .filter(|(_, p)| p.modified > revision)
+            .filter_map(|(start, _)| self.data.get(start))
+            .collect();
+
+        result.sort_by_key(|e| e.range.start);
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_provenance() {
+        let mut h: BumpyVector<String> = BumpyVector::new(100);
+
+        // Nothing is tracked until it's turned on
+        h.insert((String::from("a"), 0..10).into()).unwrap();
+        h.set_revision(5);
+        assert_eq!(None, h.revision());
+        assert_eq!(None, h.provenance(0));
+        assert_eq!(0, h.modified_since(0).len());
+
+        // Existing entries get the current revision
+        h.track_provenance();
+        assert_eq!(Some(0), h.revision());
+        assert_eq!(Some(Provenance { created: 0, modified: 0 }), h.provenance(5));
+
+        h.set_revision(1);
+        h.insert((String::from("b"), 10..20).into()).unwrap();
+        h.insert((String::from("c"), 20..30).into()).unwrap();
+
+        h.set_revision(2);
+        h.get_mut(5).unwrap().entry.push('!');
+        h.get_exact_mut(20).unwrap().entry.push('!');
+
+        assert_eq!(Some(Provenance { created: 0, modified: 2 }), h.provenance(5));
+        assert_eq!(Some(Provenance { created: 1, modified: 1 }), h.provenance(15));
+
+        let changed: Vec<&str> = h.modified_since(1).iter().map(|e| &e.entry[..]).collect();
+        assert_eq!(vec!["a!", "c!"], changed);
+        assert_eq!(3, h.modified_since(0).len());
+
+        // Removed entries are forgotten
+        h.remove(5).unwrap();
+        assert_eq!(None, h.provenance(5));
+        assert_eq!(1, h.modified_since(1).len());
+
+        // Turning it on again doesn't reset anything
+        h.track_provenance();
+        assert_eq!(Some(2), h.revision());
+        assert_eq!(Some(Provenance { created: 1, modified: 1 }), h.provenance(15));
+    }
+}
@@ -0,0 +1,163 @@
+//! Helpers for working with `Range<usize>`.
+//!
+//! These are the bits of interval math that `BumpyVector` needs internally,
+//! exposed so code working with the same ranges doesn't have to reinvent
+//! them. All ranges are half-open, like `std::ops::Range`, and empty ranges
+//! never overlap or contain anything.
+//!
+//! # Example
+//!
+//! ```
+//! use bumpy_vector::ranges;
+//!
+//! assert!(ranges::overlaps(&(0..10), &(9..20)));
+//! assert!(!ranges::overlaps(&(0..10), &(10..20)));
+//! assert!(ranges::is_adjacent(&(0..10), &(10..20)));
+//!
+//! // What's left of 0..100 after taking out a couple of chunks?
+//! assert_eq!(vec![0..10, 20..30, 90..100], ranges::subtract_all(&(0..100), &[10..20, 30..90]));
+//! ```
+
+use std::cmp::{max, min};
+use std::ops::Range;
+
+/// Returns `true` if `a` and `b` have at least one index in common.
+pub fn overlaps(a: &Range<usize>, b: &Range<usize>) -> bool {
+    !a.is_empty() && !b.is_empty() && a.start < b.end && b.start < a.end
+}
+
+/// Returns `true` if every index in `inner` is also in `outer`.
+///
+/// An empty `inner` is contained by anything.
+pub fn contains(outer: &Range<usize>, inner: &Range<usize>) -> bool {
+    inner.is_empty() || (outer.start <= inner.start && inner.end <= outer.end)
+}
+
+/// Returns `true` if `a` ends exactly where `b` starts, or vice versa.
+pub fn is_adjacent(a: &Range<usize>, b: &Range<usize>) -> bool {
+    !a.is_empty() && !b.is_empty() && (a.end == b.start || b.end == a.start)
+}
+
+/// Returns the indexes that are in both `a` and `b`, if there are any.
+pub fn intersection(a: &Range<usize>, b: &Range<usize>) -> Option<Range<usize>> {
+    if !overlaps(a, b) {
+        return None;
+    }
+
+    Some(max(a.start, b.start)..min(a.end, b.end))
+}
+
+/// Returns the parts of `a` that aren't in `b`, in order.
+///
+/// That's anywhere from zero pieces (if `b` covers `a`) to two (if `b` is in
+/// the middle of `a`). Empty pieces are never returned.
+pub fn subtract(a: &Range<usize>, b: &Range<usize>) -> Vec<Range<usize>> {
+    if a.is_empty() {
+        return vec![];
+    }
+
+    if !overlaps(a, b) {
+        return vec![a.clone()];
+    }
+
+    let mut result = Vec::new();
+
+    if a.start < b.start {
+        result.push(a.start..b.start);
+    }
+
+    if b.end < a.end {
+        result.push(b.end..a.end);
+    }
+
+    result
+}
+
+/// Returns the parts of `a` that aren't in any of `others`, in order.
+///
+/// This is how gaps are found: subtract everything that's occupied, and
+/// what's left is free. `others` doesn't need to be sorted, and can overlap.
+pub fn subtract_all(a: &Range<usize>, others: &[Range<usize>]) -> Vec<Range<usize>> {
+    let mut sorted: Vec<&Range<usize>> = others.iter().filter(|r| overlaps(a, r)).collect();
+    sorted.sort_by_key(|r| r.start);
+
+    let mut result = Vec::new();
+    let mut position = a.start;
+
+    for r in sorted {
+        if r.start > position {
+            result.push(position..r.start);
+        }
+
+        position = max(position, r.end);
+    }
+
+    if position < a.end {
+        result.push(position..a.end);
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_overlaps() {
+        assert!(overlaps(&(0..10), &(0..10)));
+        assert!(overlaps(&(0..10), &(5..6)));
+        assert!(overlaps(&(5..6), &(0..10)));
+        assert!(overlaps(&(0..10), &(9..11)));
+        assert!(!overlaps(&(0..10), &(10..11)));
+        assert!(!overlaps(&(10..11), &(0..10)));
+
+        // Empty ranges never overlap
+        assert!(!overlaps(&(0..10), &(5..5)));
+        assert!(!overlaps(&(5..5), &(5..5)));
+    }
+
+    #[test]
+    fn test_contains() {
+        assert!(contains(&(0..10), &(0..10)));
+        assert!(contains(&(0..10), &(2..5)));
+        assert!(!contains(&(0..10), &(5..11)));
+        assert!(!contains(&(2..5), &(0..10)));
+        assert!(contains(&(2..5), &(100..100)));
+    }
+
+    #[test]
+    fn test_is_adjacent() {
+        assert!(is_adjacent(&(0..10), &(10..11)));
+        assert!(is_adjacent(&(10..11), &(0..10)));
+        assert!(!is_adjacent(&(0..10), &(11..12)));
+        assert!(!is_adjacent(&(0..10), &(9..12)));
+        assert!(!is_adjacent(&(0..10), &(10..10)));
+    }
+
+    #[test]
+    fn test_intersection() {
+        assert_eq!(Some(5..10), intersection(&(0..10), &(5..15)));
+        assert_eq!(Some(2..3), intersection(&(0..10), &(2..3)));
+        assert_eq!(None, intersection(&(0..10), &(10..15)));
+    }
+
+    #[test]
+    fn test_subtract() {
+        assert_eq!(vec![0..2, 5..10], subtract(&(0..10), &(2..5)));
+        assert_eq!(vec![5..10], subtract(&(0..10), &(0..5)));
+        assert_eq!(vec![0..5], subtract(&(0..10), &(5..20)));
+        assert_eq!(Vec::<Range<usize>>::new(), subtract(&(0..10), &(0..10)));
+        assert_eq!(vec![0..10], subtract(&(0..10), &(20..30)));
+        assert_eq!(Vec::<Range<usize>>::new(), subtract(&(5..5), &(20..30)));
+    }
+
+    #[test]
+    fn test_subtract_all() {
+        assert_eq!(vec![0..100], subtract_all(&(0..100), &[]));
+        assert_eq!(vec![0..10, 90..100], subtract_all(&(0..100), &[50..90, 10..60]));
+        assert_eq!(vec![10..20], subtract_all(&(10..20), &[0..5, 25..30]));
+        assert_eq!(Vec::<Range<usize>>::new(), subtract_all(&(10..20), &[0..15, 15..30]));
+    }
+}
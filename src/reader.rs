@@ -0,0 +1,244 @@
+//! Build a `BumpyVector` while reading through a stream.
+//!
+//! Parsing a sequential file format usually means reading a field, figuring
+//! out what it is, recording it, and moving on - and keeping track of the
+//! offsets by hand is tedious and easy to get wrong. A `BumpyBuilder` wraps
+//! any `Read + Seek` source and records each "claimed" chunk at the stream's
+//! current position, so the offsets are always right.
+//!
+//! # Example
+//!
+//! ```
+//! use std::io::Cursor;
+//! use bumpy_vector::reader::BumpyBuilder;
+//!
+//! let data = Cursor::new(b"MZ\x00\x00\x04\x00\x00\x00data".to_vec());
+//! let mut builder: BumpyBuilder<_, &str> = BumpyBuilder::new(data).unwrap();
+//!
+//! // Claim things in order; offsets are tracked automatically
+//! builder.claim_next(2, "magic").unwrap();
+//! builder.skip(2).unwrap();
+//!
+//! // Look at the length before claiming it
+//! let b = builder.peek(4).unwrap();
+//! let length = u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as usize;
+//! builder.claim_next(4, "length").unwrap();
+//! builder.claim_next(length, "body").unwrap();
+//!
+//! let v = builder.into_vector();
+//! assert_eq!(3, v.len());
+//! assert_eq!(8..12, v.get(10).unwrap().range);
+//! ```
+
+use std::convert::TryFrom;
+use std::io::{Read, Seek, SeekFrom};
+use std::ops::Range;
+
+use crate::{BumpyEntry, BumpyError, BumpyResult, BumpyVector};
+
+/// Populates a `BumpyVector` as a stream is consumed.
+#[derive(Debug)]
+pub struct BumpyBuilder<R, T> {
+    reader: R,
+    vector: BumpyVector<T>,
+}
+
+/// Convert a stream offset to a `usize`.
+fn to_usize(offset: u64) -> BumpyResult<usize> {
+    usize::try_from(offset).map_err(|_| BumpyError::Overflow)
+}
+
+impl<R, T> BumpyBuilder<R, T>
+where
+    R: Read + Seek
+{
+    /// Create a builder whose `max_size` is the length of the stream.
+    ///
+    /// Claiming starts at the stream's current position.
+    pub fn new(mut reader: R) -> BumpyResult<Self> {
+        let current = reader.stream_position()?;
+        let length = reader.seek(SeekFrom::End(0))?;
+        reader.seek(SeekFrom::Start(current))?;
+
+        Ok(Self::with_max_size(reader, to_usize(length)?))
+    }
+
+    /// Create a builder with an explicit `max_size`, for when the stream's
+    /// length isn't meaningful.
+    pub fn with_max_size(reader: R, max_size: usize) -> Self {
+        BumpyBuilder {
+            reader,
+            vector: BumpyVector::new(max_size),
+        }
+    }
+
+    /// The stream's current position.
+    pub fn position(&mut self) -> BumpyResult<usize> {
+        to_usize(self.reader.stream_position()?)
+    }
+
+    /// Move forward `n` bytes without claiming them.
+    pub fn skip(&mut self, n: usize) -> BumpyResult<()> {
+        let n = match i64::try_from(n) {
+            Ok(n) => n,
+            Err(_) => return Err(BumpyError::Overflow),
+        };
+
+        self.reader.seek(SeekFrom::Current(n))?;
+
+        Ok(())
+    }
+
+    /// Read the next `size` bytes, without moving forward.
+    ///
+    /// # Return
+    ///
+    /// Returns an error if the bytes go past `max_size` or the end of the
+    /// stream. Sizes usually come from the data being parsed, so nothing is
+    /// allocated until the bytes have actually been read.
+    pub fn peek(&mut self, size: usize) -> BumpyResult<Vec<u8>> {
+        let start = self.position()?;
+        self.vector.range_from(start, size)?;
+
+        let mut buffer = Vec::new();
+        let result = (&mut self.reader).take(size as u64).read_to_end(&mut buffer);
+
+        // Always go back, even if the read failed
+        self.reader.seek(SeekFrom::Start(start as u64))?;
+        result?;
+
+        if buffer.len() < size {
+            return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into());
+        }
+
+        Ok(buffer)
+    }
+
+    /// Record the next `size` bytes as an entry holding `value`, and move
+    /// past them.
+    ///
+    /// # Return
+    ///
+    /// Returns the range that was claimed. If the entry can't be inserted
+    /// (see `BumpyVector::insert()`), the position doesn't change.
+    pub fn claim_next(&mut self, size: usize, value: T) -> BumpyResult<Range<usize>> {
+        let start = self.position()?;
+        let range = self.vector.range_from(start, size)?;
+
+        self.vector.insert(BumpyEntry {
+            entry: value,
+            range: range.clone(),
+        })?;
+
+        self.skip(size)?;
+
+        Ok(range)
+    }
+
+    /// Read the next `size` bytes, turn them into a value with `f`, and
+    /// record them as an entry holding that value.
+    ///
+    /// # Return
+    ///
+    /// Returns a clone of the new value, since it's often needed to decide
+    /// what to claim next. If reading, `f`, or inserting fails, the position
+    /// doesn't change.
+    pub fn claim_next_with<F>(&mut self, size: usize, f: F) -> BumpyResult<T>
+    where
+        F: FnOnce(&[u8]) -> BumpyResult<T>,
+        T: Clone,
+    {
+        // peek() checks the range before reading anything
+        let value = f(&self.peek(size)?)?;
+        self.claim_next(size, value.clone())?;
+
+        Ok(value)
+    }
+
+    /// Get a reference to the vector built so far.
+    pub fn vector(&self) -> &BumpyVector<T> {
+        &self.vector
+    }
+
+    /// Finish, and return the vector.
+    pub fn into_vector(self) -> BumpyVector<T> {
+        self.vector
+    }
+
+    /// Finish, and return both the stream and the vector.
+    pub fn into_parts(self) -> (R, BumpyVector<T>) {
+        (self.reader, self.vector)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_builder() {
+        let mut data = Cursor::new(vec![0u8; 16]);
+        data.set_position(2);
+
+        let mut builder: BumpyBuilder<_, &str> = BumpyBuilder::new(data).unwrap();
+        assert_eq!(16, builder.vector().max_size());
+        assert_eq!(2, builder.position().unwrap());
+
+        assert_eq!(2..6, builder.claim_next(4, "a").unwrap());
+        builder.skip(2).unwrap();
+        assert_eq!(8..9, builder.claim_next(1, "b").unwrap());
+        assert_eq!(9, builder.position().unwrap());
+
+        // Running off the end fails, and doesn't move
+        assert!(builder.claim_next(100, "c").is_err());
+        assert_eq!(9, builder.position().unwrap());
+
+        let (reader, v) = builder.into_parts();
+        assert_eq!(9, reader.position());
+        assert_eq!(2, v.len());
+        assert_eq!("a", v.get(5).unwrap().entry);
+        assert!(v.get(6).is_none());
+    }
+
+    #[test]
+    fn test_claim_with() {
+        let data = Cursor::new(b"\x03abc\x02".to_vec());
+        let mut builder: BumpyBuilder<_, Vec<u8>> = BumpyBuilder::new(data).unwrap();
+
+        assert_eq!(vec![3], builder.peek(1).unwrap());
+        assert_eq!(0, builder.position().unwrap());
+
+        let length = builder.claim_next_with(1, |b| Ok(b.to_vec())).unwrap()[0] as usize;
+        builder.claim_next_with(length, |b| Ok(b.to_vec())).unwrap();
+
+        // A failing parser doesn't claim anything
+        assert!(builder.claim_next_with(1, |_| Err("nope".into())).is_err());
+        assert_eq!(4, builder.position().unwrap());
+
+        // Neither does reading past the end
+        assert!(builder.claim_next_with(2, |b| Ok(b.to_vec())).is_err());
+        assert_eq!(4, builder.position().unwrap());
+
+        let v = builder.into_vector();
+        assert_eq!(b"abc".to_vec(), v.get(2).unwrap().entry);
+    }
+
+    #[test]
+    fn test_hostile_sizes() {
+        // Sizes from a corrupt length field fail, rather than trying to
+        // allocate them
+        let mut builder: BumpyBuilder<_, Vec<u8>> = BumpyBuilder::new(Cursor::new(vec![0u8; 16])).unwrap();
+        assert!(matches!(builder.peek(usize::MAX), Err(BumpyError::OutOfBounds)));
+        assert!(builder.claim_next_with(1 << 40, |b| Ok(b.to_vec())).is_err());
+
+        // Even when max_size doesn't catch them
+        let mut builder: BumpyBuilder<_, Vec<u8>> = BumpyBuilder::with_max_size(Cursor::new(vec![0u8; 16]), usize::MAX);
+        assert!(matches!(builder.peek(1 << 40), Err(BumpyError::Io(_))));
+        assert!(matches!(builder.peek(usize::MAX), Err(BumpyError::Io(_))));
+        assert!(builder.claim_next_with(1 << 40, |b| Ok(b.to_vec())).is_err());
+        assert_eq!(0, builder.position().unwrap());
+        assert_eq!(16, builder.peek(16).unwrap().len());
+    }
+}
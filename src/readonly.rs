@@ -0,0 +1,78 @@
+//! Make a vector read-only.
+//!
+//! A finished analysis is often handed to code that should look but not
+//! touch, like plugins. Once a vector is read-only, everything that would
+//! change it fails the same way it would if there was nothing to change -
+//! inserts return an error, removes return nothing, and mutable lookups
+//! return `None` - while everything that just reads keeps working.
+//!
+//! # Example
+//!
+//! ```
+//! use bumpy_vector::BumpyVector;
+//!
+//! let mut v: BumpyVector<&str> = BumpyVector::new(100);
+//! v.insert(("hello", 0..10).into()).unwrap();
+//! v.set_read_only(true);
+//!
+//! assert!(v.insert(("world", 10..20).into()).is_err());
+//! assert!(v.remove(5).is_none());
+//! assert!(v.get_mut(5).is_none());
+//!
+//! // Reading is fine
+//! assert_eq!("hello", v.get(5).unwrap().entry);
+//! ```
+
+use crate::BumpyVector;
+
+impl<T> BumpyVector<T> {
+    /// Turn read-only mode on or off.
+    pub fn set_read_only(&mut self, read_only: bool) {
+        self.read_only = read_only;
+    }
+
+    /// Returns `true` if the vector is read-only.
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use crate::BumpyError;
+    use crate::offsets::OffsetMap;
+
+    #[test]
+    fn test_read_only() {
+        let mut h: BumpyVector<String> = BumpyVector::new(100);
+        h.insert((String::from("a"), 10..20).into()).unwrap();
+        h.insert((String::from("b"), 20..30).into()).unwrap();
+
+        h.set_read_only(true);
+        assert!(h.is_read_only());
+        let generation = h.generation();
+
+        assert!(h.insert((String::from("c"), 0..5).into()).is_err());
+        assert!(h.insert_idempotent((String::from("c"), 0..5).into()).is_err());
+        assert!(h.upsert((String::from("c"), 10..20).into()).is_err());
+        assert!(h.remove(10).is_none());
+        assert_eq!(0, h.remove_range(0..100).len());
+        assert!(h.get_mut(10).is_none());
+        assert!(h.get_exact_mut(10).is_none());
+        assert!(!h.set_type(10, 1));
+        assert_eq!(0, h.iter_mut().count());
+        assert!(matches!(h.apply_offset_map(&OffsetMap::new(&[0..5, 10..20])), Err(BumpyError::ReadOnly)));
+
+        // Nothing changed
+        assert_eq!(generation, h.generation());
+        assert_eq!(100, h.max_size());
+        assert_eq!(vec!["a", "b"], h.iter().map(|e| &e.entry[..]).collect::<Vec<_>>());
+        assert_eq!(10..20, h.get(10).unwrap().range);
+
+        // Until it's writable again
+        h.set_read_only(false);
+        assert!(h.remove(10).is_some());
+    }
+}
@@ -0,0 +1,162 @@
+//! Move several entries at once.
+//!
+//! Shifting a run of adjacent entries along by one can't be done one entry
+//! at a time, since every entry but the last would land on its neighbour.
+//! `relocate_many()` checks where everything ends up as a whole, then moves
+//! all of it together, so moves within the batch never get in each other's
+//! way.
+//!
+//! # Example
+//!
+//! ```
+//! use bumpy_vector::BumpyVector;
+//!
+//! let mut v: BumpyVector<&str> = BumpyVector::new(100);
+//! v.insert(("a", 0..4).into()).unwrap();
+//! v.insert(("b", 4..8).into()).unwrap();
+//! v.insert(("c", 8..12).into()).unwrap();
+//!
+//! // Make room at the start
+//! v.relocate_many(&[(0, 1), (4, 5), (8, 9)]).unwrap();
+//!
+//! assert!(v.get(0).is_none());
+//! assert_eq!(1..5, v.get(1).unwrap().range);
+//! assert_eq!(9..13, v.get(12).unwrap().range);
+//! ```
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::ops::Range;
+
+use crate::{ranges, BumpyEntry, BumpyError, BumpyResult, BumpyVector};
+
+impl<T> BumpyVector<T> {
+    /// Apply a batch of moves, each of which moves the entry at `from` so it
+    /// starts at `to`, all at once.
+    ///
+    /// Note that `from` doesn't need to be the start of the entry, just
+    /// somewhere in it. If an entry is moved more than once, the last move
+    /// wins. Moved entries lose their history, like they would if they were
+    /// removed and inserted again, but keep their type tags.
+    ///
+    /// # Return
+    ///
+    /// If any move can't be done, nothing changes and an error is returned:
+    /// `NotFound` if there's no entry at `from`, `Pinned` if it's pinned,
+    /// `Locked` if it's locked or would move onto a locked range,
+    /// `OutOfBounds` (or `Overflow`) if it would end past `max_size`, and
+    /// `Overlap` if it would land on an entry that isn't moving, or on
+    /// another entry in the batch. A read-only vector always returns
+    /// `ReadOnly`.
+    pub fn relocate_many(&mut self, moves: &[(usize, usize)]) -> BumpyResult<()> {
+        if self.read_only {
+            return Err(BumpyError::ReadOnly);
+        }
+
+        // Where each moving entry starts -> where it's going
+        let mut planned: BTreeMap<usize, Range<usize>> = BTreeMap::new();
+
+        for &(from, to) in moves {
+            let start = self.get_entry_start(from).ok_or(BumpyError::NotFound)?;
+            let old = self.data[&start].range.clone();
+
+            if self.pins.contains(&start) {
+                return Err(BumpyError::Pinned);
+            }
+
+            let end = to.checked_add(old.len()).ok_or(BumpyError::Overflow)?;
+            let new = to..end;
+            if end > self.max_size {
+                return Err(BumpyError::OutOfBounds);
+            }
+
+            if self.is_locked(&old) || self.is_locked(&new) {
+                return Err(BumpyError::Locked);
+            }
+
+            planned.insert(start, new);
+        }
+
+        // Nothing can land on an entry that's staying put...
+        for new in planned.values() {
+            if self.iter_range(new.clone()).any(|e| !planned.contains_key(&e.range.start)) {
+                return Err(BumpyError::Overlap);
+            }
+        }
+
+        // ...or on another moving entry
+        let mut destinations: Vec<&Range<usize>> = planned.values().collect();
+        destinations.sort_by_key(|r| r.start);
+        if destinations.windows(2).any(|pair| ranges::overlaps(pair[0], pair[1])) {
+            return Err(BumpyError::Overlap);
+        }
+
+        // Take everything out first, so nothing collides on the way back in.
+        // Nothing expires until everything's back, and it's only one change
+        let moving: Vec<_> = planned.into_iter().filter_map(|(start, new)| {
+            let tag = self.types.get(start);
+            let expires = self.temporary.expiry(start);
+
+            self.unlink(start).map(|e| (e.entry, new, tag, expires))
+        }).collect();
+
+        let mut moved: BTreeSet<usize> = BTreeSet::new();
+        for (entry, range, tag, expires) in moving {
+            moved.insert(range.start);
+            self.restore(BumpyEntry { entry, range }, tag, false, expires);
+        }
+
+        self.expire_temporary_except(|s| moved.contains(&s));
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_relocate_many() {
+        let mut h: BumpyVector<&str> = BumpyVector::new(20);
+        h.insert(("a", 0..2).into()).unwrap();
+        h.insert(("b", 2..4).into()).unwrap();
+        h.insert(("c", 4..6).into()).unwrap();
+        h.insert_typed(("d", 10..12).into(), 7).unwrap();
+        h.insert(("e", 18..20).into()).unwrap();
+
+        let layout = |h: &BumpyVector<&'static str>| -> Vec<(&'static str, Range<usize>)> {
+            h.iter().map(|e| (e.entry, e.range.clone())).collect()
+        };
+        let before = layout(&h);
+
+        // Every one of these fails, and changes nothing
+        assert!(matches!(h.relocate_many(&[(0, 1), (8, 9)]), Err(BumpyError::NotFound)));
+        assert!(matches!(h.relocate_many(&[(0, 1)]), Err(BumpyError::Overlap)));
+        assert!(matches!(h.relocate_many(&[(0, 12), (11, 13)]), Err(BumpyError::Overlap)));
+        assert!(matches!(h.relocate_many(&[(0, 19)]), Err(BumpyError::OutOfBounds)));
+        assert!(matches!(h.relocate_many(&[(0, usize::MAX)]), Err(BumpyError::Overflow)));
+        h.lock_range(15..16);
+        assert!(matches!(h.relocate_many(&[(10, 14)]), Err(BumpyError::Locked)));
+        h.unlock_range(15..16);
+        h.pin(18);
+        assert!(matches!(h.relocate_many(&[(1, 6), (19, 16)]), Err(BumpyError::Pinned)));
+        h.set_read_only(true);
+        assert!(matches!(h.relocate_many(&[(10, 14)]), Err(BumpyError::ReadOnly)));
+        h.set_read_only(false);
+        assert_eq!(before, layout(&h));
+
+        // A run can shift along into itself
+        h.relocate_many(&[(0, 1), (2, 3), (4, 5)]).unwrap();
+        assert_eq!(vec![("a", 1..3), ("b", 3..5), ("c", 5..7), ("d", 10..12), ("e", 18..20)], layout(&h));
+
+        // And entries can swap places
+        h.relocate_many(&[(2, 10), (11, 1)]).unwrap();
+        assert_eq!(vec![("d", 1..3), ("b", 3..5), ("c", 5..7), ("a", 10..12), ("e", 18..20)], layout(&h));
+        assert_eq!(Some(7), h.type_of(1));
+        assert_eq!(None, h.type_of(10));
+
+        // Moving nothing is fine
+        h.relocate_many(&[]).unwrap();
+    }
+}
@@ -0,0 +1,138 @@
+//! Pick entries at random.
+//!
+//! Only available with the 'rand' feature:
+//!
+//! ```toml
+//! bumpy_vector = { version = "~0.0.0", features = ["rand"] }
+//! ```
+//!
+//! Nobody can check every one of a million automatically-generated
+//! annotations, but checking a few dozen at random says a lot about how good
+//! the rest are. Weighting the sample lets it lean towards the entries that
+//! matter more - bigger ones, say, or ones a particular analysis produced.
+//!
+//! # Example
+//!
+//! ```
+//! use bumpy_vector::BumpyVector;
+//! use rand::SeedableRng;
+//! use rand::rngs::StdRng;
+//!
+//! let mut v: BumpyVector<&str> = BumpyVector::new(100);
+//! v.insert(("a", 0..10).into()).unwrap();
+//! v.insert(("b", 10..20).into()).unwrap();
+//! v.insert(("c", 20..30).into()).unwrap();
+//!
+//! let mut rng = StdRng::seed_from_u64(1);
+//! assert_eq!(2, v.sample(2, &mut rng).len());
+//!
+//! // Only "b" has any weight, so it's the only one that can come up
+//! let picked = v.sample_weighted(2, &mut rng, |e| if e.entry == "b" { 1.0 } else { 0.0 }).unwrap();
+//! assert_eq!(1, picked.len());
+//! assert_eq!("b", picked[0].entry);
+//! ```
+
+use rand::Rng;
+use rand::seq::{IteratorRandom, SliceRandom};
+
+use crate::{BumpyEntry, BumpyError, BumpyResult, BumpyVector};
+
+impl<T> BumpyVector<T> {
+    /// Pick `n` different entries at random, each one equally likely.
+    ///
+    /// # Return
+    ///
+    /// Returns the picked entries, in order. If there are `n` or fewer
+    /// entries, they're all returned.
+    pub fn sample<R>(&self, n: usize, rng: &mut R) -> Vec<&BumpyEntry<T>>
+    where
+        R: Rng + ?Sized
+    {
+        let mut result = self.iter().choose_multiple(rng, n);
+        result.sort_by_key(|e| e.range.start);
+
+        result
+    }
+
+    /// Pick `n` different entries at random, where the chance of picking
+    /// each one is proportional to `weight(entry)`.
+    ///
+    /// Entries with a weight of zero are never picked.
+    ///
+    /// # Return
+    ///
+    /// Returns the picked entries, in order. If there are `n` or fewer
+    /// entries with a non-zero weight, they're all returned. If any weight is
+    /// negative or not a number, returns an error.
+    pub fn sample_weighted<R, F>(&self, n: usize, rng: &mut R, weight: F) -> BumpyResult<Vec<&BumpyEntry<T>>>
+    where
+        R: Rng + ?Sized,
+        F: Fn(&BumpyEntry<T>) -> f64,
+    {
+        let mut candidates: Vec<(&BumpyEntry<T>, f64)> = Vec::new();
+        for e in self.iter() {
+            let w = weight(e);
+
+            if w.is_nan() || w < 0.0 {
+                return Err(BumpyError::Other(format!("Invalid weight for entry at {}: {}", e.range.start, w)));
+            }
+
+            if w > 0.0 {
+                candidates.push((e, w));
+            }
+        }
+
+        let mut result: Vec<&BumpyEntry<T>> = match candidates.choose_multiple_weighted(rng, n, |(_, w)| *w) {
+            Ok(picked) => picked.map(|(e, _)| *e).collect(),
+            Err(e)     => return Err(BumpyError::Other(format!("Couldn't sample entries: {}", e))),
+        };
+        result.sort_by_key(|e| e.range.start);
+
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    #[test]
+    fn test_sample() {
+        let mut h: BumpyVector<usize> = BumpyVector::new(1000);
+        for i in 0..100 {
+            h.insert((i, (i * 10)..(i * 10 + 5)).into()).unwrap();
+        }
+        let mut rng = StdRng::seed_from_u64(1234);
+
+        // Uniform samples are different entries, in order
+        let picked = h.sample(10, &mut rng);
+        assert_eq!(10, picked.len());
+        assert!(picked.windows(2).all(|w| w[0].range.start < w[1].range.start));
+
+        // Asking for too many gets everything
+        assert_eq!(100, h.sample(1000, &mut rng).len());
+        assert_eq!(0, h.sample(0, &mut rng).len());
+
+        // Only even entries have weight
+        let picked = h.sample_weighted(20, &mut rng, |e| if e.entry % 2 == 0 { e.entry as f64 } else { 0.0 }).unwrap();
+        assert_eq!(20, picked.len());
+        assert!(picked.iter().all(|e| e.entry % 2 == 0));
+        assert!(picked.windows(2).all(|w| w[0].range.start < w[1].range.start));
+
+        // Zero weights never come up, even if it means returning fewer
+        let picked = h.sample_weighted(20, &mut rng, |e| if e.entry < 5 { 1.0 } else { 0.0 }).unwrap();
+        assert_eq!(vec![0, 1, 2, 3, 4], picked.iter().map(|e| e.entry).collect::<Vec<_>>());
+
+        // Bad weights are an error
+        assert!(h.sample_weighted(1, &mut rng, |_| -1.0).is_err());
+        assert!(h.sample_weighted(1, &mut rng, |_| f64::NAN).is_err());
+
+        // Nothing to pick from
+        let h: BumpyVector<usize> = BumpyVector::new(1000);
+        assert_eq!(0, h.sample(5, &mut rng).len());
+        assert_eq!(0, h.sample_weighted(5, &mut rng, |_| 1.0).unwrap().len());
+    }
+}
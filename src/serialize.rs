@@ -0,0 +1,255 @@
+//! Checking a vector as it's deserialized.
+//!
+//! A serialized vector is just data, so nothing stops it from having
+//! entries that overlap, go past `max_size`, or end before they start.
+//! Rather than trust it, deserializing reads everything into a plain struct
+//! first and then inserts the entries one at a time, so they're held to the
+//! same rules as any other insert.
+//!
+//! Serializing is the one place the derived code isn't good enough, because
+//! temporary entries (see `insert_temporary()`) have to be left out.
+//!
+//! Normally the first bad entry fails the whole load. To open a damaged file
+//! anyway, `BumpyVector::from_serialized_lossy()` keeps everything that fits
+//! and hands back the rest.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::convert::TryFrom;
+use std::ops::Range;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde::ser::SerializeStruct;
+
+use crate::{BumpyEntry, BumpyError, BumpyVector, InsertError};
+use crate::provenance::ProvenanceTracker;
+use crate::types::TypeTag;
+
+/// The fields of a `BumpyVector` that are serialized, before they've been
+/// checked.
+#[derive(Deserialize)]
+pub(crate) struct SerializedBumpyVector<T> {
+    data: BTreeMap<usize, BumpyEntry<T>>,
+    max_size: usize,
+
+    #[serde(default)]
+    read_only: bool,
+
+    #[serde(default)]
+    locks: Vec<Range<usize>>,
+
+    #[serde(default)]
+    pins: BTreeSet<usize>,
+
+    #[serde(default)]
+    types: BTreeMap<usize, TypeTag>,
+
+    #[serde(default)]
+    provenance: Option<ProvenanceTracker>,
+}
+
+impl<T> SerializedBumpyVector<T> {
+    /// Build the vector, keeping every entry that fits and returning the
+    /// ones that don't.
+    fn load(self) -> (BumpyVector<T>, Vec<InsertError<T>>) {
+        let mut result = BumpyVector::new(self.max_size);
+        let mut dropped = Vec::new();
+
+        // The keys are just a copy of where each entry starts, so they're
+        // ignored in favour of the entries themselves
+        for e in self.data.into_values() {
+            if let Err(e) = result.insert(e) {
+                dropped.push(e);
+            }
+        }
+
+        for r in self.locks {
+            result.lock_range(r);
+        }
+
+        for start in self.pins {
+            result.pin(start);
+        }
+
+        // A tag without an entry at exactly that start is meaningless
+        for (start, tag) in self.types {
+            if let Some(e) = result.data.get(&start) {
+                result.types.set(start, e.range.len(), tag);
+            }
+        }
+
+        // Loading isn't a change
+        result.generation = 0;
+        result.read_only = self.read_only;
+        result.provenance = self.provenance;
+
+        (result, dropped)
+    }
+}
+
+impl<T> TryFrom<SerializedBumpyVector<T>> for BumpyVector<T> {
+    type Error = BumpyError;
+
+    fn try_from(o: SerializedBumpyVector<T>) -> Result<Self, BumpyError> {
+        let (result, dropped) = o.load();
+
+        match dropped.into_iter().next() {
+            Some(e) => Err(e.reason),
+            None    => Ok(result),
+        }
+    }
+}
+
+/// The entries that aren't temporary, as a map from where they start.
+struct PersistentEntries<'a, T>(&'a BumpyVector<T>);
+
+impl<'a, T> Serialize for PersistentEntries<'a, T>
+where
+    T: Serialize
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(self.0.data.iter().filter(|(start, _)| !self.0.temporary.contains(**start)))
+    }
+}
+
+impl<T> Serialize for BumpyVector<T>
+where
+    T: Serialize
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("BumpyVector", 7)?;
+        state.serialize_field("data", &PersistentEntries(self))?;
+        state.serialize_field("max_size", &self.max_size)?;
+        state.serialize_field("read_only", &self.read_only)?;
+        state.serialize_field("locks", &self.locks)?;
+        state.serialize_field("pins", &self.pins)?;
+        state.serialize_field("types", self.types.tags())?;
+        state.serialize_field("provenance", &self.provenance)?;
+
+        state.end()
+    }
+}
+
+impl<T> BumpyVector<T> {
+    /// Deserialize a vector, dropping any entries that don't fit instead of
+    /// failing.
+    ///
+    /// Entries that overlap an earlier one, go past `max_size`, or are empty
+    /// are left out, and returned along with the reason. Anything that can't
+    /// be parsed at all is still an error.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bumpy_vector::{BumpyError, BumpyVector};
+    ///
+    /// // "b" overlaps "a", and "c" is past the end
+    /// let serialized = r#"(
+    ///     data: {
+    ///         0: (entry: "a", range: (start: 0, end: 4)),
+    ///         2: (entry: "b", range: (start: 2, end: 6)),
+    ///         8: (entry: "c", range: (start: 8, end: 12)),
+    ///     },
+    ///     max_size: 10,
+    /// )"#;
+    ///
+    /// let mut deserializer = ron::de::Deserializer::from_str(serialized).unwrap();
+    /// let (v, dropped) = BumpyVector::<String>::from_serialized_lossy(&mut deserializer).unwrap();
+    ///
+    /// assert_eq!(1, v.len());
+    /// assert_eq!("a", v.get(0).unwrap().entry);
+    ///
+    /// assert_eq!(2, dropped.len());
+    /// assert_eq!("b", dropped[0].entry.entry);
+    /// assert!(matches!(dropped[0].reason, BumpyError::Overlap));
+    /// assert!(matches!(dropped[1].reason, BumpyError::OutOfBounds));
+    /// ```
+    pub fn from_serialized_lossy<'de, D>(deserializer: D) -> Result<(Self, Vec<InsertError<T>>), D::Error>
+    where
+        D: Deserializer<'de>,
+        T: Deserialize<'de>,
+    {
+        Ok(SerializedBumpyVector::deserialize(deserializer)?.load())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_deserialize_checks() {
+        let mut h: BumpyVector<String> = BumpyVector::new(10);
+        h.insert((String::from("a"), 1..3).into()).unwrap();
+        h.insert((String::from("b"), 3..4).into()).unwrap();
+        h.lock_range(0..2);
+        h.lock_range(5..6);
+        let serialized = ron::ser::to_string(&h).unwrap();
+
+        let h: BumpyVector<String> = ron::de::from_str(&serialized).unwrap();
+        assert_eq!(2, h.len());
+        assert_eq!(&[0..2, 5..6], h.locked_ranges());
+        assert_eq!(0, h.generation());
+
+        // Tags survive too, as long as their entry does
+        let mut h: BumpyVector<String> = BumpyVector::new(10);
+        h.insert_typed((String::from("a"), 1..3).into(), 7).unwrap();
+        let h: BumpyVector<String> = ron::de::from_str(&ron::ser::to_string(&h).unwrap()).unwrap();
+        assert_eq!(Some(7), h.type_of(2));
+
+        let h: BumpyVector<String> = ron::de::from_str(r#"(data: { 1: (entry: "a", range: (start: 1, end: 3)) }, max_size: 10, types: { 2: 7 })"#).unwrap();
+        assert_eq!(None, h.type_of(2));
+
+        // Hand-edited files can have all sorts of problems
+        let load = |data: &str, max_size: usize| -> Result<BumpyVector<String>, ron::de::Error> {
+            ron::de::from_str(&format!("(data: {{ {} }}, max_size: {})", data, max_size))
+        };
+
+        assert!(load(r#"1: (entry: "a", range: (start: 1, end: 3))"#, 10).is_ok());
+        assert!(load(r#"1: (entry: "a", range: (start: 1, end: 3)), 2: (entry: "b", range: (start: 2, end: 4))"#, 10).is_err());
+        assert!(load(r#"1: (entry: "a", range: (start: 1, end: 11))"#, 10).is_err());
+        assert!(load(r#"1: (entry: "a", range: (start: 3, end: 1))"#, 10).is_err());
+        assert!(load(r#"18446744073709551615: (entry: "a", range: (start: 18446744073709551615, end: 1))"#, 10).is_err());
+
+        // A key that doesn't match its entry is harmless
+        let h = load(r#"5: (entry: "a", range: (start: 1, end: 3))"#, 10).unwrap();
+        assert_eq!(1..3, h.get(2).unwrap().range);
+        assert!(h.get(5).is_none());
+    }
+
+    #[test]
+    fn test_from_serialized_lossy() {
+        let load = |data: &str| {
+            let serialized = format!("(data: {{ {} }}, max_size: 10, locks: [(start: 8, end: 9)])", data);
+            let mut deserializer = ron::de::Deserializer::from_str(&serialized).unwrap();
+
+            BumpyVector::<String>::from_serialized_lossy(&mut deserializer)
+        };
+
+        let (h, dropped) = load(r#"
+            1: (entry: "a", range: (start: 1, end: 3)),
+            2: (entry: "b", range: (start: 2, end: 4)),
+            3: (entry: "c", range: (start: 3, end: 3)),
+            4: (entry: "d", range: (start: 4, end: 6)),
+            9: (entry: "e", range: (start: 9, end: 11)),
+        "#).unwrap();
+
+        assert_eq!(vec!["a", "d"], h.iter().map(|e| &e.entry[..]).collect::<Vec<_>>());
+        assert_eq!(1, h.locked_ranges().len());
+        assert!(h.is_locked(&(8..9)));
+
+        let dropped: Vec<(&str, &BumpyError)> = dropped.iter().map(|e| (&e.entry.entry[..], &e.reason)).collect();
+        assert_eq!(3, dropped.len());
+        assert!(matches!(dropped[0], ("b", BumpyError::Overlap)));
+        assert!(matches!(dropped[1], ("c", BumpyError::ZeroSize)));
+        assert!(matches!(dropped[2], ("e", BumpyError::OutOfBounds)));
+
+        // Nothing wrong, nothing dropped
+        let (h, dropped) = load(r#"1: (entry: "a", range: (start: 1, end: 3))"#).unwrap();
+        assert_eq!(1, h.len());
+        assert_eq!(0, dropped.len());
+
+        // But it still has to parse
+        assert!(load(r#"1: (entry: "a", range: (start: 1))"#).is_err());
+    }
+}
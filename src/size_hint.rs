@@ -0,0 +1,191 @@
+//! Estimate how big a vector is once it's saved.
+//!
+//! Autosaving a big project on every change is slow, so it helps to know
+//! whether a full save is worth it, or whether a few entries have grown
+//! suspiciously large. `serialized_size_hint()` works out how many bytes each
+//! entry takes in one of the formats this crate can write, and
+//! `serialized_size_hint_with()` does the same with any other estimate.
+//!
+//! As with saving, temporary entries (see `insert_temporary()`) are left out.
+
+use std::ops::Range;
+
+#[cfg(any(feature = "serialize", feature = "jsonl"))]
+use serde::Serialize;
+
+#[cfg(any(feature = "serialize", feature = "jsonl"))]
+use crate::BumpyResult;
+#[cfg(feature = "serialize")]
+use crate::BumpyError;
+use crate::{BumpyEntry, BumpyVector};
+
+/// A format to estimate the size of; see `BumpyVector::serialized_size_hint()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SerializedFormat {
+    /// The whole vector, serialized with `ron`. Only available with the
+    /// 'serialize' feature.
+    #[cfg(feature = "serialize")]
+    Ron,
+
+    /// The entries as JSON Lines (see `export_jsonl()`). Only available with
+    /// the 'jsonl' feature.
+    #[cfg(feature = "jsonl")]
+    JsonLines,
+}
+
+/// How many bytes a vector takes to save.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct SizeHint {
+    /// The range of each entry and how many bytes it takes, in order.
+    pub entries: Vec<(Range<usize>, usize)>,
+
+    /// How many bytes it all takes, including anything that isn't part of
+    /// an entry.
+    pub total: usize,
+}
+
+impl SizeHint {
+    /// Return the entry that takes the most bytes, if there are any.
+    pub fn largest(&self) -> Option<&(Range<usize>, usize)> {
+        self.entries.iter().max_by_key(|(_, size)| *size)
+    }
+}
+
+impl<T> BumpyVector<T> {
+    /// Estimate how many bytes each entry, and the whole vector, take to
+    /// save in `format`.
+    ///
+    /// For `SerializedFormat::JsonLines`, this is exact; for
+    /// `SerializedFormat::Ron` it's close, since each entry is serialized on
+    /// its own.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # #[cfg(feature = "jsonl")]
+    /// # {
+    /// use bumpy_vector::BumpyVector;
+    /// use bumpy_vector::size_hint::SerializedFormat;
+    ///
+    /// let mut v: BumpyVector<String> = BumpyVector::new(100);
+    /// v.insert((String::from("header"), 0..16).into()).unwrap();
+    /// v.insert(("x".repeat(10_000), 16..64).into()).unwrap();
+    ///
+    /// let hint = v.serialized_size_hint(SerializedFormat::JsonLines).unwrap();
+    /// assert_eq!(39, hint.entries[0].1);
+    /// assert_eq!(16..64, hint.largest().unwrap().0);
+    ///
+    /// let mut out: Vec<u8> = Vec::new();
+    /// v.export_jsonl(&mut out).unwrap();
+    /// assert_eq!(out.len(), hint.total);
+    /// # }
+    /// ```
+    ///
+    /// # Return
+    ///
+    /// Returns an error if something can't be serialized.
+    #[cfg(any(feature = "serialize", feature = "jsonl"))]
+    pub fn serialized_size_hint(&self, format: SerializedFormat) -> BumpyResult<SizeHint>
+    where
+        T: Serialize
+    {
+        let mut entries = Vec::new();
+
+        for e in self.iter().filter(|e| !self.temporary.contains(e.range.start)) {
+            let size = match format {
+                #[cfg(feature = "serialize")]
+                SerializedFormat::Ron => ron::ser::to_string(e).map_err(|e| BumpyError::Other(e.to_string()))?.len(),
+
+                // Plus the newline
+                #[cfg(feature = "jsonl")]
+                SerializedFormat::JsonLines => serde_json::to_vec(&crate::jsonl::Line(e)).map_err(crate::jsonl::json_error)?.len() + 1,
+            };
+
+            entries.push((e.range.clone(), size));
+        }
+
+        let total = match format {
+            #[cfg(feature = "serialize")]
+            SerializedFormat::Ron => ron::ser::to_string(self).map_err(|e| BumpyError::Other(e.to_string()))?.len(),
+
+            #[cfg(feature = "jsonl")]
+            SerializedFormat::JsonLines => entries.iter().map(|(_, size)| size).sum(),
+        };
+
+        Ok(SizeHint { entries, total })
+    }
+
+    /// Estimate how many bytes each entry takes to save with `f`.
+    ///
+    /// The total is just the sum of the entries.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bumpy_vector::BumpyVector;
+    ///
+    /// let mut v: BumpyVector<Vec<u8>> = BumpyVector::new(100);
+    /// v.insert((vec![0; 16], 0..16).into()).unwrap();
+    /// v.insert((vec![0; 4], 16..20).into()).unwrap();
+    ///
+    /// // Say, a length and the bytes
+    /// let hint = v.serialized_size_hint_with(|e| 8 + e.entry.len());
+    /// assert_eq!(36, hint.total);
+    /// ```
+    pub fn serialized_size_hint_with<F>(&self, mut f: F) -> SizeHint
+    where
+        F: FnMut(&BumpyEntry<T>) -> usize
+    {
+        let entries: Vec<(Range<usize>, usize)> = self.iter()
+            .filter(|e| !self.temporary.contains(e.range.start))
+            .map(|e| (e.range.clone(), f(e)))
+            .collect();
+        let total = entries.iter().map(|(_, size)| size).sum();
+
+        SizeHint { entries, total }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_serialized_size_hint() {
+        let mut h: BumpyVector<String> = BumpyVector::new(100);
+        h.insert((String::from("a"), 0..10).into()).unwrap();
+        h.insert((String::from("bbbb"), 10..20).into()).unwrap();
+        h.insert_temporary((String::from("temporary"), 20..30).into(), 5).unwrap();
+
+        let hint = h.serialized_size_hint_with(|e| e.entry.len());
+        assert_eq!(vec![(0..10, 1), (10..20, 4)], hint.entries);
+        assert_eq!(5, hint.total);
+        assert_eq!(Some(&(10..20, 4)), hint.largest());
+
+        assert_eq!(None, BumpyVector::<String>::new(10).serialized_size_hint_with(|_| 1).largest());
+
+        #[cfg(feature = "serialize")]
+        {
+            let hint = h.serialized_size_hint(SerializedFormat::Ron).unwrap();
+            assert_eq!(ron::ser::to_string(&h.get(0).unwrap()).unwrap().len(), hint.entries[0].1);
+            assert_eq!(ron::ser::to_string(&h).unwrap().len(), hint.total);
+            assert!(hint.total > hint.entries.iter().map(|(_, size)| size).sum());
+        }
+
+        #[cfg(feature = "jsonl")]
+        {
+            let hint = h.serialized_size_hint(SerializedFormat::JsonLines).unwrap();
+            assert_eq!(2, hint.entries.len());
+
+            let mut out: Vec<u8> = Vec::new();
+            h.export_jsonl(&mut out).unwrap();
+            assert_eq!(out.len(), hint.total);
+
+            // Values that can't be turned into JSON are an error, not a panic
+            let mut h: BumpyVector<std::collections::HashMap<Vec<u8>, u32>> = BumpyVector::new(10);
+            h.insert((vec![(vec![1], 2)].into_iter().collect(), 0..10).into()).unwrap();
+            assert!(h.serialized_size_hint(SerializedFormat::JsonLines).is_err());
+        }
+    }
+}
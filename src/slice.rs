@@ -0,0 +1,262 @@
+//! Views of part of a vector.
+//!
+//! `slice()` returns a `BumpySlice`, which only sees the entries in a range.
+//!
+//! A plugin that annotates one section of a file shouldn't be able to touch
+//! the rest of it. `with_range_mut()` hands a closure a `BumpySliceMut`,
+//! which works like the vector itself but refuses to change anything outside
+//! its range.
+//!
+//! # Example
+//!
+//! ```
+//! use bumpy_vector::BumpyVector;
+//!
+//! let mut v: BumpyVector<&str> = BumpyVector::new(100);
+//! v.insert(("header", 0..16).into()).unwrap();
+//!
+//! v.with_range_mut(16..64, |section| {
+//!     assert!(section.insert(("code", 16..32).into()).is_ok());
+//!
+//!     // Outside the section
+//!     assert!(section.insert(("data", 64..80).into()).is_err());
+//!     assert!(section.remove(0).is_none());
+//! }).unwrap();
+//!
+//! assert_eq!(2, v.len());
+//! ```
+
+use std::ops::Range;
+
+use crate::{ranges, BumpyEntry, BumpyError, BumpyResult, BumpyVector, InsertError};
+
+/// A read-only view of the part of a vector inside a range; see
+/// `BumpyVector::slice()`.
+///
+/// Entries that are partly inside the range are part of the view, like they
+/// are for `get_range()`.
+#[derive(Debug, Clone)]
+pub struct BumpySlice<'a, T> {
+    vector: &'a BumpyVector<T>,
+    range: Range<usize>,
+}
+
+impl<'a, T> BumpySlice<'a, T> {
+    /// The range this view covers.
+    pub fn range(&self) -> Range<usize> {
+        self.range.clone()
+    }
+
+    /// Return a reference to the entry at `index`, if it's in the view.
+    pub fn get(&self, index: usize) -> Option<&'a BumpyEntry<T>> {
+        if !self.range.contains(&index) {
+            return None;
+        }
+
+        self.vector.get(index)
+    }
+
+    /// Iterate over the entries in the view, in order.
+    pub fn iter(&self) -> crate::iter::RangeIter<'a, T> {
+        self.vector.iter_range(self.range.clone())
+    }
+
+    /// Return the number of entries in the view.
+    pub fn len(&self) -> usize {
+        self.iter().count()
+    }
+
+    /// Return `true` if there are no entries in the view.
+    pub fn is_empty(&self) -> bool {
+        self.iter().next().is_none()
+    }
+}
+
+/// A mutable view of the part of a vector inside a range; see
+/// `BumpyVector::with_range_mut()`.
+///
+/// Only entries that are entirely inside the range can be inserted, changed,
+/// or removed. Entries that stick out of it can still be read.
+#[derive(Debug)]
+pub struct BumpySliceMut<'a, T> {
+    vector: &'a mut BumpyVector<T>,
+    range: Range<usize>,
+}
+
+impl<'a, T> BumpySliceMut<'a, T> {
+    /// Returns `true` if `range` is entirely inside the view.
+    fn contains(&self, range: &Range<usize>) -> bool {
+        ranges::contains(&self.range, range)
+    }
+
+    /// The range this view covers.
+    pub fn range(&self) -> Range<usize> {
+        self.range.clone()
+    }
+
+    /// Insert an entry; see `BumpyVector::insert()`.
+    ///
+    /// # Return
+    ///
+    /// Returns an `OutOfBounds` error if any of the entry is outside the view.
+    pub fn insert(&mut self, entry: BumpyEntry<T>) -> Result<(), InsertError<T>> {
+        if !self.contains(&entry.range) {
+            return Err(InsertError { entry, reason: BumpyError::OutOfBounds });
+        }
+
+        self.vector.insert(entry)
+    }
+
+    /// Remove the entry at `index`; see `BumpyVector::remove()`.
+    ///
+    /// # Return
+    ///
+    /// Returns `None` if the entry isn't entirely inside the view.
+    pub fn remove(&mut self, index: usize) -> Option<BumpyEntry<T>> {
+        if !self.contains(&self.vector.get(index)?.range.clone()) {
+            return None;
+        }
+
+        self.vector.remove(index)
+    }
+
+    /// Remove every entry that's entirely inside the view.
+    pub fn remove_all(&mut self) -> Vec<BumpyEntry<T>> {
+        let view = self.range.clone();
+
+        self.vector.extract_if(|e| ranges::contains(&view, &e.range)).collect()
+    }
+
+    /// Return a reference to the entry at `index`, if it's in the view.
+    pub fn get(&self, index: usize) -> Option<&BumpyEntry<T>> {
+        if !self.range.contains(&index) {
+            return None;
+        }
+
+        self.vector.get(index)
+    }
+
+    /// Return a mutable reference to the value at `index`; see
+    /// `BumpyVector::get_mut()`.
+    ///
+    /// # Return
+    ///
+    /// Returns `None` if the entry isn't entirely inside the view.
+    pub fn get_mut(&mut self, index: usize) -> Option<BumpyEntry<&mut T>> {
+        if !self.contains(&self.vector.get(index)?.range.clone()) {
+            return None;
+        }
+
+        self.vector.get_mut(index)
+    }
+
+    /// Iterate over the entries that overlap the view, in order.
+    pub fn iter(&self) -> crate::iter::RangeIter<'_, T> {
+        self.vector.iter_range(self.range.clone())
+    }
+}
+
+impl<T> BumpyVector<T> {
+    /// Return a read-only view of the entries within `range`.
+    ///
+    /// As with `get_range()`, any part of the range past `max_size` is
+    /// ignored.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bumpy_vector::BumpyVector;
+    ///
+    /// let mut v: BumpyVector<&str> = BumpyVector::new(100);
+    /// v.insert(("a", 0..10).into()).unwrap();
+    /// v.insert(("b", 10..20).into()).unwrap();
+    /// v.insert(("c", 50..60).into()).unwrap();
+    ///
+    /// let s = v.slice(5..15);
+    /// assert_eq!(2, s.len());
+    /// assert_eq!("b", s.get(12).unwrap().entry);
+    /// assert!(s.get(55).is_none());
+    /// ```
+    pub fn slice(&self, range: Range<usize>) -> BumpySlice<'_, T> {
+        BumpySlice { vector: self, range }
+    }
+
+    /// Call `f` with a view of this vector that can only change what's
+    /// inside `range`.
+    ///
+    /// # Return
+    ///
+    /// Returns whatever `f` does, or an error (without calling `f`) if
+    /// `range` ends before it starts or goes past `max_size`.
+    pub fn with_range_mut<F, R>(&mut self, range: Range<usize>, f: F) -> BumpyResult<R>
+    where
+        F: FnOnce(&mut BumpySliceMut<'_, T>) -> R
+    {
+        self.check_range(&range)?;
+
+        Ok(f(&mut BumpySliceMut { vector: self, range }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_with_range_mut() {
+        let mut h: BumpyVector<String> = BumpyVector::new(100);
+        h.insert((String::from("before"), 0..10).into()).unwrap();
+        h.insert((String::from("across"), 15..25).into()).unwrap();
+        h.insert((String::from("inside"), 30..40).into()).unwrap();
+
+        let removed = h.with_range_mut(20..50, |view| {
+            assert_eq!(20..50, view.range());
+
+            // Everything touching the view can be seen
+            let seen: Vec<&str> = view.iter().map(|e| &e.entry[..]).collect();
+            assert_eq!(vec!["across", "inside"], seen);
+            assert!(view.get(5).is_none());
+            assert_eq!("across", view.get(20).unwrap().entry);
+
+            // But only what's inside can be changed
+            assert!(view.get_mut(20).is_none());
+            view.get_mut(35).unwrap().entry.push('!');
+            assert!(view.remove(20).is_none());
+            assert!(matches!(view.insert((String::from("x"), 45..55).into()), Err(InsertError { reason: BumpyError::OutOfBounds, .. })));
+            assert!(matches!(view.insert((String::from("x"), 25..26).into()), Ok(())));
+            assert!(matches!(view.insert((String::from("x"), 24..26).into()), Err(InsertError { reason: BumpyError::Overlap, .. })));
+
+            view.remove_all()
+        }).unwrap();
+
+        let removed: Vec<&str> = removed.iter().map(|e| &e.entry[..]).collect();
+        assert_eq!(vec!["x", "inside!"], removed);
+        assert_eq!(vec!["before", "across"], h.iter().map(|e| &e.entry[..]).collect::<Vec<_>>());
+
+        // The range itself has to make sense
+        assert!(h.with_range_mut(50..101, |_| ()).is_err());
+        assert!(h.with_range_mut(Range { start: 50, end: 40 }, |_| ()).is_err());
+    }
+
+    #[test]
+    fn test_slice() {
+        let mut h: BumpyVector<u32> = BumpyVector::new(100);
+        h.insert((1, 0..10).into()).unwrap();
+        h.insert((2, 15..25).into()).unwrap();
+        h.insert((3, 90..100).into()).unwrap();
+
+        let s = h.slice(20..95);
+        assert_eq!(20..95, s.range());
+        assert_eq!(2, s.len());
+        assert_eq!(vec![2, 3], s.iter().map(|e| e.entry).collect::<Vec<_>>());
+        assert_eq!(2, s.get(20).unwrap().entry);
+        assert!(s.get(15).is_none());
+        assert!(s.get(97).is_none());
+        assert!(s.get(30).is_none());
+
+        assert!(h.slice(30..90).is_empty());
+        assert!(h.slice(200..300).is_empty());
+        assert_eq!(1, h.slice(95..200).len());
+    }
+}
@@ -0,0 +1,306 @@
+//! Look at an index once, then decide what to do about it.
+//!
+//! "Update the entry covering this address, or insert one if there's nothing
+//! there" takes two lookups and an awkward `match` with `get_mut()` and
+//! `insert()`. `entry_at()` does the lookup once and returns a `Slot` -
+//! either the entry that's there, or the empty space - with everything that
+//! can be done with it. It works like `HashMap::entry()`.
+//!
+//! # Example
+//!
+//! ```
+//! use bumpy_vector::BumpyVector;
+//!
+//! let mut v: BumpyVector<u32> = BumpyVector::new(100);
+//!
+//! // Count references to each 4-byte word
+//! for address in [0, 8, 2, 0] {
+//!     *v.entry_at(address).unwrap().or_insert_with(4, || 0).unwrap() += 1;
+//! }
+//!
+//! assert_eq!(3, v.get(0).unwrap().entry);
+//! assert_eq!(1, v.get(8).unwrap().entry);
+//! ```
+
+use std::ops::Range;
+
+use crate::{BumpyEntry, BumpyVector, InsertError};
+
+/// A position in a vector, with or without an entry; see
+/// `BumpyVector::entry_at()`.
+#[derive(Debug)]
+pub enum Slot<'a, T> {
+    /// There's an entry covering the index.
+    Occupied(OccupiedSlot<'a, T>),
+
+    /// There isn't.
+    Vacant(VacantSlot<'a, T>),
+}
+
+impl<'a, T> Slot<'a, T> {
+    /// Returns the value if there's an entry, or inserts one from `index`
+    /// to `index + size` with the value from `f`.
+    ///
+    /// # Return
+    ///
+    /// Returns a reference to the value, or an error if a new entry doesn't
+    /// fit (see `insert()`).
+    pub fn or_insert_with<F>(self, size: usize, f: F) -> Result<&'a mut T, InsertError<T>>
+    where
+        F: FnOnce() -> T
+    {
+        match self {
+            Slot::Occupied(slot) => Ok(slot.into_mut()),
+            Slot::Vacant(slot)   => slot.insert(f(), size),
+        }
+    }
+
+    /// Call `f` on the value, if there's an entry.
+    pub fn and_modify<F>(mut self, f: F) -> Self
+    where
+        F: FnOnce(&mut T)
+    {
+        if let Slot::Occupied(slot) = &mut self {
+            f(slot.get_mut());
+        }
+
+        self
+    }
+}
+
+/// An index that's covered by an entry.
+#[derive(Debug)]
+pub struct OccupiedSlot<'a, T> {
+    vector: &'a mut BumpyVector<T>,
+
+    /// Where the entry starts.
+    start: usize,
+}
+
+impl<'a, T> OccupiedSlot<'a, T> {
+    fn entry(&self) -> &BumpyEntry<T> {
+        match self.vector.data.get(&self.start) {
+            Some(e) => e,
+            None    => unreachable!("An occupied slot always has an entry"),
+        }
+    }
+
+    fn entry_mut(&mut self) -> &mut BumpyEntry<T> {
+        match self.vector.data.get_mut(&self.start) {
+            Some(e) => e,
+            None    => unreachable!("An occupied slot always has an entry"),
+        }
+    }
+
+    /// The range of the entry.
+    pub fn range(&self) -> Range<usize> {
+        self.entry().range.clone()
+    }
+
+    /// Returns a reference to the value.
+    pub fn get(&self) -> &T {
+        &self.entry().entry
+    }
+
+    /// Returns a mutable reference to the value.
+    pub fn get_mut(&mut self) -> &mut T {
+        self.vector.touch(self.start);
+
+        &mut self.entry_mut().entry
+    }
+
+    /// Returns a mutable reference to the value that lives as long as the
+    /// vector borrow.
+    pub fn into_mut(self) -> &'a mut T {
+        self.vector.touch(self.start);
+
+        match self.vector.data.get_mut(&self.start) {
+            Some(e) => &mut e.entry,
+            None    => unreachable!("An occupied slot always has an entry"),
+        }
+    }
+
+    /// Replace the value, keeping the range, and return the old one.
+    pub fn insert(&mut self, value: T) -> T {
+        std::mem::replace(self.get_mut(), value)
+    }
+
+    /// Remove the entry.
+    ///
+    /// # Return
+    ///
+    /// Returns the entry, or `None` if it's locked.
+    pub fn remove(self) -> Option<BumpyEntry<T>> {
+        self.vector.remove_exact(self.start)
+    }
+}
+
+/// An index that isn't covered by an entry.
+#[derive(Debug)]
+pub struct VacantSlot<'a, T> {
+    vector: &'a mut BumpyVector<T>,
+    index: usize,
+}
+
+impl<'a, T> VacantSlot<'a, T> {
+    /// The index that was looked up.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// The empty space around the index, up to the entries on either side
+    /// (or the ends of the vector).
+    ///
+    /// This is empty if the index is past `max_size`.
+    pub fn available(&self) -> Range<usize> {
+        let start = self.vector.entry_before(self.index).map(|e| e.range.end).unwrap_or(0);
+        let end = self.vector.entry_after(self.index).map(|e| e.range.start).unwrap_or(self.vector.max_size);
+
+        if self.index >= end {
+            return end..end;
+        }
+
+        start..end
+    }
+
+    /// Insert an entry from `index` to `index + size`.
+    ///
+    /// # Return
+    ///
+    /// Returns a reference to the new value, or an error if it doesn't fit
+    /// (see `insert()`).
+    pub fn insert(self, value: T, size: usize) -> Result<&'a mut T, InsertError<T>> {
+        let entry = match self.vector.range_from(self.index, size) {
+            Ok(range) => BumpyEntry { entry: value, range },
+            Err(reason) => return Err(InsertError {
+                entry: BumpyEntry { entry: value, range: self.index..self.index },
+                reason,
+            }),
+        };
+
+        self.vector.insert(entry)?;
+
+        match self.vector.data.get_mut(&self.index) {
+            Some(e) => Ok(&mut e.entry),
+            None    => unreachable!("The entry was just inserted"),
+        }
+    }
+}
+
+impl<T> BumpyVector<T> {
+    /// Look up `index`, and return either the entry that covers it or the
+    /// empty space there.
+    ///
+    /// Note that the entry doesn't necessarily need to *start* at `index`,
+    /// just overlap it.
+    ///
+    /// # Return
+    ///
+    /// Like `get_mut()`, returns `None` if the vector is read-only.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bumpy_vector::BumpyVector;
+    /// use bumpy_vector::slot::Slot;
+    ///
+    /// let mut v: BumpyVector<&str> = BumpyVector::new(100);
+    /// v.insert(("hello", 10..20).into()).unwrap();
+    ///
+    /// match v.entry_at(15).unwrap() {
+    ///     Slot::Occupied(slot) => assert_eq!(10..20, slot.range()),
+    ///     Slot::Vacant(_)      => unreachable!(),
+    /// }
+    ///
+    /// match v.entry_at(25).unwrap() {
+    ///     Slot::Occupied(_)  => unreachable!(),
+    ///     Slot::Vacant(slot) => assert_eq!(20..100, slot.available()),
+    /// }
+    /// ```
+    pub fn entry_at(&mut self, index: usize) -> Option<Slot<'_, T>> {
+        if self.read_only {
+            return None;
+        }
+
+        match self.get_entry_start(index) {
+            Some(start) => {
+                self.record_access(start);
+
+                Some(Slot::Occupied(OccupiedSlot { vector: self, start }))
+            },
+            None => Some(Slot::Vacant(VacantSlot { vector: self, index })),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_entry_at() {
+        let mut h: BumpyVector<String> = BumpyVector::new(100);
+        h.insert((String::from("a"), 10..20).into()).unwrap();
+        h.insert((String::from("b"), 30..40).into()).unwrap();
+
+        // Occupied
+        match h.entry_at(15).unwrap() {
+            Slot::Occupied(mut slot) => {
+                assert_eq!(10..20, slot.range());
+                assert_eq!("a", slot.get());
+                slot.get_mut().push('!');
+                assert_eq!("a!", slot.insert(String::from("c")));
+            },
+            Slot::Vacant(_) => panic!("expected an entry"),
+        }
+        assert_eq!("c", h.get(10).unwrap().entry);
+
+        // Vacant, with room on both sides
+        match h.entry_at(25).unwrap() {
+            Slot::Vacant(slot) => {
+                assert_eq!(25, slot.index());
+                assert_eq!(20..30, slot.available());
+                assert!(slot.insert(String::from("d"), 10).is_err());
+            },
+            Slot::Occupied(_) => panic!("expected nothing"),
+        }
+
+        match h.entry_at(5).unwrap() {
+            Slot::Vacant(slot) => assert_eq!(0..10, slot.available()),
+            Slot::Occupied(_)  => panic!("expected nothing"),
+        }
+
+        match h.entry_at(500).unwrap() {
+            Slot::Vacant(slot) => assert!(slot.available().is_empty()),
+            Slot::Occupied(_)  => panic!("expected nothing"),
+        }
+
+        // The shortcuts
+        h.entry_at(20).unwrap().or_insert_with(5, || String::from("e")).unwrap().push('?');
+        assert_eq!("e?", h.get(24).unwrap().entry);
+        h.entry_at(24).unwrap().and_modify(|s| s.push('!')).or_insert_with(5, || String::from("f")).unwrap();
+        assert_eq!("e?!", h.get(20).unwrap().entry);
+        h.entry_at(0).unwrap().and_modify(|s| s.push('!')).or_insert_with(1, || String::from("g")).unwrap();
+        assert_eq!("g", h.get(0).unwrap().entry);
+
+        let e = h.entry_at(99).unwrap().or_insert_with(5, || String::from("h")).unwrap_err();
+        assert_eq!("h", e.entry.entry);
+
+        // Removing respects locks
+        h.lock_range(30..31);
+        match h.entry_at(35).unwrap() {
+            Slot::Occupied(slot) => assert!(slot.remove().is_none()),
+            Slot::Vacant(_)      => panic!("expected an entry"),
+        }
+        h.unlock_range(0..100);
+        match h.entry_at(35).unwrap() {
+            Slot::Occupied(slot) => assert_eq!("b", slot.remove().unwrap().entry),
+            Slot::Vacant(_)      => panic!("expected an entry"),
+        }
+        assert!(h.get(35).is_none());
+
+        h.set_read_only(true);
+        assert!(h.entry_at(0).is_none());
+    }
+}
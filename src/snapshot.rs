@@ -0,0 +1,272 @@
+//! Publish immutable snapshots of a `BumpyVector` to other threads.
+//!
+//! A `Publisher` owns a private working copy that the writer mutates freely.
+//! Calling `publish()` copies it into a new, immutable snapshot, and swaps
+//! that in for all `SnapshotReader`s. Readers get an `Arc` to the latest
+//! snapshot, which they can iterate for as long as they like; a writer
+//! publishing in the meantime doesn't affect them, and they never block the
+//! writer.
+//!
+//! The only shared state is the pointer to the current snapshot, and it's
+//! swapped atomically rather than behind a lock. Loading never waits for the
+//! writer - a reader just takes its own reference to whichever snapshot is
+//! current - so a UI thread never waits on an analysis thread. The writer,
+//! for its part, only waits for readers that are partway through taking a
+//! reference (a few instructions, never an iteration) before it lets go of
+//! the old snapshot.
+//!
+//! # Example
+//!
+//! ```
+//! use bumpy_vector::BumpyVector;
+//! use bumpy_vector::snapshot::Publisher;
+//!
+//! let mut publisher: Publisher<String> = Publisher::new(BumpyVector::new(100));
+//! let reader = publisher.reader();
+//!
+//! // Changes aren't visible until they're published
+//! publisher.vector_mut().insert((String::from("hello"), 0..10).into()).unwrap();
+//! assert_eq!(0, reader.load().len());
+//!
+//! publisher.publish();
+//! assert_eq!(1, reader.load().len());
+//! ```
+
+use std::fmt;
+use std::marker::PhantomData;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicPtr, AtomicUsize, Ordering};
+
+use crate::BumpyVector;
+
+/// The slot holding the current snapshot, shared by the publisher and every
+/// reader.
+///
+/// A reader can't just load the pointer and then take a reference, since
+/// the writer might drop the snapshot in between. Instead, readers count
+/// themselves in while they do it, and the writer waits for the count to
+/// drop to zero before dropping the snapshot it replaced. There are two
+/// counts, alternating with each swap, so a steady stream of new readers
+/// (which can only see the new snapshot) can't keep the writer waiting.
+struct Slot<T> {
+    /// The current snapshot, from `Arc::into_raw()`. The slot holds one
+    /// reference to it.
+    current: AtomicPtr<BumpyVector<T>>,
+
+    /// Goes up by one every time the snapshot is replaced.
+    epoch: AtomicUsize,
+
+    /// How many readers are taking a reference, by which epoch they started
+    /// in (odd or even).
+    loading: [AtomicUsize; 2],
+
+    /// The slot owns an `Arc<BumpyVector<T>>`, so it can only be shared
+    /// between threads when that can.
+    marker: PhantomData<Arc<BumpyVector<T>>>,
+}
+
+impl<T> Slot<T> {
+    fn new(snapshot: Arc<BumpyVector<T>>) -> Self {
+        Slot {
+            current: AtomicPtr::new(Arc::into_raw(snapshot) as *mut _),
+            epoch: AtomicUsize::new(0),
+            loading: [AtomicUsize::new(0), AtomicUsize::new(0)],
+            marker: PhantomData,
+        }
+    }
+
+    /// Take a reference to the current snapshot.
+    fn load(&self) -> Arc<BumpyVector<T>> {
+        // Count ourselves in for the current epoch. If it changes before
+        // we're counted, the writer might not wait for us, so try again
+        let epoch = loop {
+            let epoch = self.epoch.load(Ordering::SeqCst);
+            self.loading[epoch % 2].fetch_add(1, Ordering::SeqCst);
+
+            if self.epoch.load(Ordering::SeqCst) == epoch {
+                break epoch;
+            }
+
+            self.loading[epoch % 2].fetch_sub(1, Ordering::SeqCst);
+        };
+
+        let current = self.current.load(Ordering::SeqCst);
+
+        // SAFETY: `current` came from `Arc::into_raw()`, and the slot's
+        // reference to it isn't dropped until we're counted out below
+        let snapshot = unsafe {
+            Arc::increment_strong_count(current);
+            Arc::from_raw(current)
+        };
+
+        self.loading[epoch % 2].fetch_sub(1, Ordering::SeqCst);
+
+        snapshot
+    }
+
+    /// Replace the current snapshot.
+    ///
+    /// Only the publisher calls this, and it needs `&mut` to, so there's
+    /// never more than one at a time.
+    fn store(&self, snapshot: Arc<BumpyVector<T>>) {
+        let old = self.current.swap(Arc::into_raw(snapshot) as *mut _, Ordering::SeqCst);
+
+        // Anyone who starts loading from now on is counted in the other
+        // epoch, and can only see the new snapshot
+        let epoch = self.epoch.fetch_add(1, Ordering::SeqCst);
+        while self.loading[epoch % 2].load(Ordering::SeqCst) != 0 {
+            std::thread::yield_now();
+        }
+
+        // SAFETY: `old` came from `Arc::into_raw()`, and every reader that
+        // could have loaded it has taken its own reference by now
+        drop(unsafe { Arc::from_raw(old) });
+    }
+}
+
+impl<T> Drop for Slot<T> {
+    fn drop(&mut self) {
+        // SAFETY: nobody else can be loading, since this is the last handle
+        drop(unsafe { Arc::from_raw(*self.current.get_mut()) });
+    }
+}
+
+impl<T> fmt::Debug for Slot<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Slot(epoch {})", self.epoch.load(Ordering::SeqCst))
+    }
+}
+
+/// Owns the working copy, and publishes snapshots of it.
+#[derive(Debug)]
+pub struct Publisher<T> {
+    working: BumpyVector<T>,
+    current: Arc<Slot<T>>,
+}
+
+impl<T> Publisher<T>
+where
+    T: Clone
+{
+    /// Create a new publisher, with `vector` as both the working copy and
+    /// the first published snapshot.
+    pub fn new(vector: BumpyVector<T>) -> Self {
+        Publisher {
+            current: Arc::new(Slot::new(Arc::new(vector.clone()))),
+            working: vector,
+        }
+    }
+
+    /// Get a read-only reference to the (possibly unpublished) working copy.
+    pub fn vector(&self) -> &BumpyVector<T> {
+        &self.working
+    }
+
+    /// Get a mutable reference to the working copy.
+    ///
+    /// Nothing done here is visible to readers until `publish()` is called.
+    pub fn vector_mut(&mut self) -> &mut BumpyVector<T> {
+        &mut self.working
+    }
+
+    /// Make the current state of the working copy visible to readers.
+    pub fn publish(&mut self) {
+        self.current.store(Arc::new(self.working.clone()));
+    }
+
+    /// Create a new reader, which can be sent to other threads.
+    pub fn reader(&self) -> SnapshotReader<T> {
+        SnapshotReader {
+            current: Arc::clone(&self.current),
+        }
+    }
+}
+
+/// A handle for loading the latest published snapshot.
+#[derive(Debug)]
+pub struct SnapshotReader<T> {
+    current: Arc<Slot<T>>,
+}
+
+impl<T> Clone for SnapshotReader<T> {
+    fn clone(&self) -> Self {
+        SnapshotReader {
+            current: Arc::clone(&self.current),
+        }
+    }
+}
+
+impl<T> SnapshotReader<T> {
+    /// Get the most recently published snapshot.
+    ///
+    /// The snapshot never changes; call `load()` again to see newer ones.
+    /// This never takes a lock, or waits for the publisher.
+    pub fn load(&self) -> Arc<BumpyVector<T>> {
+        self.current.load()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::thread;
+
+    #[test]
+    fn test_publish() {
+        let mut v: BumpyVector<&str> = BumpyVector::new(10);
+        v.insert(("a", 0..2).into()).unwrap();
+
+        let mut publisher = Publisher::new(v);
+        let reader = publisher.reader();
+
+        // Take a snapshot, then change things
+        let old = reader.load();
+        publisher.vector_mut().insert(("b", 2..4).into()).unwrap();
+        publisher.publish();
+
+        // The old snapshot is unchanged, and the new one has both
+        assert_eq!(1, old.len());
+        assert_eq!(2, reader.load().len());
+        assert_eq!(2, publisher.vector().len());
+
+        // Old snapshots go away once nobody's using them
+        let current = reader.load();
+        assert_eq!(1, Arc::strong_count(&old));
+        assert_eq!(2, Arc::strong_count(&current));
+        drop(publisher);
+        assert_eq!(3, Arc::strong_count(&reader.load()));
+        drop(reader);
+        assert_eq!(1, Arc::strong_count(&current));
+    }
+
+    #[test]
+    fn test_readers_on_other_threads() {
+        let mut publisher: Publisher<usize> = Publisher::new(BumpyVector::new(1000));
+
+        let readers: Vec<_> = (0..4).map(|_| {
+            let reader = publisher.reader();
+
+            thread::spawn(move || {
+                let mut last = 0;
+
+                // Snapshots only ever grow, and are always internally consistent
+                while last < 100 {
+                    let snapshot = reader.load();
+                    assert!(snapshot.len() >= last);
+                    assert_eq!(snapshot.len(), snapshot.iter().count());
+                    last = snapshot.len();
+                }
+            })
+        }).collect();
+
+        for i in 0..100 {
+            publisher.vector_mut().insert((i, (i * 10)..(i * 10 + 10)).into()).unwrap();
+            publisher.publish();
+        }
+
+        for reader in readers {
+            reader.join().unwrap();
+        }
+    }
+}
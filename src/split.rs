@@ -0,0 +1,178 @@
+//! Split entries that cross a boundary.
+//!
+//! Exporting a vector page by page (or sector by sector) means every entry
+//! has to fit inside one page, or it gets cut off. `split_all_at()` breaks
+//! each entry that crosses a boundary into pieces that don't, using a
+//! function to work out what each piece's value should be.
+//!
+//! # Example
+//!
+//! ```
+//! use bumpy_vector::BumpyVector;
+//!
+//! let mut v: BumpyVector<String> = BumpyVector::new(0x3000);
+//! v.insert((String::from("table"), 0x0f00..0x2100).into()).unwrap();
+//!
+//! // Split on 4k pages
+//! v.split_all_at(&[0x1000, 0x2000], |e, piece| {
+//!     format!("{} (+{:#x})", e.entry, piece.start - e.range.start)
+//! }).unwrap();
+//!
+//! assert_eq!(3, v.len());
+//! assert_eq!("table (+0x100)", v.get(0x1000).unwrap().entry);
+//! assert_eq!(0x2000..0x2100, v.get(0x2000).unwrap().range);
+//! ```
+
+use std::collections::BTreeSet;
+use std::ops::Range;
+
+use crate::{BumpyEntry, BumpyError, BumpyResult, BumpyVector};
+
+impl<T> BumpyVector<T> {
+    /// Split every entry that crosses any of `boundaries`, so that no entry
+    /// starts before a boundary and ends after it.
+    ///
+    /// `splitter` is called with the original entry and the range of each
+    /// piece, in order, and returns the value for that piece. A boundary at
+    /// the very start or end of an entry doesn't split it, and the
+    /// boundaries don't need to be sorted.
+    ///
+    /// The first piece of a pinned entry stays pinned, since it hasn't
+    /// moved. Every split entry loses its type tag (which described the
+    /// whole thing) and its history, and pieces of temporary entries aren't
+    /// temporary.
+    ///
+    /// # Return
+    ///
+    /// Returns how many entries were split. If any entry that needs splitting
+    /// is locked, nothing changes and it's `Locked`; it's `ReadOnly` if the
+    /// vector is read-only.
+    pub fn split_all_at<F>(&mut self, boundaries: &[usize], mut splitter: F) -> BumpyResult<usize>
+    where
+        F: FnMut(&BumpyEntry<T>, &Range<usize>) -> T
+    {
+        if self.read_only {
+            return Err(BumpyError::ReadOnly);
+        }
+
+        let boundaries: BTreeSet<usize> = boundaries.iter().copied().collect();
+        let cuts = |e: &BumpyEntry<T>| -> Vec<usize> {
+            match e.range.len() {
+                0..=1 => Vec::new(),
+                _ => boundaries.range((e.range.start + 1)..e.range.end).copied().collect(),
+            }
+        };
+
+        // Find everything that needs to split before changing anything
+        let crossing: Vec<(usize, Vec<usize>)> = self.data.values()
+            .map(|e| (e.range.start, cuts(e)))
+            .filter(|(_, cuts)| !cuts.is_empty())
+            .collect();
+
+        if crossing.iter().any(|(start, _)| self.data.get(start).is_some_and(|e| self.is_locked(&e.range))) {
+            return Err(BumpyError::Locked);
+        }
+
+        // Unlink rather than remove, so no temporary entry expires (maybe
+        // one that's still waiting to be split) until everything's done
+        let mut split = Vec::with_capacity(crossing.len());
+        for (start, cuts) in crossing {
+            let pinned = self.pins.contains(&start);
+            let e = match self.unlink(start) {
+                Some(e) => e,
+                None => continue,
+            };
+
+            let mut piece_start = e.range.start;
+            let mut pieces: Vec<BumpyEntry<T>> = Vec::with_capacity(cuts.len() + 1);
+            for piece_end in cuts.into_iter().chain(std::iter::once(e.range.end)) {
+                let range = piece_start..piece_end;
+                pieces.push(BumpyEntry { entry: splitter(&e, &range), range });
+                piece_start = piece_end;
+            }
+
+            for piece in pieces {
+                self.link(piece);
+            }
+
+            if pinned {
+                self.pins.insert(start);
+            }
+
+            split.push(start);
+        }
+
+        // However many entries were split, it's one change
+        if !split.is_empty() {
+            self.expire_temporary_except(|s| split.binary_search(&s).is_ok());
+        }
+
+        Ok(split.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_split_all_at() {
+        let mut h: BumpyVector<String> = BumpyVector::new(20);
+        h.insert((String::from("a"), 0..3).into()).unwrap();
+        h.insert((String::from("b"), 3..12).into()).unwrap();
+        h.insert((String::from("c"), 12..16).into()).unwrap();
+        h.insert((String::from("d"), 18..19).into()).unwrap();
+        h.pin(3);
+
+        let split = |e: &BumpyEntry<String>, piece: &Range<usize>| format!("{}{}", e.entry, piece.start);
+
+        // Boundaries on the edges of entries don't split anything
+        assert_eq!(0, h.split_all_at(&[0, 3, 12, 16, 18, 19, 100], split).unwrap());
+        assert_eq!(4, h.len());
+
+        assert_eq!(2, h.split_all_at(&[8, 4, 14, 8, 18], split).unwrap());
+        let result: Vec<(&str, Range<usize>)> = h.iter().map(|e| (&e.entry[..], e.range.clone())).collect();
+        assert_eq!(vec![
+            ("a", 0..3),
+            ("b3", 3..4),
+            ("b4", 4..8),
+            ("b8", 8..12),
+            ("c12", 12..14),
+            ("c14", 14..16),
+            ("d", 18..19),
+        ], result);
+
+        // The first piece stays pinned
+        assert!(h.is_pinned(3));
+        assert!(!h.is_pinned(4));
+
+        // Locked entries can't be split
+        h.lock_range(1..2);
+        assert!(matches!(h.split_all_at(&[1, 10], split), Err(BumpyError::Locked)));
+        assert_eq!(7, h.len());
+        assert_eq!(1, h.split_all_at(&[10], split).unwrap());
+
+        h.set_read_only(true);
+        assert!(matches!(h.split_all_at(&[5], split), Err(BumpyError::ReadOnly)));
+    }
+
+    #[test]
+    fn test_split_all_at_temporary() {
+        // Splitting the first entry mustn't expire the second before it's
+        // split too
+        let mut h: BumpyVector<String> = BumpyVector::new(20);
+        h.insert_temporary((String::from("a"), 0..4).into(), 2).unwrap();
+        h.insert_temporary((String::from("b"), 4..8).into(), 1).unwrap();
+
+        let split = |e: &BumpyEntry<String>, piece: &Range<usize>| format!("{}{}", e.entry, piece.start);
+        assert_eq!(2, h.split_all_at(&[2, 6], split).unwrap());
+        assert_eq!(4, h.len());
+        assert_eq!("b6", h.get(6).unwrap().entry);
+
+        // And splitting is only one change
+        h.insert_temporary((String::from("c"), 10..14).into(), 2).unwrap();
+        assert_eq!(2, h.split_all_at(&[1, 5], split).unwrap());
+        assert!(h.is_temporary(10));
+    }
+}
@@ -0,0 +1,183 @@
+//! Asynchronous iteration over a `BumpyVector`.
+//!
+//! Only available with the 'futures' feature:
+//!
+//! ```toml
+//! bumpy_vector = { version = "~0.0.0", features = ["futures"] }
+//! ```
+//!
+//! Walking a vector with millions of entries takes a while. In an async
+//! server that would block the executor, so `stream_range()` returns a
+//! `futures::Stream` that gives up its time slice every so often. Gaps
+//! between entries are jumped over rather than scanned, so they cost
+//! nothing, however big they are.
+//!
+//! # Example
+//!
+//! ```
+//! use bumpy_vector::BumpyVector;
+//! use futures::executor::block_on;
+//! use futures::stream::StreamExt;
+//!
+//! let mut v: BumpyVector<&str> = BumpyVector::new(1_000_000);
+//! v.insert(("a", 0..10).into()).unwrap();
+//! v.insert(("b", 999_990..1_000_000).into()).unwrap();
+//!
+//! let entries: Vec<_> = block_on(v.stream_range(0..1_000_000).collect());
+//! assert_eq!(2, entries.len());
+//! ```
+
+use std::ops::Range;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use futures::stream::Stream;
+
+use crate::{BumpyEntry, BumpyVector};
+
+/// How many entries to produce before yielding to the executor.
+const YIELD_EVERY: usize = 4096;
+
+/// A `Stream` of the entries in a range; see `BumpyVector::stream_range()`.
+#[derive(Debug)]
+pub struct RangeStream<'a, T> {
+    vector: &'a BumpyVector<T>,
+
+    /// The next index to look at.
+    next: usize,
+
+    /// Where to stop.
+    end: usize,
+
+    /// How many entries have been produced since the last time we yielded.
+    produced: usize,
+}
+
+impl<'a, T> Stream for RangeStream<'a, T> {
+    type Item = &'a BumpyEntry<T>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        if this.next >= this.end {
+            return Poll::Ready(None);
+        }
+
+        // We've been busy - let somebody else run for a bit, and ask to be
+        // polled again right away
+        if this.produced >= YIELD_EVERY {
+            this.produced = 0;
+            cx.waker().wake_by_ref();
+            return Poll::Pending;
+        }
+
+        match this.vector.data.range(this.next..this.end).next() {
+            Some((_, e)) => {
+                // Jump over it, and whatever gap was before it
+                this.next = e.range.end;
+                this.produced += 1;
+
+                Poll::Ready(Some(e))
+            },
+            None => {
+                this.next = this.end;
+
+                Poll::Ready(None)
+            },
+        }
+    }
+}
+
+impl<T> BumpyVector<T> {
+    /// Return a `Stream` of the entries within the given range.
+    ///
+    /// The entries are the same ones `get_range()` would return, in the same
+    /// order, but they're produced lazily and the stream periodically yields
+    /// while there are a lot of them.
+    pub fn stream_range(&self, range: Range<usize>) -> RangeStream<'_, T> {
+        let next = match self.get_entry_start(range.start) {
+            Some(e) => e,
+            None    => range.start,
+        };
+
+        // Empty ranges don't contain anything
+        let end = if range.is_empty() {
+            next
+        } else {
+            std::cmp::min(range.end, self.max_size)
+        };
+
+        RangeStream {
+            vector: self,
+            next,
+            end,
+            produced: 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use futures::executor::block_on;
+    use futures::stream::StreamExt;
+    use futures::task::noop_waker_ref;
+
+    #[test]
+    fn test_stream_range() {
+        let mut h: BumpyVector<&str> = BumpyVector::new(10);
+        h.insert(("a", 1..3).into()).unwrap();
+        h.insert(("b", 3..4).into()).unwrap();
+        h.insert(("c", 6..9).into()).unwrap();
+
+        let result: Vec<&str> = block_on(h.stream_range(0..10).map(|e| e.entry).collect());
+        assert_eq!(vec!["a", "b", "c"], result);
+
+        let result: Vec<&str> = block_on(h.stream_range(2..7).map(|e| e.entry).collect());
+        assert_eq!(vec!["a", "b", "c"], result);
+
+        let result: Vec<&str> = block_on(h.stream_range(4..6).map(|e| e.entry).collect());
+        assert_eq!(0, result.len());
+
+        let result: Vec<&str> = block_on(h.stream_range(2..2).map(|e| e.entry).collect());
+        assert_eq!(0, result.len());
+    }
+
+    #[test]
+    fn test_stream_yields() {
+        // Poll by hand, counting how many entries it produced and how many
+        // times it gave up
+        let poll_all = |h: &BumpyVector<usize>| -> (usize, usize) {
+            let mut stream = h.stream_range(0..h.max_size());
+            let mut cx = Context::from_waker(noop_waker_ref());
+
+            let (mut produced, mut pending) = (0, 0);
+            loop {
+                match Pin::new(&mut stream).poll_next(&mut cx) {
+                    Poll::Pending => pending += 1,
+                    Poll::Ready(Some(e)) => {
+                        assert_eq!(produced, e.entry);
+                        produced += 1;
+                    },
+                    Poll::Ready(None) => break,
+                }
+            }
+
+            (produced, pending)
+        };
+
+        // Huge gaps don't need to yield at all
+        let mut h: BumpyVector<usize> = BumpyVector::new(usize::MAX);
+        h.insert((0, 10..20).into()).unwrap();
+        h.insert((1, (usize::MAX - 1)..usize::MAX).into()).unwrap();
+        assert_eq!((2, 0), poll_all(&h));
+
+        // Lots of entries do
+        let mut h: BumpyVector<usize> = BumpyVector::new(100_000);
+        for i in 0..10_000 {
+            h.insert((i, (i * 10)..(i * 10 + 1)).into()).unwrap();
+        }
+        assert_eq!((10_000, 10_000 / YIELD_EVERY), poll_all(&h));
+    }
+}
@@ -0,0 +1,121 @@
+//! Conveniences for vectors of strings.
+//!
+//! Strings are by far the most common thing to annotate a binary with, so
+//! this module adds searching to any `BumpyVector` whose values can be viewed
+//! as a `&str` - `String`, `&str`, and `Interned<String>` all work.
+//!
+//! # Example
+//!
+//! ```
+//! use bumpy_vector::BumpyVector;
+//! use bumpy_vector::strings::StringSearch;
+//!
+//! let mut v: BumpyVector<&str> = BumpyVector::new(100);
+//! v.insert(("Header", 0..4).into()).unwrap();
+//! v.insert(("Padding", 4..8).into()).unwrap();
+//! v.insert(("More padding", 8..16).into()).unwrap();
+//!
+//! assert_eq!(1, v.find_substring("padding").len());
+//! assert_eq!(2, v.find_substring_ignore_case("padding").len());
+//! assert_eq!(4..8, v.find_ignore_case("PADDING")[0].range);
+//! ```
+
+use std::ops::Range;
+
+use crate::{BumpyEntry, BumpyVector, InsertError};
+use crate::intern::{Interned, Interner};
+
+/// An `Interner` for strings.
+pub type StringInterner = Interner<String>;
+
+impl AsRef<str> for Interned<String> {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+/// Search functions for vectors of strings.
+///
+/// All results are returned in address order.
+pub trait StringSearch<S> {
+    /// Find the entries that contain `needle`.
+    fn find_substring(&self, needle: &str) -> Vec<&BumpyEntry<S>>;
+
+    /// Find the entries that contain `needle`, ignoring case.
+    fn find_substring_ignore_case(&self, needle: &str) -> Vec<&BumpyEntry<S>>;
+
+    /// Find the entries that are exactly `value`, ignoring case.
+    fn find_ignore_case(&self, value: &str) -> Vec<&BumpyEntry<S>>;
+}
+
+impl<S> StringSearch<S> for BumpyVector<S>
+where
+    S: AsRef<str>
+{
+    fn find_substring(&self, needle: &str) -> Vec<&BumpyEntry<S>> {
+        self.into_iter().filter(|e| e.entry.as_ref().contains(needle)).collect()
+    }
+
+    fn find_substring_ignore_case(&self, needle: &str) -> Vec<&BumpyEntry<S>> {
+        let needle = needle.to_lowercase();
+
+        self.into_iter().filter(|e| e.entry.as_ref().to_lowercase().contains(&needle)).collect()
+    }
+
+    fn find_ignore_case(&self, value: &str) -> Vec<&BumpyEntry<S>> {
+        let value = value.to_lowercase();
+
+        self.into_iter().filter(|e| e.entry.as_ref().to_lowercase() == value).collect()
+    }
+}
+
+impl BumpyVector<Interned<String>> {
+    /// Intern a copy of `value`, then insert it as a new entry covering
+    /// `range`.
+    ///
+    /// See `insert()` for return and errors.
+    pub fn insert_str(&mut self, interner: &mut StringInterner, value: &str, range: Range<usize>) -> Result<(), InsertError<Interned<String>>> {
+        self.insert_interned(interner, value.to_string(), range)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_string_search() {
+        let mut v: BumpyVector<String> = BumpyVector::new(100);
+        v.insert((String::from("abc"), 10..12).into()).unwrap();
+        v.insert((String::from("ABC"), 0..2).into()).unwrap();
+        v.insert((String::from("xyz"), 20..22).into()).unwrap();
+        v.insert((String::from("xabcx"), 30..32).into()).unwrap();
+
+        let found: Vec<Range<usize>> = v.find_substring("abc").into_iter().map(|e| e.range.clone()).collect();
+        assert_eq!(vec![10..12, 30..32], found);
+
+        let found: Vec<Range<usize>> = v.find_substring_ignore_case("aBc").into_iter().map(|e| e.range.clone()).collect();
+        assert_eq!(vec![0..2, 10..12, 30..32], found);
+
+        let found: Vec<Range<usize>> = v.find_ignore_case("abc").into_iter().map(|e| e.range.clone()).collect();
+        assert_eq!(vec![0..2, 10..12], found);
+
+        assert_eq!(0, v.find_substring("nope").len());
+    }
+
+    #[test]
+    fn test_interned_strings() {
+        let mut interner = StringInterner::new();
+        let mut v: BumpyVector<Interned<String>> = BumpyVector::new(100);
+
+        v.insert_str(&mut interner, "padding", 0..4).unwrap();
+        v.insert_str(&mut interner, "padding", 4..8).unwrap();
+        v.insert_str(&mut interner, "header", 8..10).unwrap();
+        assert_eq!("header", v.insert_str(&mut interner, "header", 9..11).unwrap_err().entry.entry.as_str());
+
+        assert_eq!(2, interner.len());
+        assert_eq!(2, v.find_substring("pad").len());
+        assert_eq!(1, v.find_ignore_case("HEADER").len());
+    }
+}
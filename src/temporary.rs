@@ -0,0 +1,243 @@
+//! Entries that remove themselves.
+//!
+//! Some entries are only meant to be around for a moment, like the regions
+//! highlighted by a search. Inserting them with `insert_temporary()` means
+//! they go away on their own after a number of changes to the vector, or all
+//! at once with `sweep()`, instead of having to be tracked down and removed.
+//!
+//! Temporary entries are never serialized, so they can't end up in a saved
+//! file by accident.
+//!
+//! # Example
+//!
+//! ```
+//! use bumpy_vector::BumpyVector;
+//!
+//! let mut v: BumpyVector<&str> = BumpyVector::new(100);
+//! v.insert_temporary(("match", 10..20).into(), 2).unwrap();
+//!
+//! v.insert(("header", 0..10).into()).unwrap();
+//! assert!(v.get(10).is_some());
+//!
+//! // The second change after it was inserted removes it
+//! v.insert(("footer", 90..100).into()).unwrap();
+//! assert!(v.get(10).is_none());
+//! ```
+
+use std::collections::BTreeMap;
+
+use crate::{BumpyEntry, BumpyVector, InsertError};
+
+/// The per-vector state.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct TemporaryEntries {
+    /// Goes up by one every change.
+    clock: u64,
+
+    /// When each temporary entry expires, indexed by where it starts.
+    expires: BTreeMap<usize, u64>,
+}
+
+impl TemporaryEntries {
+    /// Returns `true` if the entry at `start` is temporary.
+    pub(crate) fn contains(&self, start: usize) -> bool {
+        self.expires.contains_key(&start)
+    }
+
+    /// Returns when the entry at `start` expires, if it's temporary.
+    pub(crate) fn expiry(&self, start: usize) -> Option<u64> {
+        self.expires.get(&start).copied()
+    }
+
+    /// Make the entry at `start` temporary again, expiring when it did
+    /// before.
+    pub(crate) fn set_expiry(&mut self, start: usize, expires: u64) {
+        self.expires.insert(start, expires);
+    }
+
+    /// Called when the entry at `start` is removed.
+    pub(crate) fn on_remove(&mut self, start: usize) {
+        self.expires.remove(&start);
+    }
+
+    /// Called when every entry is removed.
+    pub(crate) fn on_clear(&mut self) {
+        self.expires.clear();
+    }
+}
+
+impl<T> BumpyVector<T> {
+    /// Insert an entry that's removed automatically by the `ttl`th change to
+    /// the vector after this one (a `ttl` of 0 counts as 1).
+    ///
+    /// Every insert, remove, and mutable lookup counts as a change. Changing
+    /// many entries at once, like `iter_mut()` or `apply_offset_map()` do,
+    /// counts as one, and a temporary entry that's moved stays temporary. A
+    /// temporary entry that's locked when it expires stays until the first
+    /// change after it's unlocked.
+    ///
+    /// # Return
+    ///
+    /// See `insert()`.
+    pub fn insert_temporary(&mut self, entry: BumpyEntry<T>, ttl: u64) -> Result<(), InsertError<T>> {
+        let start = entry.range.start;
+        self.insert(entry)?;
+
+        self.temporary.expires.insert(start, self.temporary.clock + ttl);
+
+        Ok(())
+    }
+
+    /// Returns `true` if there's an entry at `index` and it's temporary.
+    pub fn is_temporary(&self, index: usize) -> bool {
+        self.get_entry_start(index).is_some_and(|start| self.temporary.contains(start))
+    }
+
+    /// Remove every temporary entry now, whether or not it's expired.
+    ///
+    /// # Return
+    ///
+    /// Returns the removed entries, in order. Locked entries are kept, and
+    /// nothing is removed from a read-only vector.
+    pub fn sweep(&mut self) -> Vec<BumpyEntry<T>> {
+        if self.read_only {
+            return vec![];
+        }
+
+        let starts: Vec<usize> = self.temporary.expires.keys().copied().collect();
+
+        starts.into_iter().filter_map(|start| self.remove_exact(start)).collect()
+    }
+
+    /// Count a change to the entry at `start`, and remove any other temporary
+    /// entries that have expired.
+    pub(crate) fn expire_temporary(&mut self, start: usize) {
+        self.expire_temporary_except(|s| s == start);
+    }
+
+    /// Count one change, and remove any temporary entries that have expired
+    /// except the ones `changed` returns `true` for (by where they start).
+    ///
+    /// Anything that changes a lot of entries at once counts as a single
+    /// change, with this called once at the end.
+    pub(crate) fn expire_temporary_except<F>(&mut self, changed: F)
+    where
+        F: Fn(usize) -> bool
+    {
+        self.temporary.clock += 1;
+
+        if self.temporary.expires.is_empty() {
+            return;
+        }
+
+        let clock = self.temporary.clock;
+        let expired: Vec<usize> = self.temporary.expires.iter()
+            .filter(|(s, expires)| !changed(**s) && **expires <= clock)
+            .map(|(s, _)| *s)
+            .collect();
+
+        for s in expired {
+            if !self.data.get(&s).is_some_and(|e| self.is_locked(&e.range)) {
+                self.unlink(s);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use crate::offsets::OffsetMap;
+
+    #[test]
+    fn test_temporary() {
+        let mut h: BumpyVector<&str> = BumpyVector::new(100);
+        h.insert_temporary(("a", 0..10).into(), 1).unwrap();
+        h.insert_temporary(("b", 10..20).into(), 3).unwrap();
+        assert!(h.insert_temporary(("error", 15..25).into(), 3).is_err());
+
+        // "a" goes with the next change, which was inserting "b"
+        assert!(h.get(0).is_none());
+        assert!(h.is_temporary(15));
+
+        // Changing "b" counts, but doesn't remove it
+        h.get_mut(15).unwrap();
+        h.insert(("c", 50..60).into()).unwrap();
+        assert!(h.get(15).is_some());
+        assert!(!h.is_temporary(50));
+
+        h.remove(50);
+        assert!(h.get(15).is_none());
+
+        // Locked entries wait
+        h.insert_temporary(("d", 20..30).into(), 0).unwrap();
+        h.lock_range(20..21);
+        h.insert(("e", 80..90).into()).unwrap();
+        assert!(h.is_temporary(20));
+        h.unlock_range(0..100);
+        h.insert(("f", 90..100).into()).unwrap();
+        assert!(h.get(20).is_none());
+
+        // Sweeping gets rid of everything temporary
+        h.insert_temporary(("g", 0..10).into(), 100).unwrap();
+        h.insert_temporary(("h", 30..40).into(), 100).unwrap();
+        let swept: Vec<&str> = h.sweep().iter().map(|e| e.entry).collect();
+        assert_eq!(vec!["g", "h"], swept);
+        assert_eq!(vec!["e", "f"], h.iter().map(|e| e.entry).collect::<Vec<_>>());
+
+        // Removing a temporary entry forgets that it was temporary
+        h.insert_temporary(("i", 0..10).into(), 100).unwrap();
+        h.remove(0);
+        h.insert(("j", 0..10).into()).unwrap();
+        assert!(!h.is_temporary(0));
+        assert_eq!(0, h.sweep().len());
+    }
+
+    #[test]
+    fn test_temporary_batch() {
+        let mut h: BumpyVector<u32> = BumpyVector::new(100);
+        for i in 10..40 {
+            h.insert((i as u32, i..(i + 1)).into()).unwrap();
+        }
+        h.insert_temporary((100, 0..2).into(), 4).unwrap();
+        h.insert_temporary((200, 45..46).into(), 100).unwrap();
+
+        // Each of these is one change, however many entries it touches
+        assert_eq!(32, h.iter_mut().count());
+        h.normalize_values(|value, _, _| *value += 1);
+        assert!(h.is_temporary(0));
+
+        // Moving a temporary entry keeps it temporary
+        h.relocate_many(&[(0, 2), (10, 50)]).unwrap();
+        assert!(h.is_temporary(2));
+        assert!(h.get(0).is_none());
+
+        // Everything moves here, and the entries that don't fit come back
+        // even if they're temporary
+        let dropped = h.apply_offset_map(&OffsetMap::new(std::slice::from_ref(&(45..46)))).unwrap();
+        assert_eq!(vec![201], dropped.iter().map(|e| e.entry).collect::<Vec<_>>());
+        assert!(h.is_temporary(2));
+        assert_eq!(31, h.len());
+
+        // That was the fourth change, but it moved the entry, so it waits
+        // for the next one
+        h.insert((0, 90..91).into()).unwrap();
+        assert!(h.get(2).is_none());
+        assert_eq!(31, h.len());
+    }
+
+    #[cfg(feature = "serialize")]
+    #[test]
+    fn test_temporary_not_serialized() {
+        let mut h: BumpyVector<String> = BumpyVector::new(100);
+        h.insert((String::from("a"), 0..10).into()).unwrap();
+        h.insert_temporary((String::from("b"), 10..20).into(), 100).unwrap();
+
+        let serialized = ron::ser::to_string(&h).unwrap();
+        let h: BumpyVector<String> = ron::de::from_str(&serialized).unwrap();
+
+        assert_eq!(1, h.len());
+        assert_eq!("a", h.get(0).unwrap().entry);
+    }
+}
@@ -0,0 +1,521 @@
+//! Model-based conformance testing.
+//!
+//! Only available with the 'testing' feature:
+//!
+//! ```toml
+//! bumpy_vector = { version = "~0.0.0", features = ["testing"] }
+//! ```
+//!
+//! This module contains a deliberately naive `ReferenceModel` that stores one
+//! slot per index, plus an `Executor` that throws random sequences of
+//! `Operation`s at both the model and a `BumpyVector` (or any other
+//! `Backend`) and complains as soon as they disagree. It's used by this
+//! crate's own tests, and is public so forks and alternative backends can
+//! run the same conformance suite.
+//!
+//! It also has `BumpyVector::random()`, which builds a reproducible random
+//! layout to use as a fixture in benchmarks.
+//!
+//! # Example
+//!
+//! ```
+//! use bumpy_vector::testing::Executor;
+//!
+//! // Run 1000 random operations against a 64-byte vector
+//! let mut executor: Executor<u64> = Executor::new(64, 1234);
+//! assert!(executor.run(1000).is_ok());
+//! ```
+
+use std::fmt::Debug;
+use std::ops::Range;
+
+use crate::{BumpyEntry, BumpyError, BumpyResult, BumpyVector};
+
+/// A tiny, deterministic pseudo-random number generator (xorshift64*).
+///
+/// This isn't suitable for anything but generating test data, but it means
+/// test runs are reproducible from a single seed without pulling in a
+/// dependency.
+#[derive(Debug, Clone)]
+pub struct TestRng {
+    state: u64,
+}
+
+impl TestRng {
+    /// Create a new generator. Any seed is valid, including 0.
+    pub fn new(seed: u64) -> Self {
+        TestRng {
+            // xorshift gets stuck on 0, so mix the seed with a constant
+            state: seed ^ 0x9e37_79b9_7f4a_7c15,
+        }
+    }
+
+    /// Get the next random `u64`.
+    pub fn next_u64(&mut self) -> u64 {
+        if self.state == 0 {
+            self.state = 0x9e37_79b9_7f4a_7c15;
+        }
+
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+
+        self.state.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    /// Get a random number from `0` to `n - 1`. If `n` is `0`, returns `0`.
+    pub fn below(&mut self, n: usize) -> usize {
+        if n == 0 {
+            return 0;
+        }
+
+        (self.next_u64() % (n as u64)) as usize
+    }
+}
+
+/// How big the entries in a random layout are.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SizeDistribution {
+    /// Every entry is the same size.
+    Fixed(usize),
+
+    /// Sizes are spread evenly over a range.
+    Uniform(Range<usize>),
+
+    /// Sizes are spread evenly over the powers of two in a range, so there
+    /// are lots of small entries and the occasional big one - which is what
+    /// the annotations on a real binary tend to look like.
+    Skewed(Range<usize>),
+}
+
+impl SizeDistribution {
+    /// Pick a size. Sizes are always at least 1.
+    fn sample(&self, rng: &mut TestRng) -> usize {
+        let size = match self {
+            SizeDistribution::Fixed(size) => *size,
+            SizeDistribution::Uniform(range) => range.start + rng.below(range.len()),
+            SizeDistribution::Skewed(range) if range.is_empty() => range.start,
+            SizeDistribution::Skewed(range) => {
+                let log2 = |n: usize| (usize::BITS - 1 - n.max(1).leading_zeros()) as usize;
+
+                let bits = log2(range.start) + rng.below(log2(range.end - 1) - log2(range.start) + 1);
+                let low = std::cmp::max(range.start, 1 << bits);
+                let high = std::cmp::min(range.end, (1usize << bits).saturating_mul(2));
+
+                low + rng.below(high - low)
+            },
+        };
+
+        std::cmp::max(size, 1)
+    }
+}
+
+/// The shape of a random layout; see `BumpyVector::random_with()`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RandomLayout {
+    /// How many entries to generate.
+    pub entries: usize,
+
+    /// The most of the vector that entries can cover, from `0.0` to `1.0`.
+    /// Once an entry would go over, no more are generated.
+    pub density: f64,
+
+    /// How big each entry is.
+    pub sizes: SizeDistribution,
+}
+
+impl<T> BumpyVector<T>
+where
+    T: From<u64>
+{
+    /// Create a vector of `max_size` with about `entries` randomly placed
+    /// entries, covering about half of it.
+    ///
+    /// The same arguments always give the same layout. Values are random
+    /// `u64`s converted to `T`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bumpy_vector::BumpyVector;
+    ///
+    /// let v: BumpyVector<u64> = BumpyVector::random(10_000, 100, 1234);
+    /// assert_eq!(100, v.len());
+    ///
+    /// // Every time
+    /// let w: BumpyVector<u64> = BumpyVector::random(10_000, 100, 1234);
+    /// assert!(v.iter().zip(w.iter()).all(|(a, b)| a.range == b.range && a.entry == b.entry));
+    /// ```
+    pub fn random(max_size: usize, entries: usize, seed: u64) -> Self {
+        let largest = std::cmp::max(max_size / std::cmp::max(entries, 1), 1);
+
+        Self::random_with(max_size, seed, &RandomLayout {
+            entries,
+            density: 1.0,
+            sizes: SizeDistribution::Uniform(1..(largest + 1)),
+        })
+    }
+
+    /// Create a vector of `max_size` with a random layout of the given
+    /// shape.
+    ///
+    /// The entries are placed in order, with the space they don't cover
+    /// spread randomly between them. There can be fewer entries than asked
+    /// for if they don't all fit within `layout.density`.
+    pub fn random_with(max_size: usize, seed: u64, layout: &RandomLayout) -> Self {
+        let mut rng = TestRng::new(seed);
+        let budget = (max_size as f64 * layout.density.clamp(0.0, 1.0)) as usize;
+
+        let mut sizes = Vec::with_capacity(layout.entries);
+        let mut covered = 0;
+        for _ in 0..layout.entries {
+            let size = layout.sizes.sample(&mut rng);
+            if covered + size > budget {
+                break;
+            }
+
+            covered += size;
+            sizes.push(size);
+        }
+
+        // Cut the rest of the space into one gap before each entry, plus one
+        // at the end
+        let free = max_size - covered;
+        let mut cuts: Vec<usize> = (0..sizes.len()).map(|_| rng.below(free + 1)).collect();
+        cuts.sort_unstable();
+
+        let mut v = BumpyVector::new(max_size);
+        let mut previous_cut = 0;
+        let mut start = 0;
+        for (size, cut) in sizes.into_iter().zip(cuts) {
+            start += cut - previous_cut;
+            previous_cut = cut;
+
+            v.insert_unchecked((T::from(rng.next_u64()), start..(start + size)).into());
+            start += size;
+        }
+
+        v
+    }
+}
+
+/// A single operation that can be applied to a `Backend`.
+#[derive(Debug, Clone)]
+pub enum Operation<T> {
+    Insert(BumpyEntry<T>),
+    Remove(usize),
+    RemoveRange(Range<usize>),
+    Get(usize),
+    GetRange(Range<usize>),
+    Len,
+}
+
+impl<T> Operation<T> {
+    /// Generate a random operation that makes sense for a vector of the
+    /// given `max_size`.
+    ///
+    /// Ranges occasionally extend past `max_size` (and are occasionally
+    /// empty), so error handling gets exercised too.
+    pub fn random(rng: &mut TestRng, max_size: usize, value: &mut impl FnMut(&mut TestRng) -> T) -> Self {
+        // Allow indexes slightly out of bounds
+        let limit = max_size + 2;
+
+        let start = rng.below(limit);
+        let end = start + rng.below(limit / 4 + 1);
+
+        match rng.below(10) {
+            0..=3 => Operation::Insert((value(rng), start..end).into()),
+            4..=5 => Operation::Remove(start),
+            6     => Operation::RemoveRange(start..end),
+            7     => Operation::Get(start),
+            8     => Operation::GetRange(start..end),
+            _     => Operation::Len,
+        }
+    }
+}
+
+/// An implementation of the core `BumpyVector` operations.
+///
+/// Results are reported in the simplest possible terms (`(range, value)`
+/// pairs, and `bool` for success) so that different implementations can be
+/// compared regardless of their error types.
+pub trait Backend<T> {
+    fn insert(&mut self, entry: BumpyEntry<T>) -> bool;
+    fn remove(&mut self, index: usize) -> Option<(Range<usize>, T)>;
+    fn remove_range(&mut self, range: Range<usize>) -> Vec<(Range<usize>, T)>;
+    fn get(&self, index: usize) -> Option<(Range<usize>, &T)>;
+    fn get_range(&self, range: Range<usize>) -> Vec<(Range<usize>, &T)>;
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<T> Backend<T> for BumpyVector<T> {
+    fn insert(&mut self, entry: BumpyEntry<T>) -> bool {
+        BumpyVector::insert(self, entry).is_ok()
+    }
+
+    fn remove(&mut self, index: usize) -> Option<(Range<usize>, T)> {
+        BumpyVector::remove(self, index).map(|e| (e.range, e.entry))
+    }
+
+    fn remove_range(&mut self, range: Range<usize>) -> Vec<(Range<usize>, T)> {
+        BumpyVector::remove_range(self, range).into_iter().map(|e| (e.range, e.entry)).collect()
+    }
+
+    fn get(&self, index: usize) -> Option<(Range<usize>, &T)> {
+        BumpyVector::get(self, index).map(|e| (e.range.clone(), &e.entry))
+    }
+
+    fn get_range(&self, range: Range<usize>) -> Vec<(Range<usize>, &T)> {
+        BumpyVector::get_range(self, range).into_iter().map(|e| (e.range.clone(), &e.entry)).collect()
+    }
+
+    fn len(&self) -> usize {
+        BumpyVector::len(self)
+    }
+}
+
+/// The simplest implementation that could possibly work.
+///
+/// Every index has a slot that is either empty, or holds the start of the
+/// entry that covers it. Every operation is a linear scan. It's slow, but
+/// it's easy to convince yourself that it's correct.
+#[derive(Debug, Clone)]
+pub struct ReferenceModel<T> {
+    /// For each index, where the entry covering it starts.
+    owners: Vec<Option<usize>>,
+
+    /// For each index, the entry that starts there.
+    entries: Vec<Option<BumpyEntry<T>>>,
+}
+
+impl<T> ReferenceModel<T> {
+    /// Create an empty model with the given `max_size`.
+    pub fn new(max_size: usize) -> Self {
+        ReferenceModel {
+            owners: vec![None; max_size],
+            entries: (0..max_size).map(|_| None).collect(),
+        }
+    }
+}
+
+impl<T> Backend<T> for ReferenceModel<T> {
+    fn insert(&mut self, entry: BumpyEntry<T>) -> bool {
+        if entry.range.is_empty() || entry.range.end > self.owners.len() {
+            return false;
+        }
+
+        if entry.range.clone().any(|i| self.owners[i].is_some()) {
+            return false;
+        }
+
+        for i in entry.range.clone() {
+            self.owners[i] = Some(entry.range.start);
+        }
+
+        let start = entry.range.start;
+        self.entries[start] = Some(entry);
+
+        true
+    }
+
+    fn remove(&mut self, index: usize) -> Option<(Range<usize>, T)> {
+        let start = (*self.owners.get(index)?)?;
+        let entry = self.entries[start].take()?;
+
+        for i in entry.range.clone() {
+            self.owners[i] = None;
+        }
+
+        Some((entry.range, entry.entry))
+    }
+
+    fn remove_range(&mut self, range: Range<usize>) -> Vec<(Range<usize>, T)> {
+        range.filter_map(|i| Backend::remove(self, i)).collect()
+    }
+
+    fn get(&self, index: usize) -> Option<(Range<usize>, &T)> {
+        let start = (*self.owners.get(index)?)?;
+
+        self.entries[start].as_ref().map(|e| (e.range.clone(), &e.entry))
+    }
+
+    fn get_range(&self, range: Range<usize>) -> Vec<(Range<usize>, &T)> {
+        let mut result: Vec<(Range<usize>, &T)> = Vec::new();
+
+        for i in range {
+            if let Some(found) = Backend::get(self, i) {
+                if result.last().map(|(r, _)| r) != Some(&found.0) {
+                    result.push(found);
+                }
+            }
+        }
+
+        result
+    }
+
+    fn len(&self) -> usize {
+        self.entries.iter().filter(|e| e.is_some()).count()
+    }
+}
+
+/// Apply the same operation to two backends, and make sure they agree.
+pub fn check_operation<T, A, B>(a: &mut A, b: &mut B, operation: Operation<T>) -> BumpyResult<()>
+where
+    T: Clone + PartialEq + Debug,
+    A: Backend<T>,
+    B: Backend<T>,
+{
+    let description = format!("{:?}", operation);
+
+    let agree = match operation {
+        Operation::Insert(entry) => a.insert(entry.clone()) == b.insert(entry),
+        Operation::Remove(index) => a.remove(index) == b.remove(index),
+        Operation::RemoveRange(range) => a.remove_range(range.clone()) == b.remove_range(range),
+        Operation::Get(index) => a.get(index) == b.get(index),
+        Operation::GetRange(range) => a.get_range(range.clone()) == b.get_range(range),
+        Operation::Len => a.len() == b.len(),
+    };
+
+    if !agree {
+        return Err(BumpyError::Other(format!("Backends disagree on operation: {}", description)));
+    }
+
+    Ok(())
+}
+
+/// Runs random operations against a `BumpyVector` and a `ReferenceModel`.
+///
+/// Use `check_operation()` directly to compare other backends.
+#[derive(Debug, Clone)]
+pub struct Executor<T> {
+    rng: TestRng,
+    max_size: usize,
+    vector: BumpyVector<T>,
+    model: ReferenceModel<T>,
+}
+
+impl<T> Executor<T>
+where
+    T: Clone + PartialEq + Debug + From<u64>,
+{
+    /// Create an executor for a vector of `max_size`, seeded with `seed`.
+    ///
+    /// Values are random `u64`s converted to `T`.
+    pub fn new(max_size: usize, seed: u64) -> Self {
+        Executor {
+            rng: TestRng::new(seed),
+            max_size,
+            vector: BumpyVector::new(max_size),
+            model: ReferenceModel::new(max_size),
+        }
+    }
+
+    /// Run `steps` random operations.
+    ///
+    /// # Return
+    ///
+    /// Returns `Ok(())` if both backends agreed every step of the way, or an
+    /// error describing the first operation they disagreed on.
+    pub fn run(&mut self, steps: usize) -> BumpyResult<()> {
+        let mut value = |rng: &mut TestRng| T::from(rng.next_u64() % 8);
+
+        for _ in 0..steps {
+            let operation = Operation::random(&mut self.rng, self.max_size, &mut value);
+            check_operation(&mut self.vector, &mut self.model, operation)?;
+        }
+
+        // Finish with a full comparison
+        check_operation(&mut self.vector, &mut self.model, Operation::GetRange(0..self.max_size))
+    }
+
+    /// The `BumpyVector` under test.
+    pub fn vector(&self) -> &BumpyVector<T> {
+        &self.vector
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_model_agrees_with_vector() {
+        for seed in 0..20 {
+            let mut executor: Executor<u64> = Executor::new(50, seed);
+            executor.run(2000).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_disagreement_is_reported() {
+        let mut v: BumpyVector<u64> = BumpyVector::new(10);
+        let mut m: ReferenceModel<u64> = ReferenceModel::new(10);
+
+        // Put something in only one of them
+        v.insert((1, 0..2).into()).unwrap();
+
+        assert!(check_operation(&mut v, &mut m, Operation::Get(5)).is_ok());
+        assert!(check_operation(&mut v, &mut m, Operation::Get(1)).is_err());
+        assert!(check_operation(&mut v, &mut m, Operation::Len).is_err());
+    }
+
+    #[test]
+    fn test_rng_is_deterministic() {
+        let mut a = TestRng::new(42);
+        let mut b = TestRng::new(42);
+
+        for _ in 0..100 {
+            assert_eq!(a.next_u64(), b.next_u64());
+        }
+
+        assert!(TestRng::new(0).below(10) < 10);
+    }
+
+    #[test]
+    fn test_random_layout() {
+        for seed in 0..10 {
+            let v: BumpyVector<u64> = BumpyVector::random(1000, 50, seed);
+            assert_eq!(50, v.len());
+
+            // Everything fits, and lines up with the reference model
+            let mut m: ReferenceModel<u64> = ReferenceModel::new(1000);
+            for e in v.iter() {
+                assert!(m.insert(e.clone()));
+            }
+            let mut v = v;
+            check_operation(&mut v, &mut m, Operation::GetRange(0..1000)).unwrap();
+        }
+
+        let a: BumpyVector<u64> = BumpyVector::random(1000, 50, 1);
+        let b: BumpyVector<u64> = BumpyVector::random(1000, 50, 2);
+        assert!(a.iter().zip(b.iter()).any(|(a, b)| a.range != b.range));
+
+        // Density caps how much gets covered
+        let layout = RandomLayout {
+            entries: 1000,
+            density: 0.25,
+            sizes: SizeDistribution::Fixed(10),
+        };
+        let v: BumpyVector<u64> = BumpyVector::random_with(1000, 1, &layout);
+        assert_eq!(25, v.len());
+        assert!(v.iter().all(|e| e.range.len() == 10));
+
+        let layout = RandomLayout {
+            entries: 200,
+            density: 1.0,
+            sizes: SizeDistribution::Skewed(1..1000),
+        };
+        let v: BumpyVector<u64> = BumpyVector::random_with(100_000, 1, &layout);
+        assert_eq!(200, v.len());
+        assert!(v.iter().all(|e| e.range.len() < 1000));
+        assert!(v.iter().filter(|e| e.range.len() < 32).count() > v.iter().filter(|e| e.range.len() >= 512).count());
+
+        // Nothing fits in nothing
+        assert_eq!(0, BumpyVector::<u64>::random(0, 10, 1).len());
+        assert_eq!(0, BumpyVector::<u64>::random(100, 0, 1).len());
+    }
+}
@@ -0,0 +1,284 @@
+//! Tag entries with what type of thing they are.
+//!
+//! Analysis tools usually want to work with one kind of entry at a time -
+//! show all the strings, or throw away every function that was found
+//! automatically. Rather than inspecting every value to find out what it
+//! is, an entry can be tagged with a small number when it's inserted, and
+//! the vector keeps an index of which entries have which tag.
+//!
+//! What the numbers mean is up to the caller. Tags are serialized, and
+//! forgotten when their entry is removed.
+//!
+//! The vector also keeps a running total of how much of it each tag covers,
+//! so `coverage_by_type()` can break down what a file is made of without
+//! looking at every entry.
+//!
+//! # Example
+//!
+//! ```
+//! use bumpy_vector::BumpyVector;
+//! use bumpy_vector::types::TypeTag;
+//!
+//! const STRING: TypeTag = 1;
+//! const FUNCTION: TypeTag = 2;
+//!
+//! let mut v: BumpyVector<&str> = BumpyVector::new(100);
+//! v.insert_typed(("hello", 0..6).into(), STRING).unwrap();
+//! v.insert_typed(("main", 10..40).into(), FUNCTION).unwrap();
+//! v.insert_typed(("world", 50..56).into(), STRING).unwrap();
+//!
+//! assert_eq!(2, v.count_of_type(STRING));
+//! assert_eq!(vec!["hello", "world"], v.iter_of_type(STRING).map(|e| e.entry).collect::<Vec<_>>());
+//!
+//! v.remove_of_type(FUNCTION);
+//! assert_eq!(2, v.len());
+//! ```
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use crate::{BumpyEntry, BumpyVector, InsertError};
+
+/// Identifies a type of entry; see the module docs.
+pub type TypeTag = u32;
+
+/// The per-vector index of tags.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct TypeTags {
+    /// The tag of each tagged entry, indexed by where it starts.
+    tags: BTreeMap<usize, TypeTag>,
+
+    /// Where each entry with each tag starts.
+    by_tag: HashMap<TypeTag, BTreeSet<usize>>,
+
+    /// How many indexes the entries with each tag cover.
+    coverage: HashMap<TypeTag, usize>,
+}
+
+impl TypeTags {
+    /// Returns the tag of the entry at `start`.
+    pub(crate) fn get(&self, start: usize) -> Option<TypeTag> {
+        self.tags.get(&start).copied()
+    }
+
+    /// Returns every tag, indexed by where its entry starts.
+    #[cfg(feature = "serialize")]
+    pub(crate) fn tags(&self) -> &BTreeMap<usize, TypeTag> {
+        &self.tags
+    }
+
+    /// Tag the `size`-long entry at `start`, replacing any tag it already
+    /// has.
+    pub(crate) fn set(&mut self, start: usize, size: usize, tag: TypeTag) {
+        self.on_remove(start, size);
+
+        self.tags.insert(start, tag);
+        self.by_tag.entry(tag).or_default().insert(start);
+        *self.coverage.entry(tag).or_default() += size;
+    }
+
+    /// Called when the `size`-long entry at `start` is removed.
+    pub(crate) fn on_remove(&mut self, start: usize, size: usize) {
+        if let Some(tag) = self.tags.remove(&start) {
+            if let Some(starts) = self.by_tag.get_mut(&tag) {
+                starts.remove(&start);
+
+                if starts.is_empty() {
+                    self.by_tag.remove(&tag);
+                    self.coverage.remove(&tag);
+                } else if let Some(coverage) = self.coverage.get_mut(&tag) {
+                    *coverage -= size;
+                }
+            }
+        }
+    }
+
+    /// Called when every entry is removed.
+    pub(crate) fn on_clear(&mut self) {
+        self.tags.clear();
+        self.by_tag.clear();
+        self.coverage.clear();
+    }
+}
+
+impl<T> BumpyVector<T> {
+    /// Insert an entry, like `insert()`, and tag it with `tag`.
+    pub fn insert_typed(&mut self, entry: BumpyEntry<T>, tag: TypeTag) -> Result<(), InsertError<T>> {
+        let range = entry.range.clone();
+        self.insert(entry)?;
+
+        self.types.set(range.start, range.len(), tag);
+
+        Ok(())
+    }
+
+    /// Tag the entry at `index` with `tag`, replacing any tag it already had.
+    ///
+    /// Note that the entry doesn't necessarily need to *start* at `index`,
+    /// just overlap it.
+    ///
+    /// Changing a tag counts as a change to the entry, like getting a mutable
+    /// reference to it does (see `generation()`).
+    ///
+    /// # Return
+    ///
+    /// Returns `false` if there's no entry there, or the vector is
+    /// read-only.
+    pub fn set_type(&mut self, index: usize, tag: TypeTag) -> bool {
+        if self.read_only {
+            return false;
+        }
+
+        let range = match self.get_entry_start(index).and_then(|start| self.data.get(&start)) {
+            Some(e) => e.range.clone(),
+            None    => return false,
+        };
+
+        self.touch(range.start);
+        self.types.set(range.start, range.len(), tag);
+
+        true
+    }
+
+    /// Returns the tag of the entry at `index`, if there's an entry and it's
+    /// tagged.
+    pub fn type_of(&self, index: usize) -> Option<TypeTag> {
+        self.types.get(self.get_entry_start(index)?)
+    }
+
+    /// Returns how many entries are tagged with `tag`.
+    pub fn count_of_type(&self, tag: TypeTag) -> usize {
+        self.types.by_tag.get(&tag).map(|starts| starts.len()).unwrap_or(0)
+    }
+
+    /// Iterate over the entries tagged with `tag`, in order.
+    pub fn iter_of_type(&self, tag: TypeTag) -> impl Iterator<Item=&BumpyEntry<T>> {
+        self.types.by_tag.get(&tag).into_iter().flatten().filter_map(move |start| self.data.get(start))
+    }
+
+    /// Returns how many indexes are covered by entries with each tag.
+    ///
+    /// Untagged entries aren't counted, and tags without any entries are
+    /// left out. This is kept up to date as entries change, so it doesn't
+    /// need to look at any entries.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use bumpy_vector::BumpyVector;
+    ///
+    /// let mut v: BumpyVector<&str> = BumpyVector::new(100);
+    /// v.insert_typed(("hello", 0..6).into(), 1).unwrap();
+    /// v.insert_typed(("main", 10..40).into(), 2).unwrap();
+    /// v.insert_typed(("world", 50..56).into(), 1).unwrap();
+    /// v.insert(("???", 60..70).into()).unwrap();
+    ///
+    /// let coverage = v.coverage_by_type();
+    /// assert_eq!(2, coverage.len());
+    /// assert_eq!(12, coverage[&1]);
+    /// assert_eq!(30, coverage[&2]);
+    /// ```
+    pub fn coverage_by_type(&self) -> HashMap<TypeTag, usize> {
+        self.types.coverage.clone()
+    }
+
+    /// Remove every entry tagged with `tag`.
+    ///
+    /// # Return
+    ///
+    /// Returns the removed entries, in order. Like `remove_range()`, locked
+    /// entries are skipped, and nothing is removed from a read-only vector.
+    pub fn remove_of_type(&mut self, tag: TypeTag) -> Vec<BumpyEntry<T>> {
+        let starts: Vec<usize> = self.types.by_tag.get(&tag).into_iter().flatten().copied().collect();
+
+        starts.into_iter().filter_map(|start| self.remove_exact(start)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use crate::offsets::OffsetMap;
+
+    #[test]
+    fn test_types() {
+        let mut h: BumpyVector<&str> = BumpyVector::new(100);
+        h.insert_typed(("a", 0..10).into(), 1).unwrap();
+        h.insert_typed(("b", 10..20).into(), 2).unwrap();
+        h.insert_typed(("c", 20..30).into(), 1).unwrap();
+        h.insert(("d", 30..40).into()).unwrap();
+        assert!(h.insert_typed(("error", 35..45).into(), 1).is_err());
+
+        assert_eq!(Some(1), h.type_of(25));
+        assert_eq!(None, h.type_of(35));
+        assert_eq!(None, h.type_of(50));
+        assert_eq!(2, h.count_of_type(1));
+        assert_eq!(0, h.count_of_type(3));
+        assert_eq!(vec!["a", "c"], h.iter_of_type(1).map(|e| e.entry).collect::<Vec<_>>());
+        assert_eq!(0, h.iter_of_type(3).count());
+
+        // Re-tagging moves it
+        assert!(h.set_type(35, 1));
+        assert!(h.set_type(5, 2));
+        assert!(!h.set_type(50, 2));
+        assert_eq!(vec!["c", "d"], h.iter_of_type(1).map(|e| e.entry).collect::<Vec<_>>());
+        assert_eq!(vec!["a", "b"], h.iter_of_type(2).map(|e| e.entry).collect::<Vec<_>>());
+
+        // Re-tagging is a change
+        h.track_changes();
+        let generation = h.generation();
+        assert!(h.set_type(15, 1));
+        assert!(h.generation() > generation);
+        assert_eq!(vec!["b"], h.changes_since(generation).modified.iter().map(|e| e.entry).collect::<Vec<_>>());
+
+        h.set_read_only(true);
+        assert!(!h.set_type(15, 2));
+        assert_eq!(Some(1), h.type_of(15));
+        h.set_read_only(false);
+        assert!(h.set_type(15, 2));
+
+        // Moving keeps tags
+        h.apply_offset_map(&OffsetMap::new(&[0..5, 95..100])).unwrap();
+        assert_eq!(vec![15..25, 25..35], h.iter_of_type(1).map(|e| e.range.clone()).collect::<Vec<_>>());
+
+        // Removing forgets them
+        h.lock_range(20..21);
+        let removed: Vec<&str> = h.remove_of_type(1).iter().map(|e| e.entry).collect();
+        assert_eq!(vec!["d"], removed);
+        assert_eq!(1, h.count_of_type(1));
+
+        h.insert(("e", 25..35).into()).unwrap();
+        assert_eq!(None, h.type_of(25));
+
+        h.unlock_range(0..100);
+        h.clear();
+        assert_eq!(0, h.count_of_type(1));
+        assert_eq!(0, h.count_of_type(2));
+    }
+
+    #[test]
+    fn test_coverage_by_type() {
+        let mut h: BumpyVector<&str> = BumpyVector::new(100);
+        h.insert_typed(("a", 0..10).into(), 1).unwrap();
+        h.insert_typed(("b", 10..15).into(), 2).unwrap();
+        h.insert_typed(("c", 20..40).into(), 1).unwrap();
+        h.insert(("d", 40..41).into()).unwrap();
+
+        let expected: HashMap<TypeTag, usize> = vec![(1, 30), (2, 5)].into_iter().collect();
+        assert_eq!(expected, h.coverage_by_type());
+
+        // Re-tagging, removing, and moving keep it up to date
+        h.set_type(40, 2);
+        h.set_type(0, 2);
+        h.remove(25);
+        let expected: HashMap<TypeTag, usize> = vec![(2, 16)].into_iter().collect();
+        assert_eq!(expected, h.coverage_by_type());
+
+        h.apply_offset_map(&OffsetMap::new(&[2..3, 50..60])).unwrap();
+        let expected: HashMap<TypeTag, usize> = vec![(2, 6)].into_iter().collect();
+        assert_eq!(expected, h.coverage_by_type());
+
+        h.clear();
+        assert!(h.coverage_by_type().is_empty());
+    }
+}
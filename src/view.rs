@@ -0,0 +1,164 @@
+//! Hide entries from reads, without removing them.
+//!
+//! A UI often wants to hide some annotations - say, the auto-generated ones -
+//! without actually removing them, or copying everything else into a new
+//! vector. A view filter is a predicate that decides which entries are
+//! visible. The normal read functions ignore it; the `_visible` versions
+//! only return entries it accepts.
+//!
+//! The filter isn't serialized.
+//!
+//! # Example
+//!
+//! ```
+//! use bumpy_vector::BumpyVector;
+//!
+//! let mut v: BumpyVector<&str> = BumpyVector::new(100);
+//! v.insert(("undefined", 0..10).into()).unwrap();
+//! v.insert(("header", 10..20).into()).unwrap();
+//!
+//! v.set_view_filter(|e| e.entry != "undefined");
+//!
+//! let visible: Vec<&str> = v.iter_visible().map(|e| e.entry).collect();
+//! assert_eq!(vec!["header"], visible);
+//!
+//! // Everything is still there
+//! assert_eq!(2, v.len());
+//! assert!(v.get(5).is_some());
+//! assert!(v.get_visible(5).is_none());
+//! ```
+
+use std::fmt;
+use std::ops::Range;
+use std::panic::RefUnwindSafe;
+use std::sync::Arc;
+
+use crate::{BumpyEntry, BumpyVector};
+
+/// A predicate that decides whether an entry is visible.
+///
+/// It has to be `RefUnwindSafe` so that a vector with a filter still is;
+/// that rules out closures that share something like a `Cell`, which a
+/// panic could leave half-changed.
+type Predicate<T> = dyn Fn(&BumpyEntry<T>) -> bool + Send + Sync + RefUnwindSafe;
+
+/// The predicate, if there is one.
+pub(crate) struct ViewFilter<T> {
+    filter: Option<Arc<Predicate<T>>>,
+}
+
+impl<T> ViewFilter<T> {
+    /// Returns `true` if `entry` passes the filter (or there isn't one).
+    fn accepts(&self, entry: &BumpyEntry<T>) -> bool {
+        match &self.filter {
+            Some(f) => f(entry),
+            None => true,
+        }
+    }
+}
+
+impl<T> Default for ViewFilter<T> {
+    fn default() -> Self {
+        ViewFilter { filter: None }
+    }
+}
+
+/// Clones share the same predicate.
+impl<T> Clone for ViewFilter<T> {
+    fn clone(&self) -> Self {
+        ViewFilter { filter: self.filter.clone() }
+    }
+}
+
+impl<T> fmt::Debug for ViewFilter<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.filter {
+            Some(_) => write!(f, "ViewFilter(set)"),
+            None => write!(f, "ViewFilter(none)"),
+        }
+    }
+}
+
+impl<T> BumpyVector<T> {
+    /// Only show entries that `f` returns `true` for, in the `_visible`
+    /// read functions. This replaces any filter that's already set.
+    pub fn set_view_filter<F>(&mut self, f: F)
+    where
+        F: Fn(&BumpyEntry<T>) -> bool + Send + Sync + RefUnwindSafe + 'static
+    {
+        self.view.filter = Some(Arc::new(f));
+    }
+
+    /// Show every entry again.
+    pub fn clear_view_filter(&mut self) {
+        self.view.filter = None;
+    }
+
+    /// Returns `true` if there's a view filter.
+    pub fn has_view_filter(&self) -> bool {
+        self.view.filter.is_some()
+    }
+
+    /// Return the entry at `index`, like `get()`, unless the view filter
+    /// hides it.
+    pub fn get_visible(&self, index: usize) -> Option<&BumpyEntry<T>> {
+        self.get(index).filter(|e| self.view.accepts(e))
+    }
+
+    /// Return the entries within `range`, like `get_range()`, except the
+    /// ones the view filter hides.
+    pub fn get_range_visible(&self, range: Range<usize>) -> Vec<&BumpyEntry<T>> {
+        self.iter_range(range).filter(|e| self.view.accepts(e)).collect()
+    }
+
+    /// Iterate over every entry the view filter doesn't hide, in order.
+    pub fn iter_visible(&self) -> impl DoubleEndedIterator<Item=&BumpyEntry<T>> {
+        self.iter().filter(move |e| self.view.accepts(e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_view_filter() {
+        let mut h: BumpyVector<u32> = BumpyVector::new(100);
+        h.insert((1, 0..10).into()).unwrap();
+        h.insert((2, 10..20).into()).unwrap();
+        h.insert((3, 20..30).into()).unwrap();
+        h.insert((4, 50..60).into()).unwrap();
+
+        // Everything is visible until there's a filter
+        assert!(!h.has_view_filter());
+        assert_eq!(4, h.iter_visible().count());
+
+        h.set_view_filter(|e| e.entry % 2 == 0);
+        assert!(h.has_view_filter());
+        assert_eq!(vec![2, 4], h.iter_visible().map(|e| e.entry).collect::<Vec<_>>());
+        assert_eq!(vec![4, 2], h.iter_visible().rev().map(|e| e.entry).collect::<Vec<_>>());
+        assert_eq!(vec![2], h.get_range_visible(5..25).iter().map(|e| e.entry).collect::<Vec<_>>());
+        assert!(h.get_visible(5).is_none());
+        assert_eq!(2, h.get_visible(15).unwrap().entry);
+
+        // It's applied when reading, so it sees changes
+        h.replace_value(5, 6);
+        assert_eq!(6, h.get_visible(5).unwrap().entry);
+
+        // Clones share it
+        let cloned = h.clone();
+        assert_eq!(3, cloned.iter_visible().count());
+
+        h.set_view_filter(|e| e.range.start >= 20);
+        assert_eq!(vec![3, 4], h.iter_visible().map(|e| e.entry).collect::<Vec<_>>());
+
+        // A panicking filter doesn't stop the vector being used
+        h.set_view_filter(|_| panic!("Filter failed"));
+        assert!(std::panic::catch_unwind(|| h.iter_visible().count()).is_err());
+
+        h.clear_view_filter();
+        assert_eq!(4, h.iter_visible().count());
+        assert_eq!(4, h.get_range(0..100).len());
+    }
+}
@@ -0,0 +1,182 @@
+//! Walk the whole address space with one set of callbacks.
+//!
+//! An exporter (a hex dump, an HTML report, ...) usually wants everything in
+//! order: each entry, each gap between entries, and where each section of
+//! the file starts and ends. Rather than stitching that together from
+//! `iter_range()` and some arithmetic every time, implement `BumpyVisitor`
+//! and hand it to `visit()` or `visit_sections()`.
+//!
+//! Every callback has a default that does nothing, so a visitor only needs
+//! the ones it cares about.
+//!
+//! # Example
+//!
+//! ```
+//! use std::ops::Range;
+//! use bumpy_vector::{BumpyEntry, BumpyVector};
+//! use bumpy_vector::visit::BumpyVisitor;
+//!
+//! #[derive(Default)]
+//! struct Listing(Vec<String>);
+//!
+//! impl BumpyVisitor<&str> for Listing {
+//!     fn entry(&mut self, entry: &BumpyEntry<&str>) {
+//!         self.0.push(format!("{:?} {}", entry.range, entry.entry));
+//!     }
+//!
+//!     fn gap(&mut self, range: Range<usize>) {
+//!         self.0.push(format!("{:?} (empty)", range));
+//!     }
+//! }
+//!
+//! let mut v: BumpyVector<&str> = BumpyVector::new(10);
+//! v.insert(("header", 0..4).into()).unwrap();
+//! v.insert(("footer", 8..10).into()).unwrap();
+//!
+//! let listing = v.visit(Listing::default());
+//! assert_eq!(vec!["0..4 header", "4..8 (empty)", "8..10 footer"], listing.0);
+//! ```
+
+use std::cmp::{max, min};
+use std::ops::Range;
+
+use crate::{BumpyEntry, BumpyVector};
+
+/// Callbacks for `BumpyVector::visit()`; see the module docs.
+///
+/// For each section, `section_start()` is called first, then `entry()` and
+/// `gap()` for everything in the section in order, then `section_end()`.
+pub trait BumpyVisitor<T> {
+    /// A section is starting.
+    fn section_start(&mut self, _section: &Range<usize>) {}
+
+    /// An entry in the section.
+    ///
+    /// Like `get_range()`, an entry that overlaps the section at all is
+    /// visited in its entirety, so an entry that crosses into the next
+    /// section is visited again there.
+    fn entry(&mut self, _entry: &BumpyEntry<T>) {}
+
+    /// A run of empty indexes, between entries or the ends of the section.
+    fn gap(&mut self, _range: Range<usize>) {}
+
+    /// A section has ended.
+    fn section_end(&mut self, _section: &Range<usize>) {}
+}
+
+impl<T> BumpyVector<T> {
+    /// Visit the whole vector, from 0 to `max_size`, as a single section.
+    ///
+    /// # Return
+    ///
+    /// Returns the visitor, so it can be used to collect results.
+    pub fn visit<V>(&self, visitor: V) -> V
+    where
+        V: BumpyVisitor<T>
+    {
+        self.visit_sections(std::slice::from_ref(&(0..self.max_size)), visitor)
+    }
+
+    /// Visit each of `sections`, in the order given.
+    ///
+    /// Anything past `max_size` is left out of a section, but the
+    /// section boundaries are passed along as given.
+    ///
+    /// # Return
+    ///
+    /// Returns the visitor, so it can be used to collect results.
+    pub fn visit_sections<V>(&self, sections: &[Range<usize>], mut visitor: V) -> V
+    where
+        V: BumpyVisitor<T>
+    {
+        for section in sections {
+            visitor.section_start(section);
+
+            let end = min(section.end, self.max_size);
+            let mut position = section.start;
+
+            for e in self.iter_range(section.clone()) {
+                if e.range.start > position {
+                    visitor.gap(position..e.range.start);
+                }
+
+                visitor.entry(e);
+                position = max(position, e.range.end);
+            }
+
+            if position < end {
+                visitor.gap(position..end);
+            }
+
+            visitor.section_end(section);
+        }
+
+        visitor
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    /// Records every callback.
+    #[derive(Default)]
+    struct Recorder(Vec<String>);
+
+    impl BumpyVisitor<&str> for Recorder {
+        fn section_start(&mut self, section: &Range<usize>) {
+            self.0.push(format!("start {:?}", section));
+        }
+
+        fn entry(&mut self, entry: &BumpyEntry<&str>) {
+            self.0.push(format!("entry {} {:?}", entry.entry, entry.range));
+        }
+
+        fn gap(&mut self, range: Range<usize>) {
+            self.0.push(format!("gap {:?}", range));
+        }
+
+        fn section_end(&mut self, section: &Range<usize>) {
+            self.0.push(format!("end {:?}", section));
+        }
+    }
+
+    #[test]
+    fn test_visit() {
+        let mut h: BumpyVector<&str> = BumpyVector::new(10);
+        h.insert(("a", 1..3).into()).unwrap();
+        h.insert(("b", 3..4).into()).unwrap();
+        h.insert(("c", 6..9).into()).unwrap();
+
+        assert_eq!(vec![
+            "start 0..10",
+            "gap 0..1",
+            "entry a 1..3",
+            "entry b 3..4",
+            "gap 4..6",
+            "entry c 6..9",
+            "gap 9..10",
+            "end 0..10",
+        ], h.visit(Recorder::default()).0);
+
+        // Sections can split entries, and go past the end
+        assert_eq!(vec![
+            "start 2..5",
+            "entry a 1..3",
+            "entry b 3..4",
+            "gap 4..5",
+            "end 2..5",
+            "start 7..20",
+            "entry c 6..9",
+            "gap 9..10",
+            "end 7..20",
+            "start 4..4",
+            "end 4..4",
+        ], h.visit_sections(&[2..5, 7..20, 4..4], Recorder::default()).0);
+
+        // Nothing at all
+        let h: BumpyVector<&str> = BumpyVector::new(10);
+        assert_eq!(vec!["start 0..10", "gap 0..10", "end 0..10"], h.visit(Recorder::default()).0);
+    }
+}